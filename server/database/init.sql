@@ -1,7 +1,4 @@
--- 1. Création du type ENUM pour les rôles
-CREATE TYPE user_role AS ENUM ('OWNER', 'ADMIN', 'MEMBER');
-
--- 2. Table des Utilisateurs
+-- 1. Table des Utilisateurs
 CREATE TABLE IF NOT EXISTS users (
     id SERIAL PRIMARY KEY,
     username VARCHAR(50) NOT NULL UNIQUE,
@@ -10,7 +7,7 @@ CREATE TABLE IF NOT EXISTS users (
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
 
--- 3. Table des Serveurs
+-- 2. Table des Serveurs
 CREATE TABLE IF NOT EXISTS servers (
     id SERIAL PRIMARY KEY,
     name VARCHAR(100) NOT NULL,
@@ -20,18 +17,19 @@ CREATE TABLE IF NOT EXISTS servers (
     CONSTRAINT fk_server_owner FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
 );
 
--- 4. Table de liaison Membres <-> Serveurs
+-- 3. Table de liaison Membres <-> Serveurs
 CREATE TABLE IF NOT EXISTS server_members (
     server_id INT NOT NULL,
     user_id INT NOT NULL,
-    role user_role NOT NULL DEFAULT 'MEMBER',
     joined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    timeout_until TIMESTAMP WITH TIME ZONE,
+    is_temporary BOOLEAN NOT NULL DEFAULT FALSE,
     PRIMARY KEY (server_id, user_id),
     CONSTRAINT fk_member_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
     CONSTRAINT fk_member_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
 );
 
--- 5. Table des Canaux (Channels)
+-- 4. Table des Canaux (Channels)
 CREATE TABLE IF NOT EXISTS channels (
     id SERIAL PRIMARY KEY,
     name VARCHAR(100) NOT NULL,
@@ -41,7 +39,7 @@ CREATE TABLE IF NOT EXISTS channels (
     CONSTRAINT fk_channel_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE
 );
 
--- 6. Table des Messages
+-- 5. Table des Messages
 -- Note: Si on passe à MongoDB plus tard, cette table ne sera plus utilisée
 CREATE TABLE IF NOT EXISTS messages (
     id SERIAL PRIMARY KEY,
@@ -51,6 +49,310 @@ CREATE TABLE IF NOT EXISTS messages (
     is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP WITH TIME ZONE,
+    reply_to_id INT,
     CONSTRAINT fk_message_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
-    CONSTRAINT fk_message_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE SET NULL
-);
\ No newline at end of file
+    CONSTRAINT fk_message_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_message_reply_to FOREIGN KEY (reply_to_id) REFERENCES messages(id) ON DELETE SET NULL
+);
+
+-- 6. Historique des modifications des messages
+CREATE TABLE IF NOT EXISTS message_revisions (
+    id SERIAL PRIMARY KEY,
+    message_id INT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_revision_message FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_revisions_message_id ON message_revisions(message_id);
+
+-- 7. Messages privés (1:1 et groupes) hors des serveurs
+CREATE TABLE IF NOT EXISTS dm_conversations (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100),
+    is_group BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS dm_participants (
+    conversation_id INT NOT NULL,
+    user_id INT NOT NULL,
+    joined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (conversation_id, user_id),
+    CONSTRAINT fk_dm_participant_conversation FOREIGN KEY (conversation_id) REFERENCES dm_conversations(id) ON DELETE CASCADE,
+    CONSTRAINT fk_dm_participant_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS direct_messages (
+    id SERIAL PRIMARY KEY,
+    content TEXT NOT NULL,
+    conversation_id INT NOT NULL,
+    author_id INT NOT NULL,
+    is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT fk_direct_message_conversation FOREIGN KEY (conversation_id) REFERENCES dm_conversations(id) ON DELETE CASCADE,
+    CONSTRAINT fk_direct_message_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_dm_participants_user_id ON dm_participants(user_id);
+CREATE INDEX IF NOT EXISTS idx_direct_messages_conversation_id ON direct_messages(conversation_id, created_at DESC);
+
+-- 8. Rôles personnalisés par serveur (permissions en champ de bits)
+-- Le rôle @everyone (is_default) est attribué implicitement à tous les membres
+CREATE TABLE IF NOT EXISTS roles (
+    id SERIAL PRIMARY KEY,
+    server_id INT NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    permissions BIGINT NOT NULL DEFAULT 0,
+    position INT NOT NULL DEFAULT 0,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_role_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS member_roles (
+    server_id INT NOT NULL,
+    user_id INT NOT NULL,
+    role_id INT NOT NULL,
+    PRIMARY KEY (server_id, user_id, role_id),
+    CONSTRAINT fk_member_role_member FOREIGN KEY (server_id, user_id) REFERENCES server_members(server_id, user_id) ON DELETE CASCADE,
+    CONSTRAINT fk_member_role_role FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_roles_server_id ON roles(server_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roles_default ON roles(server_id) WHERE is_default;
+CREATE INDEX IF NOT EXISTS idx_member_roles_role_id ON member_roles(role_id);
+
+-- 9. Surcharges de permissions par canal (rôle ou membre)
+CREATE TABLE IF NOT EXISTS channel_permission_overwrites (
+    id SERIAL PRIMARY KEY,
+    channel_id INT NOT NULL,
+    role_id INT,
+    user_id INT,
+    allow BIGINT NOT NULL DEFAULT 0,
+    deny BIGINT NOT NULL DEFAULT 0,
+    CONSTRAINT fk_overwrite_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    CONSTRAINT fk_overwrite_role FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE,
+    CONSTRAINT fk_overwrite_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT chk_overwrite_target CHECK ((role_id IS NULL) <> (user_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_overwrites_channel_role ON channel_permission_overwrites(channel_id, role_id) WHERE role_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_overwrites_channel_user ON channel_permission_overwrites(channel_id, user_id) WHERE user_id IS NOT NULL;
+
+-- 10. Bannissements (expires_at NULL = définitif)
+CREATE TABLE IF NOT EXISTS server_bans (
+    server_id INT NOT NULL,
+    user_id INT NOT NULL,
+    reason TEXT,
+    banned_by INT,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (server_id, user_id),
+    CONSTRAINT fk_ban_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
+    CONSTRAINT fk_ban_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_ban_banned_by FOREIGN KEY (banned_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- 11. Journal d'audit des actions d'administration
+CREATE TABLE IF NOT EXISTS audit_log (
+    id SERIAL PRIMARY KEY,
+    server_id INT NOT NULL,
+    actor_id INT,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(20),
+    target_id INT,
+    before JSONB,
+    after JSONB,
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_audit_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
+    CONSTRAINT fk_audit_actor FOREIGN KEY (actor_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_server ON audit_log(server_id, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(server_id, action, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(server_id, actor_id, id DESC);
+
+-- 12. Invitations (max_uses / expires_at NULL = illimité)
+CREATE TABLE IF NOT EXISTS invitations (
+    id SERIAL PRIMARY KEY,
+    code VARCHAR(20) UNIQUE NOT NULL,
+    server_id INT NOT NULL,
+    creator_id INT,
+    max_uses INT,
+    uses INT NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    temporary BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_invitation_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
+    CONSTRAINT fk_invitation_creator FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE SET NULL,
+    CONSTRAINT chk_invitation_max_uses CHECK (max_uses IS NULL OR max_uses > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invitations_server ON invitations(server_id);
+
+-- 13. Fils de discussion (issus d'un message d'un canal)
+CREATE TABLE IF NOT EXISTS threads (
+    id SERIAL PRIMARY KEY,
+    channel_id INT NOT NULL,
+    root_message_id INT NOT NULL UNIQUE,
+    name VARCHAR(100) NOT NULL,
+    creator_id INT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_thread_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    CONSTRAINT fk_thread_root_message FOREIGN KEY (root_message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_thread_creator FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_threads_channel ON threads(channel_id);
+
+CREATE TABLE IF NOT EXISTS thread_members (
+    thread_id INT NOT NULL,
+    user_id INT NOT NULL,
+    joined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (thread_id, user_id),
+    CONSTRAINT fk_thread_member_thread FOREIGN KEY (thread_id) REFERENCES threads(id) ON DELETE CASCADE,
+    CONSTRAINT fk_thread_member_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Messages d'un fil (NULL = message du canal)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS thread_id INT;
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS fk_message_thread;
+ALTER TABLE messages ADD CONSTRAINT fk_message_thread FOREIGN KEY (thread_id) REFERENCES threads(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_messages_thread ON messages(thread_id, created_at) WHERE thread_id IS NOT NULL;
+
+-- 14. Réactions aux messages
+CREATE TABLE IF NOT EXISTS message_reactions (
+    message_id INT NOT NULL,
+    user_id INT NOT NULL,
+    emoji VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, user_id, emoji),
+    CONSTRAINT fk_reaction_message FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_reaction_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_reactions_emoji ON message_reactions(message_id, emoji);
+
+-- 15. Mentions (une ligne par utilisateur notifié)
+CREATE TABLE IF NOT EXISTS message_mentions (
+    message_id INT NOT NULL,
+    user_id INT NOT NULL,
+    kind VARCHAR(10) NOT NULL,
+    seen_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, user_id),
+    CONSTRAINT fk_mention_message FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_mention_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_mentions_unseen ON message_mentions(user_id, message_id) WHERE seen_at IS NULL;
+
+-- 16. États de lecture (dernier message lu par canal)
+CREATE TABLE IF NOT EXISTS channel_read_states (
+    user_id INT NOT NULL,
+    channel_id INT NOT NULL,
+    last_read_message_id INT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, channel_id),
+    CONSTRAINT fk_read_state_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_read_state_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
+
+-- 17. Pagination de l'historique par curseur (id croissants par conversation)
+CREATE INDEX IF NOT EXISTS idx_messages_channel ON messages(channel_id, id);
+DROP INDEX IF EXISTS idx_messages_thread;
+CREATE INDEX IF NOT EXISTS idx_messages_thread ON messages(thread_id, id) WHERE thread_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_direct_messages_conversation ON direct_messages(conversation_id, id);
+DROP INDEX IF EXISTS idx_direct_messages_conversation_id;
+
+-- 18. Recherche plein texte (configuration 'simple' : les messages mélangent les langues)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (to_tsvector('simple', content)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_messages_search ON messages USING GIN (search_vector);
+
+-- 19. Pièces jointes (message_id NULL tant que le message n'est pas envoyé)
+CREATE TABLE IF NOT EXISTS attachments (
+    id SERIAL PRIMARY KEY,
+    message_id INT,
+    channel_id INT NOT NULL,
+    uploader_id INT,
+    filename VARCHAR(255) NOT NULL,
+    content_type VARCHAR(127) NOT NULL,
+    size BIGINT NOT NULL,
+    width INT,
+    height INT,
+    checksum CHAR(64) NOT NULL,
+    storage_key VARCHAR(255) NOT NULL,
+    thumbnail_key VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_attachment_message FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_attachment_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    CONSTRAINT fk_attachment_uploader FOREIGN KEY (uploader_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_message ON attachments(message_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_pending ON attachments(created_at) WHERE message_id IS NULL;
+
+-- 20. Messages épinglés (pinned_at NULL = non épinglé)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS pinned_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS pinned_by INT;
+
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS fk_message_pinned_by;
+ALTER TABLE messages ADD CONSTRAINT fk_message_pinned_by
+    FOREIGN KEY (pinned_by) REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_messages_pinned ON messages(channel_id, pinned_at DESC)
+    WHERE pinned_at IS NOT NULL;
+
+-- 21. Présence : statut choisi (hors "offline", déduit des connexions) et statut personnalisé
+ALTER TABLE users ADD COLUMN IF NOT EXISTS presence_status VARCHAR(16) NOT NULL DEFAULT 'online';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS custom_status VARCHAR(128);
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS chk_user_presence_status;
+ALTER TABLE users ADD CONSTRAINT chk_user_presence_status
+    CHECK (presence_status IN ('online', 'idle', 'dnd', 'invisible'));
+
+-- 22. Bus d'événements Postgres (EVENT_BUS=postgres) : messages trop gros pour NOTIFY,
+--     notifiés par leur identifiant et purgés après quelques minutes
+CREATE TABLE IF NOT EXISTS event_bus_payloads (
+    id BIGSERIAL PRIMARY KEY,
+    payload TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_bus_payloads_created ON event_bus_payloads(created_at);
+
+-- 23. Sessions de connexion : refresh tokens (hashés) révocables, un par appareil
+CREATE TABLE IF NOT EXISTS sessions (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL,
+    refresh_token_hash CHAR(64) NOT NULL UNIQUE,
+    previous_token_hash CHAR(64),
+    user_agent TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT fk_session_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
+CREATE INDEX IF NOT EXISTS idx_sessions_previous_token ON sessions(previous_token_hash);
+
+-- 24. Profil utilisateur : nom affiché, avatar (pièce jointe image), bio, pronoms
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name VARCHAR(32);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_attachment_id INT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS bio VARCHAR(190);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pronouns VARCHAR(40);
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS fk_user_avatar;
+ALTER TABLE users ADD CONSTRAINT fk_user_avatar
+    FOREIGN KEY (avatar_attachment_id) REFERENCES attachments(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_avatar ON users(avatar_attachment_id)
+    WHERE avatar_attachment_id IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_direct_messages_conversation_id;
+DROP INDEX IF EXISTS idx_dm_participants_user_id;
+
+DROP TABLE IF EXISTS direct_messages;
+DROP TABLE IF EXISTS dm_participants;
+DROP TABLE IF EXISTS dm_conversations;
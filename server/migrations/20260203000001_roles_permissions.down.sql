@@ -0,0 +1,19 @@
+-- Recréer l'ENUM user_role et la colonne role
+CREATE TYPE user_role AS ENUM ('OWNER', 'ADMIN', 'MEMBER');
+
+ALTER TABLE server_members ADD COLUMN role user_role NOT NULL DEFAULT 'MEMBER';
+
+-- Les membres ayant un rôle personnalisé redeviennent ADMIN
+UPDATE server_members sm SET role = 'ADMIN'
+WHERE EXISTS (SELECT 1 FROM member_roles mr WHERE mr.server_id = sm.server_id AND mr.user_id = sm.user_id);
+
+UPDATE server_members sm SET role = 'OWNER'
+FROM servers s
+WHERE s.id = sm.server_id AND s.owner_id = sm.user_id;
+
+DROP INDEX IF EXISTS idx_member_roles_role_id;
+DROP INDEX IF EXISTS idx_roles_default;
+DROP INDEX IF EXISTS idx_roles_server_id;
+
+DROP TABLE IF EXISTS member_roles;
+DROP TABLE IF EXISTS roles;
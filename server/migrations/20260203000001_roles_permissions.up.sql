@@ -0,0 +1,52 @@
+-- ==============================================
+-- Rôles personnalisés par serveur avec champ de bits de permissions
+-- Remplace l'ENUM fixe user_role (OWNER / ADMIN / MEMBER)
+-- ==============================================
+
+-- Table des Rôles
+-- `is_default` désigne le rôle @everyone, attribué implicitement à tous les membres
+-- `position` ordonne la hiérarchie : un rôle ne peut gérer que les rôles strictement inférieurs
+CREATE TABLE IF NOT EXISTS roles (
+    id SERIAL PRIMARY KEY,
+    server_id INT NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    permissions BIGINT NOT NULL DEFAULT 0,
+    position INT NOT NULL DEFAULT 0,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_role_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE
+);
+
+-- Un membre peut avoir plusieurs rôles
+CREATE TABLE IF NOT EXISTS member_roles (
+    server_id INT NOT NULL,
+    user_id INT NOT NULL,
+    role_id INT NOT NULL,
+    PRIMARY KEY (server_id, user_id, role_id),
+    CONSTRAINT fk_member_role_member FOREIGN KEY (server_id, user_id) REFERENCES server_members(server_id, user_id) ON DELETE CASCADE,
+    CONSTRAINT fk_member_role_role FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_roles_server_id ON roles(server_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roles_default ON roles(server_id) WHERE is_default;
+CREATE INDEX IF NOT EXISTS idx_member_roles_role_id ON member_roles(role_id);
+
+-- Rôle @everyone pour chaque serveur existant
+-- VIEW_CHANNELS | SEND_MESSAGES | READ_MESSAGE_HISTORY
+INSERT INTO roles (server_id, name, permissions, position, is_default)
+SELECT id, '@everyone', 7, 0, TRUE FROM servers;
+
+-- Les anciens ADMIN reçoivent un rôle "Admin" équivalent
+-- @everyone | MENTION_EVERYONE | MANAGE_MESSAGES | MANAGE_CHANNELS | MANAGE_SERVER | KICK_MEMBERS | BAN_MEMBERS
+INSERT INTO roles (server_id, name, permissions, position)
+SELECT DISTINCT server_id, 'Admin', 895, 1 FROM server_members WHERE role = 'ADMIN';
+
+INSERT INTO member_roles (server_id, user_id, role_id)
+SELECT sm.server_id, sm.user_id, r.id
+FROM server_members sm
+INNER JOIN roles r ON r.server_id = sm.server_id AND r.name = 'Admin' AND r.is_default = FALSE
+WHERE sm.role = 'ADMIN';
+
+-- Le propriétaire est désormais déterminé uniquement par servers.owner_id
+ALTER TABLE server_members DROP COLUMN IF EXISTS role;
+DROP TYPE IF EXISTS user_role;
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_overwrites_channel_user;
+DROP INDEX IF EXISTS idx_overwrites_channel_role;
+
+DROP TABLE IF EXISTS channel_permission_overwrites;
@@ -0,0 +1,22 @@
+-- ==============================================
+-- Surcharges de permissions par canal (rôle ou membre)
+-- Permettent les canaux privés et les canaux d'annonces en lecture seule
+-- ==============================================
+
+-- Chaque surcharge cible soit un rôle, soit un membre
+-- `deny` retire des permissions, `allow` en ajoute (appliqués dans cet ordre)
+CREATE TABLE IF NOT EXISTS channel_permission_overwrites (
+    id SERIAL PRIMARY KEY,
+    channel_id INT NOT NULL,
+    role_id INT,
+    user_id INT,
+    allow BIGINT NOT NULL DEFAULT 0,
+    deny BIGINT NOT NULL DEFAULT 0,
+    CONSTRAINT fk_overwrite_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    CONSTRAINT fk_overwrite_role FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE,
+    CONSTRAINT fk_overwrite_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT chk_overwrite_target CHECK ((role_id IS NULL) <> (user_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_overwrites_channel_role ON channel_permission_overwrites(channel_id, role_id) WHERE role_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_overwrites_channel_user ON channel_permission_overwrites(channel_id, user_id) WHERE user_id IS NOT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE server_members DROP COLUMN IF EXISTS timeout_until;
+
+DROP TABLE IF EXISTS server_bans;
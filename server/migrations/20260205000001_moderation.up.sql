@@ -0,0 +1,20 @@
+-- ==============================================
+-- Modération : bannissements persistants et exclusions temporaires
+-- ==============================================
+
+-- Liste des bannis d'un serveur (expires_at NULL = définitif)
+CREATE TABLE IF NOT EXISTS server_bans (
+    server_id INT NOT NULL,
+    user_id INT NOT NULL,
+    reason TEXT,
+    banned_by INT,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (server_id, user_id),
+    CONSTRAINT fk_ban_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
+    CONSTRAINT fk_ban_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_ban_banned_by FOREIGN KEY (banned_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- Un membre exclu temporairement ne peut plus envoyer de messages jusqu'à cette date
+ALTER TABLE server_members ADD COLUMN IF NOT EXISTS timeout_until TIMESTAMP WITH TIME ZONE;
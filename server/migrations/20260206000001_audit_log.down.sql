@@ -0,0 +1,3 @@
+UPDATE roles SET permissions = permissions & ~2048::bigint;
+
+DROP TABLE IF EXISTS audit_log;
@@ -0,0 +1,26 @@
+-- ==============================================
+-- Journal d'audit des actions d'administration
+-- ==============================================
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id SERIAL PRIMARY KEY,
+    server_id INT NOT NULL,
+    actor_id INT,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(20),
+    target_id INT,
+    before JSONB,
+    after JSONB,
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_audit_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
+    CONSTRAINT fk_audit_actor FOREIGN KEY (actor_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- Pagination par curseur (id décroissant) et filtres
+CREATE INDEX IF NOT EXISTS idx_audit_log_server ON audit_log(server_id, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(server_id, action, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(server_id, actor_id, id DESC);
+
+-- VIEW_AUDIT_LOG (2048) pour les rôles qui gèrent déjà le serveur (MANAGE_SERVER = 64)
+UPDATE roles SET permissions = permissions | 2048 WHERE permissions & 64 <> 0;
@@ -0,0 +1,5 @@
+UPDATE roles SET permissions = permissions & ~4096::bigint;
+
+ALTER TABLE server_members DROP COLUMN IF EXISTS is_temporary;
+
+DROP TABLE IF EXISTS invitations;
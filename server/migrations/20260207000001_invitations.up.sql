@@ -0,0 +1,31 @@
+-- ==============================================
+-- Invitations multiples : expiration, nombre d'utilisations, révocation
+-- ==============================================
+
+CREATE TABLE IF NOT EXISTS invitations (
+    id SERIAL PRIMARY KEY,
+    code VARCHAR(20) UNIQUE NOT NULL,
+    server_id INT NOT NULL,
+    creator_id INT,
+    max_uses INT,
+    uses INT NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    -- Les membres entrés par cette invitation sont retirés à leur déconnexion s'ils n'ont reçu aucun rôle
+    temporary BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_invitation_server FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE,
+    CONSTRAINT fk_invitation_creator FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE SET NULL,
+    CONSTRAINT chk_invitation_max_uses CHECK (max_uses IS NULL OR max_uses > 0)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invitations_server ON invitations(server_id);
+
+-- Le code historique de chaque serveur devient son invitation permanente initiale
+INSERT INTO invitations (code, server_id, creator_id, created_at)
+SELECT invitation_code, id, owner_id, created_at FROM servers
+ON CONFLICT (code) DO NOTHING;
+
+ALTER TABLE server_members ADD COLUMN IF NOT EXISTS is_temporary BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- CREATE_INVITE (4096) fait partie des permissions par défaut de @everyone
+UPDATE roles SET permissions = permissions | 4096 WHERE is_default = true;
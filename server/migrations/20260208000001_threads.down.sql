@@ -0,0 +1,6 @@
+ALTER TABLE messages DROP COLUMN IF EXISTS thread_id;
+
+DROP TABLE IF EXISTS thread_members;
+DROP TABLE IF EXISTS threads;
+
+ALTER TABLE messages DROP COLUMN IF EXISTS reply_to_id;
@@ -0,0 +1,40 @@
+-- ==============================================
+-- Réponses et fils de discussion
+-- ==============================================
+
+-- Réponse à un message précis (conservée comme simple message si l'original disparaît)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS reply_to_id INT;
+ALTER TABLE messages ADD CONSTRAINT fk_message_reply_to
+    FOREIGN KEY (reply_to_id) REFERENCES messages(id) ON DELETE SET NULL;
+
+-- Un fil naît d'un message d'un canal et hérite des permissions de ce canal
+CREATE TABLE IF NOT EXISTS threads (
+    id SERIAL PRIMARY KEY,
+    channel_id INT NOT NULL,
+    root_message_id INT NOT NULL UNIQUE,
+    name VARCHAR(100) NOT NULL,
+    creator_id INT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_thread_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE,
+    CONSTRAINT fk_thread_root_message FOREIGN KEY (root_message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_thread_creator FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_threads_channel ON threads(channel_id);
+
+-- Participants d'un fil
+CREATE TABLE IF NOT EXISTS thread_members (
+    thread_id INT NOT NULL,
+    user_id INT NOT NULL,
+    joined_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (thread_id, user_id),
+    CONSTRAINT fk_thread_member_thread FOREIGN KEY (thread_id) REFERENCES threads(id) ON DELETE CASCADE,
+    CONSTRAINT fk_thread_member_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Messages d'un fil (NULL = message du canal)
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS thread_id INT;
+ALTER TABLE messages ADD CONSTRAINT fk_message_thread
+    FOREIGN KEY (thread_id) REFERENCES threads(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_messages_thread ON messages(thread_id, created_at) WHERE thread_id IS NOT NULL;
@@ -0,0 +1,19 @@
+-- ==============================================
+-- Mentions
+-- ==============================================
+
+-- Une ligne par utilisateur notifié ; kind garde la raison la plus directe
+-- (user, role, here, everyone)
+CREATE TABLE IF NOT EXISTS message_mentions (
+    message_id INT NOT NULL,
+    user_id INT NOT NULL,
+    kind VARCHAR(10) NOT NULL,
+    seen_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (message_id, user_id),
+    CONSTRAINT fk_mention_message FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE,
+    CONSTRAINT fk_mention_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Boîte de réception : mentions non vues d'un utilisateur
+CREATE INDEX IF NOT EXISTS idx_message_mentions_unseen ON message_mentions(user_id, message_id) WHERE seen_at IS NULL;
@@ -0,0 +1,14 @@
+-- ==============================================
+-- États de lecture des canaux
+-- ==============================================
+
+-- Dernier message lu par un utilisateur dans un canal (les identifiants sont croissants)
+CREATE TABLE IF NOT EXISTS channel_read_states (
+    user_id INT NOT NULL,
+    channel_id INT NOT NULL,
+    last_read_message_id INT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, channel_id),
+    CONSTRAINT fk_read_state_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_read_state_channel FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE INDEX IF NOT EXISTS idx_direct_messages_conversation_id ON direct_messages(conversation_id, created_at DESC);
+DROP INDEX IF EXISTS idx_direct_messages_conversation;
+
+DROP INDEX IF EXISTS idx_messages_thread;
+CREATE INDEX IF NOT EXISTS idx_messages_thread ON messages(thread_id, created_at) WHERE thread_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_messages_channel_id ON messages(channel_id);
+DROP INDEX IF EXISTS idx_messages_channel;
@@ -0,0 +1,13 @@
+-- ==============================================
+-- Pagination de l'historique par curseur
+-- ==============================================
+
+-- L'historique est parcouru par id (before / after / around), conversation par conversation
+CREATE INDEX IF NOT EXISTS idx_messages_channel ON messages(channel_id, id);
+DROP INDEX IF EXISTS idx_messages_channel_id;
+
+DROP INDEX IF EXISTS idx_messages_thread;
+CREATE INDEX IF NOT EXISTS idx_messages_thread ON messages(thread_id, id) WHERE thread_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_direct_messages_conversation ON direct_messages(conversation_id, id);
+DROP INDEX IF EXISTS idx_direct_messages_conversation_id;
@@ -0,0 +1,9 @@
+-- ==============================================
+-- Recherche plein texte dans les messages
+-- ==============================================
+
+-- Configuration 'simple' : pas de racinisation, les messages mélangent les langues
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (to_tsvector('simple', content)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_messages_search ON messages USING GIN (search_vector);
@@ -0,0 +1,13 @@
+-- ==============================================
+-- Messages épinglés dans les canaux
+-- ==============================================
+
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS pinned_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS pinned_by INT;
+
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS fk_message_pinned_by;
+ALTER TABLE messages ADD CONSTRAINT fk_message_pinned_by
+    FOREIGN KEY (pinned_by) REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_messages_pinned ON messages(channel_id, pinned_at DESC)
+    WHERE pinned_at IS NOT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE users DROP CONSTRAINT IF EXISTS chk_user_presence_status;
+ALTER TABLE users DROP COLUMN IF EXISTS custom_status;
+ALTER TABLE users DROP COLUMN IF EXISTS presence_status;
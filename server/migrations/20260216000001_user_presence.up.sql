@@ -0,0 +1,10 @@
+-- ==============================================
+-- Statut de présence choisi et statut personnalisé
+-- ==============================================
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS presence_status VARCHAR(16) NOT NULL DEFAULT 'online';
+ALTER TABLE users ADD COLUMN IF NOT EXISTS custom_status VARCHAR(128);
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS chk_user_presence_status;
+ALTER TABLE users ADD CONSTRAINT chk_user_presence_status
+    CHECK (presence_status IN ('online', 'idle', 'dnd', 'invisible'));
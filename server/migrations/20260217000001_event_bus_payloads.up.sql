@@ -0,0 +1,11 @@
+-- ==============================================
+-- Bus d'événements Postgres : messages trop gros pour NOTIFY
+-- ==============================================
+
+CREATE TABLE IF NOT EXISTS event_bus_payloads (
+    id BIGSERIAL PRIMARY KEY,
+    payload TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_bus_payloads_created ON event_bus_payloads(created_at);
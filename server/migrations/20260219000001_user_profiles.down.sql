@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_users_avatar;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS fk_user_avatar;
+ALTER TABLE users DROP COLUMN IF EXISTS pronouns;
+ALTER TABLE users DROP COLUMN IF EXISTS bio;
+ALTER TABLE users DROP COLUMN IF EXISTS avatar_attachment_id;
+ALTER TABLE users DROP COLUMN IF EXISTS display_name;
@@ -0,0 +1,16 @@
+-- ==============================================
+-- Profil utilisateur : nom affiché, avatar, bio, pronoms
+-- ==============================================
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name VARCHAR(32);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_attachment_id INT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS bio VARCHAR(190);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pronouns VARCHAR(40);
+
+-- L'avatar est une image envoyée par l'utilisateur en pièce jointe
+ALTER TABLE users DROP CONSTRAINT IF EXISTS fk_user_avatar;
+ALTER TABLE users ADD CONSTRAINT fk_user_avatar
+    FOREIGN KEY (avatar_attachment_id) REFERENCES attachments(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_avatar ON users(avatar_attachment_id)
+    WHERE avatar_attachment_id IS NOT NULL;
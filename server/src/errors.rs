@@ -25,6 +25,9 @@ pub enum AppError {
     #[error("Non autorisé")]
     Unauthorized,
     
+    #[error("Mot de passe incorrect")]
+    IncorrectPassword,
+    
     // Erreurs de permissions
     #[error("Permission refusée")]
     Forbidden,
@@ -32,8 +35,17 @@ pub enum AppError {
     #[error("Vous devez être propriétaire pour effectuer cette action")]
     OwnerOnly,
     
-    #[error("Vous devez être admin ou propriétaire pour effectuer cette action")]
-    AdminOnly,
+    #[error("Permission manquante: {0}")]
+    MissingPermission(String),
+    
+    #[error("Vous ne pouvez pas gérer un rôle ou un membre de rang égal ou supérieur au vôtre")]
+    RoleHierarchy,
+    
+    #[error("Vous êtes banni de ce serveur")]
+    Banned,
+    
+    #[error("Vous êtes exclu temporairement de ce serveur jusqu'au {0}")]
+    MemberTimedOut(String),
     
     // Erreurs de ressources
     #[error("Utilisateur non trouvé")]
@@ -48,9 +60,39 @@ pub enum AppError {
     #[error("Message non trouvé")]
     MessageNotFound,
     
+    #[error("Conversation non trouvée")]
+    ConversationNotFound,
+    
+    #[error("Rôle non trouvé")]
+    RoleNotFound,
+    
+    #[error("Surcharge de permissions non trouvée")]
+    OverwriteNotFound,
+    
+    #[error("Bannissement non trouvé")]
+    BanNotFound,
+    
+    #[error("Fil de discussion non trouvé")]
+    ThreadNotFound,
+    
+    #[error("Ce message possède déjà un fil de discussion")]
+    ThreadAlreadyExists,
+    
+    #[error("Pièce jointe non trouvée")]
+    AttachmentNotFound,
+    
+    #[error("Session non trouvée")]
+    SessionNotFound,
+    
+    #[error("Fichier trop volumineux ({0} Mo maximum)")]
+    AttachmentTooLarge(u64),
+    
     #[error("Code d'invitation invalide")]
     InvalidInvitationCode,
     
+    #[error("Cette invitation a expiré ou atteint son nombre maximal d'utilisations")]
+    InvitationExpired,
+    
     #[error("Vous êtes déjà membre de ce serveur")]
     AlreadyMember,
     
@@ -65,6 +107,10 @@ pub enum AppError {
     #[error("Erreur de base de données: {0}")]
     DatabaseError(String),
     
+    // Erreurs de stockage des fichiers
+    #[error("Erreur de stockage: {0}")]
+    StorageError(String),
+    
     // Erreurs internes
     #[error("Erreur interne du serveur")]
     InternalServerError,
@@ -80,37 +126,64 @@ impl From<sqlx::Error> for AppError {
     }
 }
 
-/// Conversion de AppError en réponse HTTP
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::UsernameTaken | AppError::EmailTaken | AppError::AlreadyMember => {
-                (StatusCode::CONFLICT, self.to_string())
-            }
-            AppError::InvalidCredentials | AppError::InvalidToken => {
-                (StatusCode::UNAUTHORIZED, self.to_string())
-            }
-            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
-            AppError::Forbidden | AppError::OwnerOnly | AppError::AdminOnly => {
-                (StatusCode::FORBIDDEN, self.to_string())
+impl AppError {
+    /// Code HTTP associé à l'erreur
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::UsernameTaken
+            | AppError::EmailTaken
+            | AppError::AlreadyMember
+            | AppError::ThreadAlreadyExists => StatusCode::CONFLICT,
+            AppError::InvalidCredentials | AppError::InvalidToken | AppError::Unauthorized => {
+                StatusCode::UNAUTHORIZED
             }
+            AppError::Forbidden
+            | AppError::IncorrectPassword
+            | AppError::OwnerOnly
+            | AppError::MissingPermission(_)
+            | AppError::RoleHierarchy
+            | AppError::Banned
+            | AppError::MemberTimedOut(_) => StatusCode::FORBIDDEN,
             AppError::UserNotFound
             | AppError::ServerNotFound
             | AppError::ChannelNotFound
             | AppError::MessageNotFound
-            | AppError::InvalidInvitationCode => (StatusCode::NOT_FOUND, self.to_string()),
-            AppError::ValidationError(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::DatabaseError(_) | AppError::InternalServerError => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Erreur interne du serveur".to_string())
+            | AppError::ConversationNotFound
+            | AppError::RoleNotFound
+            | AppError::OverwriteNotFound
+            | AppError::BanNotFound
+            | AppError::ThreadNotFound
+            | AppError::AttachmentNotFound
+            | AppError::SessionNotFound
+            | AppError::InvalidInvitationCode => StatusCode::NOT_FOUND,
+            AppError::InvitationExpired => StatusCode::GONE,
+            AppError::AttachmentTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::ValidationError(_) | AppError::OwnerCannotLeave => StatusCode::BAD_REQUEST,
+            AppError::DatabaseError(_) | AppError::StorageError(_) | AppError::InternalServerError => {
+                StatusCode::INTERNAL_SERVER_ERROR
             }
-            AppError::OwnerCannotLeave => (StatusCode::BAD_REQUEST, self.to_string()),
-        };
+        }
+    }
 
+    /// Message présentable au client (les erreurs internes ne sont pas détaillées)
+    pub fn client_message(&self) -> String {
+        match self {
+            AppError::DatabaseError(_) | AppError::StorageError(_) | AppError::InternalServerError => {
+                "Erreur interne du serveur".to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Conversion de AppError en réponse HTTP
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
         let body = Json(json!({
-            "error": error_message,
+            "error": self.client_message(),
         }));
 
-        (status, body).into_response()
+        (self.status_code(), body).into_response()
     }
 }
 
@@ -0,0 +1,135 @@
+use axum::{
+    extract::{multipart::MultipartError, Multipart, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use crate::errors::{AppError, AppResult};
+use crate::handlers::middleware::AuthUser;
+use crate::models::{Attachment, AttachmentContent, UploadedFile};
+use crate::state::AppState;
+
+/// Envoyer des fichiers dans un canal (POST /channels/:channel_id/attachments)
+///
+/// Corps `multipart/form-data` : chaque champ portant un nom de fichier est une pièce
+/// jointe. Les pièces jointes retournées sont ensuite référencées dans `attachment_ids`
+/// à la création du message.
+pub async fn upload_attachments(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<i32>,
+    mut multipart: Multipart,
+) -> AppResult<(StatusCode, Json<Vec<Attachment>>)> {
+    let service = &app_state.attachment_service;
+    let multipart_error = |error: MultipartError| match error.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => service.too_large(),
+        _ => AppError::ValidationError(error.body_text()),
+    };
+
+    let mut files = Vec::new();
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        // Les champs texte éventuels sont ignorés
+        let Some(filename) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+        let content_type = field.content_type().map(str::to_string);
+
+        // Lecture par morceaux pour refuser au plus tôt un fichier trop volumineux
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+            if data.len() + chunk.len() > service.max_size() {
+                return Err(service.too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        files.push(UploadedFile {
+            filename,
+            content_type,
+            data,
+        });
+    }
+
+    let attachments = service
+        .upload(channel_id, files, auth_user.user_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(attachments)))
+}
+
+/// Télécharger une pièce jointe (GET /attachments/:id)
+pub async fn download_attachment(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(attachment_id): Path<i32>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let content = app_state.attachment_service
+        .download(attachment_id, auth_user.user_id, false)
+        .await?;
+    let etag = format!("\"{}\"", content.attachment.checksum);
+    Ok(file_response(content, etag, &headers))
+}
+
+/// Télécharger la miniature d'une image (GET /attachments/:id/thumbnail)
+pub async fn download_thumbnail(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(attachment_id): Path<i32>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let content = app_state.attachment_service
+        .download(attachment_id, auth_user.user_id, true)
+        .await?;
+    let etag = format!("\"{}-thumb\"", content.attachment.checksum);
+    Ok(file_response(content, etag, &headers))
+}
+
+/// Réponse binaire ; seules les images reconnues sont affichées directement
+fn file_response(content: AttachmentContent, etag: String, headers: &HeaderMap) -> Response {
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    let disposition = if content.attachment.width.is_some() {
+        "inline"
+    } else {
+        "attachment"
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, content.content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "{}; filename*=UTF-8''{}",
+                    disposition,
+                    encode_filename(&content.attachment.filename)
+                ),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+            (header::ETAG, etag),
+        ],
+        content.data,
+    )
+        .into_response()
+}
+
+/// Encodage RFC 5987 du nom de fichier
+fn encode_filename(filename: &str) -> String {
+    filename
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => (byte as char).to_string(),
+            b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,21 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{AuditLogPage, AuditLogQuery};
+use crate::state::AppState;
+
+/// Consulter le journal d'audit d'un serveur (GET /servers/:server_id/audit-log)
+pub async fn get_audit_log(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+    Query(query): Query<AuditLogQuery>,
+) -> AppResult<Json<AuditLogPage>> {
+    let page = app_state.audit_service
+        .get_audit_log(server_id, query, auth_user.user_id)
+        .await?;
+    Ok(Json(page))
+}
@@ -1,27 +1,76 @@
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
 use crate::errors::AppResult;
 use crate::handlers::middleware::AuthUser;
-use crate::models::{AuthResponse, CreateUserDto, LoginDto, User};
+use crate::models::{AuthResponse, CreateUserDto, LoginDto, RefreshTokenDto, SessionInfo, User};
 use crate::state::AppState;
 
 /// Handler pour l'inscription (POST /auth/signup)
 pub async fn signup(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     Json(dto): Json<CreateUserDto>,
 ) -> AppResult<(StatusCode, Json<AuthResponse>)> {
-    let response = app_state.auth_service.register(dto).await?;
+    let response = app_state.auth_service.register(dto, user_agent(&headers)).await?;
     Ok((StatusCode::CREATED, Json(response)))
 }
 
 /// Handler pour la connexion (POST /auth/login)
 pub async fn login(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     Json(dto): Json<LoginDto>,
 ) -> AppResult<Json<AuthResponse>> {
-    let response = app_state.auth_service.login(dto).await?;
+    let response = app_state.auth_service.login(dto, user_agent(&headers)).await?;
     Ok(Json(response))
 }
 
+/// Handler pour renouveler les tokens d'une session (POST /auth/refresh)
+pub async fn refresh(
+    State(app_state): State<AppState>,
+    Json(dto): Json<RefreshTokenDto>,
+) -> AppResult<Json<AuthResponse>> {
+    let response = app_state.auth_service.refresh(&dto.refresh_token).await?;
+    Ok(Json(response))
+}
+
+/// Handler pour se déconnecter : révoque la session du token (POST /auth/logout)
+pub async fn logout(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<StatusCode> {
+    app_state.auth_service
+        .revoke_session(auth_user.user_id, auth_user.session_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler pour lister les sessions actives (GET /auth/sessions)
+pub async fn list_sessions(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<SessionInfo>>> {
+    let sessions = app_state.auth_service
+        .list_sessions(auth_user.user_id, auth_user.session_id)
+        .await?;
+    Ok(Json(sessions))
+}
+
+/// Handler pour révoquer une session (DELETE /auth/sessions/:session_id)
+pub async fn revoke_session(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(session_id): Path<i32>,
+) -> AppResult<StatusCode> {
+    app_state.auth_service
+        .revoke_session(auth_user.user_id, session_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// Handler pour récupérer l'utilisateur actuel (GET /auth/me)
 pub async fn get_me(
     State(app_state): State<AppState>,
@@ -30,3 +79,11 @@ pub async fn get_me(
     let user = app_state.auth_service.get_current_user(auth_user.user_id).await?;
     Ok(Json(user))
 }
+
+/// Appareil à l'origine de la connexion, affiché dans la liste des sessions
+fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(512).collect())
+}
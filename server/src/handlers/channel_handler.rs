@@ -1,16 +1,23 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
+    Extension,
     Json,
 };
+use std::sync::Arc;
 use crate::errors::AppResult;
 use crate::handlers::middleware::AuthUser;
-use crate::models::{Channel, CreateChannelDto, UpdateChannelDto};
+use crate::models::{
+    AckChannelDto, Channel, ChannelReadState, ChannelWithReadState, CreateChannelDto,
+    PermissionOverwrite, PermissionOverwriteDto, UpdateChannelDto,
+};
 use crate::state::AppState;
+use crate::ws::{Hub, SocketEvent};
 
 /// Créer un canal (POST /servers/:server_id/channels)
 pub async fn create_channel(
     State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
     auth_user: AuthUser,
     Path(server_id): Path<i32>,
     Json(dto): Json<CreateChannelDto>,
@@ -18,21 +25,61 @@ pub async fn create_channel(
     let channel = app_state.channel_service
         .create_channel(server_id, dto, auth_user.user_id)
         .await?;
+
+    // Abonner les sockets qui voient le nouveau canal, puis les prévenir
+    hub.sync_channel_rooms(server_id).await;
+
+    let event = SocketEvent::ChannelCreated {
+        server_id,
+        channel_id: channel.id,
+        name: channel.name.clone(),
+    };
+    hub.emit(format!("channel:{}", channel.id), event).await;
+
     Ok((StatusCode::CREATED, Json(channel)))
 }
 
-/// Lister les canaux d'un serveur (GET /servers/:server_id/channels)
+/// Lister les canaux d'un serveur avec leurs non-lus (GET /servers/:server_id/channels)
 pub async fn list_channels(
     State(app_state): State<AppState>,
     auth_user: AuthUser,
     Path(server_id): Path<i32>,
-) -> AppResult<Json<Vec<Channel>>> {
+) -> AppResult<Json<Vec<ChannelWithReadState>>> {
     let channels = app_state.channel_service
         .get_server_channels(server_id, auth_user.user_id)
         .await?;
+    let channels = app_state.read_state_service
+        .with_read_states(auth_user.user_id, channels)
+        .await?;
     Ok(Json(channels))
 }
 
+/// Marquer un canal comme lu (POST /channels/:id/ack)
+pub async fn ack_channel(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<i32>,
+    dto: Option<Json<AckChannelDto>>,
+) -> AppResult<Json<ChannelReadState>> {
+    let Json(dto) = dto.unwrap_or_default();
+    let state = app_state.read_state_service
+        .ack_channel(channel_id, dto, auth_user.user_id)
+        .await?;
+    
+    // Synchroniser les autres sockets de l'utilisateur
+    let event = SocketEvent::ChannelAck {
+        channel_id: state.channel_id,
+        last_read_message_id: state.last_read_message_id,
+        unread_count: state.unread_count,
+        mention_count: state.mention_count,
+    };
+    
+    hub.emit(format!("user:{}", auth_user.user_id), event).await;
+    
+    Ok(Json(state))
+}
+
 /// Récupérer un canal (GET /channels/:id)
 pub async fn get_channel(
     State(app_state): State<AppState>,
@@ -61,11 +108,103 @@ pub async fn update_channel(
 /// Supprimer un canal (DELETE /channels/:id)
 pub async fn delete_channel(
     State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
     auth_user: AuthUser,
     Path(channel_id): Path<i32>,
 ) -> AppResult<StatusCode> {
-    app_state.channel_service
+    let channel = app_state.channel_service
         .delete_channel(channel_id, auth_user.user_id)
         .await?;
+
+    // Prévenir les abonnés du canal, puis fermer sa room
+    let room = format!("channel:{}", channel.id);
+    let event = SocketEvent::ChannelDeleted {
+        server_id: channel.server_id,
+        channel_id: channel.id,
+    };
+    hub.emit(room.clone(), event).await;
+    hub.leave_rooms(vec![room.clone()], vec![room]).await;
+
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Lister les surcharges de permissions d'un canal (GET /channels/:id/permissions)
+pub async fn list_channel_overwrites(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<i32>,
+) -> AppResult<Json<Vec<PermissionOverwrite>>> {
+    let overwrites = app_state.channel_service
+        .get_overwrites(channel_id, auth_user.user_id)
+        .await?;
+    Ok(Json(overwrites))
+}
+
+/// Définir la surcharge d'un rôle (PUT /channels/:id/permissions/roles/:role_id)
+pub async fn put_role_overwrite(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((channel_id, role_id)): Path<(i32, i32)>,
+    Json(dto): Json<PermissionOverwriteDto>,
+) -> AppResult<Json<PermissionOverwrite>> {
+    let overwrite = app_state.channel_service
+        .set_role_overwrite(channel_id, role_id, dto, auth_user.user_id)
+        .await?;
+    sync_overwritten_channel(&app_state, &hub, channel_id).await?;
+    Ok(Json(overwrite))
+}
+
+/// Supprimer la surcharge d'un rôle (DELETE /channels/:id/permissions/roles/:role_id)
+pub async fn delete_role_overwrite(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((channel_id, role_id)): Path<(i32, i32)>,
+) -> AppResult<StatusCode> {
+    app_state.channel_service
+        .delete_role_overwrite(channel_id, role_id, auth_user.user_id)
+        .await?;
+    sync_overwritten_channel(&app_state, &hub, channel_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Définir la surcharge d'un membre (PUT /channels/:id/permissions/members/:user_id)
+pub async fn put_member_overwrite(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((channel_id, target_user_id)): Path<(i32, i32)>,
+    Json(dto): Json<PermissionOverwriteDto>,
+) -> AppResult<Json<PermissionOverwrite>> {
+    let overwrite = app_state.channel_service
+        .set_member_overwrite(channel_id, target_user_id, dto, auth_user.user_id)
+        .await?;
+    sync_overwritten_channel(&app_state, &hub, channel_id).await?;
+    Ok(Json(overwrite))
+}
+
+/// Supprimer la surcharge d'un membre (DELETE /channels/:id/permissions/members/:user_id)
+pub async fn delete_member_overwrite(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((channel_id, target_user_id)): Path<(i32, i32)>,
+) -> AppResult<StatusCode> {
+    app_state.channel_service
+        .delete_member_overwrite(channel_id, target_user_id, auth_user.user_id)
+        .await?;
+    sync_overwritten_channel(&app_state, &hub, channel_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Réabonner les sockets du serveur après un changement de surcharge
+async fn sync_overwritten_channel(
+    app_state: &AppState,
+    hub: &Hub,
+    channel_id: i32,
+) -> AppResult<()> {
+    let server_id = app_state.channel_service.get_server_id(channel_id).await?;
+    hub.sync_channel_rooms(server_id).await;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension,
+    Json,
+};
+use std::sync::Arc;
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{CreateMessageDto, DmConversationDetails, MessageQuery, MessageWithAuthor, OpenDmDto};
+use crate::state::AppState;
+use crate::ws::{Hub, SocketEvent};
+
+/// Ouvrir une conversation privée (POST /dms)
+pub async fn open_dm(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Json(dto): Json<OpenDmDto>,
+) -> AppResult<(StatusCode, Json<DmConversationDetails>)> {
+    let conversation = app_state.dm_service
+        .open_conversation(dto, auth_user.user_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(conversation)))
+}
+
+/// Lister les conversations privées (GET /dms)
+pub async fn list_dms(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+) -> AppResult<Json<Vec<DmConversationDetails>>> {
+    let conversations = app_state.dm_service
+        .get_user_conversations(auth_user.user_id)
+        .await?;
+    Ok(Json(conversations))
+}
+
+/// Récupérer une conversation privée (GET /dms/:id)
+pub async fn get_dm(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(conversation_id): Path<i32>,
+) -> AppResult<Json<DmConversationDetails>> {
+    let conversation = app_state.dm_service
+        .get_conversation(conversation_id, auth_user.user_id)
+        .await?;
+    Ok(Json(conversation))
+}
+
+/// Envoyer un message privé (POST /dms/:id/messages)
+pub async fn send_dm_message(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path(conversation_id): Path<i32>,
+    Json(dto): Json<CreateMessageDto>,
+) -> AppResult<(StatusCode, Json<MessageWithAuthor>)> {
+    let message = app_state.dm_service
+        .send_message(conversation_id, dto, auth_user.user_id)
+        .await?;
+
+    let conversation = app_state.dm_service
+        .get_conversation(conversation_id, auth_user.user_id)
+        .await?;
+
+    // Broadcaster le message dans la room personnelle de chaque participant
+    let event = SocketEvent::DirectMessage {
+        conversation_id,
+        message_id: message.id,
+        content: message.content.clone(),
+        author_id: message.author_id,
+        author_username: message.author_username.clone(),
+        created_at: message.created_at.to_rfc3339(),
+    };
+
+    let rooms: Vec<String> = conversation
+        .participants
+        .iter()
+        .map(|participant| format!("user:{}", participant.user_id))
+        .collect();
+
+    hub.emit_to_rooms(rooms, event).await;
+
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+/// Récupérer l'historique d'une conversation privée (GET /dms/:id/messages)
+pub async fn get_dm_messages(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(conversation_id): Path<i32>,
+    Query(query): Query<MessageQuery>,
+) -> AppResult<Json<Vec<MessageWithAuthor>>> {
+    let messages = app_state.dm_service
+        .get_messages(conversation_id, auth_user.user_id, query)
+        .await?;
+    Ok(Json(messages))
+}
@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{CreateInvitationDto, Invitation, InvitationPreview};
+use crate::state::AppState;
+
+/// Créer une invitation (POST /servers/:server_id/invites)
+pub async fn create_invite(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+    dto: Option<Json<CreateInvitationDto>>,
+) -> AppResult<(StatusCode, Json<Invitation>)> {
+    let Json(dto) = dto.unwrap_or_default();
+    let invitation = app_state.invitation_service
+        .create_invitation(server_id, dto, auth_user.user_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(invitation)))
+}
+
+/// Lister les invitations d'un serveur (GET /servers/:server_id/invites)
+pub async fn list_invites(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+) -> AppResult<Json<Vec<Invitation>>> {
+    let invitations = app_state.invitation_service
+        .get_invitations(server_id, auth_user.user_id)
+        .await?;
+    Ok(Json(invitations))
+}
+
+/// Révoquer une invitation (DELETE /servers/:server_id/invites/:code)
+pub async fn revoke_invite(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path((server_id, code)): Path<(i32, String)>,
+) -> AppResult<StatusCode> {
+    app_state.invitation_service
+        .revoke_invitation(server_id, &code, auth_user.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Aperçu public d'une invitation (GET /invites/:code)
+pub async fn preview_invite(
+    State(app_state): State<AppState>,
+    Path(code): Path<String>,
+) -> AppResult<Json<InvitationPreview>> {
+    let preview = app_state.invitation_service.preview_invitation(&code).await?;
+    Ok(Json(preview))
+}
@@ -0,0 +1,33 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{Mention, MentionQuery};
+use crate::state::AppState;
+
+/// Mentions non vues de l'utilisateur (GET /users/me/mentions)
+pub async fn list_mentions(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<MentionQuery>,
+) -> AppResult<Json<Vec<Mention>>> {
+    let mentions = app_state.mention_service
+        .get_unseen_mentions(auth_user.user_id, query)
+        .await?;
+    Ok(Json(mentions))
+}
+
+/// Marquer une mention comme vue (DELETE /users/me/mentions/:message_id)
+pub async fn dismiss_mention(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(message_id): Path<i32>,
+) -> AppResult<StatusCode> {
+    app_state.mention_service
+        .dismiss_mention(auth_user.user_id, message_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
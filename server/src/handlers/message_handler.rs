@@ -4,25 +4,21 @@ use axum::{
     Extension,
     Json,
 };
-use serde::Deserialize;
-use socketioxide::SocketIo;
+use std::sync::Arc;
 use crate::errors::AppResult;
 use crate::handlers::middleware::AuthUser;
-use crate::models::{CreateMessageDto, Message, MessageWithAuthor};
+use crate::irc::IrcGateway;
+use crate::models::{
+    CreateMessageDto, Message, MessageQuery, MessageRevision, MessageWithAuthor, UpdateMessageDto,
+};
 use crate::state::AppState;
-use crate::ws::SocketEvent;
-
-/// Query parameters pour la pagination
-#[derive(Deserialize)]
-pub struct MessageQuery {
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
-}
+use crate::ws::{self, Hub, SocketEvent};
 
 /// Créer un message (POST /channels/:channel_id/messages)
 pub async fn create_message(
     State(app_state): State<AppState>,
-    Extension(io): Extension<SocketIo>,
+    Extension(hub): Extension<Arc<Hub>>,
+    Extension(irc): Extension<Arc<IrcGateway>>,
     auth_user: AuthUser,
     Path(channel_id): Path<i32>,
     Json(dto): Json<CreateMessageDto>,
@@ -30,23 +26,10 @@ pub async fn create_message(
     let message = app_state.message_service
         .create_message(channel_id, dto, auth_user.user_id)
         .await?;
+    let mentions = app_state.mention_service.record_mentions(&message).await;
     
-    // Broadcaster le nouveau message via WebSocket
-    let event = SocketEvent::NewMessage {
-        channel_id: message.channel_id,
-        message_id: message.id,
-        content: message.content.clone(),
-        author_id: auth_user.user_id,
-        author_username: auth_user.username,
-        created_at: message.created_at.to_rfc3339(),
-    };
-    
-    // Envoyer à tous les membres du canal
-    io.of("/")
-        .expect("default namespace")
-        .to(format!("channel:{}", channel_id))
-        .emit(event.event_name(), serde_json::to_value(event).unwrap())
-        .ok();
+    // Diffuser aux membres du canal, aux mentionnés et aux clients IRC
+    ws::broadcast_new_message(&hub, &irc, &message, &auth_user.username, &mentions, None).await;
     
     Ok((StatusCode::CREATED, Json(message)))
 }
@@ -56,18 +39,58 @@ pub async fn get_messages(
     State(app_state): State<AppState>,
     auth_user: AuthUser,
     Path(channel_id): Path<i32>,
-    Query(params): Query<MessageQuery>,
+    Query(query): Query<MessageQuery>,
 ) -> AppResult<Json<Vec<MessageWithAuthor>>> {
     let messages = app_state.message_service
-        .get_channel_messages(channel_id, auth_user.user_id, params.limit, params.offset)
+        .get_channel_messages(channel_id, auth_user.user_id, query)
         .await?;
     Ok(Json(messages))
 }
 
+/// Modifier un message (PATCH /messages/:id)
+pub async fn update_message(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path(message_id): Path<i32>,
+    Json(dto): Json<UpdateMessageDto>,
+) -> AppResult<Json<Message>> {
+    let message = app_state.message_service
+        .update_message(message_id, dto, auth_user.user_id)
+        .await?;
+    
+    // Broadcaster la modification via WebSocket
+    let event = SocketEvent::MessageUpdated {
+        channel_id: message.channel_id,
+        message_id: message.id,
+        content: message.content.clone(),
+        updated_at: message
+            .updated_at
+            .unwrap_or(message.created_at)
+            .to_rfc3339(),
+    };
+    
+    hub.emit(format!("channel:{}", message.channel_id), event).await;
+    
+    Ok(Json(message))
+}
+
+/// Récupérer l'historique des modifications d'un message (GET /messages/:id/revisions)
+pub async fn get_message_revisions(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(message_id): Path<i32>,
+) -> AppResult<Json<Vec<MessageRevision>>> {
+    let revisions = app_state.message_service
+        .get_message_revisions(message_id, auth_user.user_id)
+        .await?;
+    Ok(Json(revisions))
+}
+
 /// Supprimer un message (DELETE /messages/:id)
 pub async fn delete_message(
     State(app_state): State<AppState>,
-    Extension(io): Extension<SocketIo>,
+    Extension(hub): Extension<Arc<Hub>>,
     auth_user: AuthUser,
     Path(message_id): Path<i32>,
 ) -> AppResult<StatusCode> {
@@ -86,11 +109,122 @@ pub async fn delete_message(
         message_id,
     };
     
-    io.of("/")
-        .expect("default namespace")
-        .to(format!("channel:{}", message.channel_id))
-        .emit(event.event_name(), serde_json::to_value(event).unwrap())
-        .ok();
+    hub.emit(format!("channel:{}", message.channel_id), event).await;
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Réagir à un message (PUT /messages/:id/reactions/:emoji)
+pub async fn add_reaction(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((message_id, emoji)): Path<(i32, String)>,
+) -> AppResult<StatusCode> {
+    let change = app_state.message_service
+        .add_reaction(message_id, &emoji, auth_user.user_id)
+        .await?;
+    
+    // Rien à diffuser si la réaction existait déjà
+    if let Some(change) = change {
+        let event = SocketEvent::ReactionAdded {
+            channel_id: change.channel_id,
+            message_id: change.message_id,
+            emoji: change.emoji,
+            user_id: auth_user.user_id,
+            count: change.count,
+        };
+        
+        hub.emit(format!("channel:{}", change.channel_id), event).await;
+    }
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Retirer sa réaction (DELETE /messages/:id/reactions/:emoji)
+pub async fn remove_reaction(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((message_id, emoji)): Path<(i32, String)>,
+) -> AppResult<StatusCode> {
+    let change = app_state.message_service
+        .remove_reaction(message_id, &emoji, auth_user.user_id)
+        .await?;
+    
+    if let Some(change) = change {
+        let event = SocketEvent::ReactionRemoved {
+            channel_id: change.channel_id,
+            message_id: change.message_id,
+            emoji: change.emoji,
+            user_id: auth_user.user_id,
+            count: change.count,
+        };
+        
+        hub.emit(format!("channel:{}", change.channel_id), event).await;
+    }
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lister les messages épinglés d'un canal (GET /channels/:id/pins)
+pub async fn list_pins(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<i32>,
+) -> AppResult<Json<Vec<MessageWithAuthor>>> {
+    let pins = app_state.message_service
+        .get_pins(channel_id, auth_user.user_id)
+        .await?;
+    Ok(Json(pins))
+}
+
+/// Épingler un message (PUT /channels/:id/pins/:message_id)
+pub async fn pin_message(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((channel_id, message_id)): Path<(i32, i32)>,
+) -> AppResult<StatusCode> {
+    let pinned_at = app_state.message_service
+        .pin_message(channel_id, message_id, auth_user.user_id)
+        .await?;
+    
+    // Rien à diffuser si le message était déjà épinglé
+    if let Some(pinned_at) = pinned_at {
+        let event = SocketEvent::MessagePinned {
+            channel_id,
+            message_id,
+            pinned_by: auth_user.user_id,
+            pinned_at: pinned_at.to_rfc3339(),
+        };
+        
+        hub.emit(format!("channel:{}", channel_id), event).await;
+    }
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Désépingler un message (DELETE /channels/:id/pins/:message_id)
+pub async fn unpin_message(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((channel_id, message_id)): Path<(i32, i32)>,
+) -> AppResult<StatusCode> {
+    let unpinned = app_state.message_service
+        .unpin_message(channel_id, message_id, auth_user.user_id)
+        .await?;
+    
+    if unpinned {
+        let event = SocketEvent::MessageUnpinned {
+            channel_id,
+            message_id,
+            unpinned_by: auth_user.user_id,
+        };
+        
+        hub.emit(format!("channel:{}", channel_id), event).await;
+    }
     
     Ok(StatusCode::NO_CONTENT)
 }
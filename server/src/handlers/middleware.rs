@@ -1,7 +1,7 @@
 use axum::{
     async_trait,
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
     RequestPartsExt,
 };
 use axum_extra::{
@@ -9,22 +9,26 @@ use axum_extra::{
     TypedHeader,
 };
 use crate::errors::AppError;
+use crate::state::AppState;
 use crate::utils::verify_token;
 
 /// Extractor pour l'utilisateur authentifié
 pub struct AuthUser {
     pub user_id: i32,
     pub username: String,
+    /// Session de connexion du token
+    pub session_id: i32,
 }
 
 #[async_trait]
 impl<S> FromRequestParts<S> for AuthUser
 where
+    AppState: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         // Extraire le header Authorization
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
@@ -34,9 +38,14 @@ where
         // Vérifier le token
         let claims = verify_token(bearer.token())?;
 
+        // Refuser les tokens d'une session révoquée (déconnexion, changement de mot de passe)
+        let app_state = AppState::from_ref(state);
+        app_state.auth_service.check_session(&claims).await?;
+
         Ok(AuthUser {
             user_id: claims.sub,
             username: claims.username,
+            session_id: claims.sid,
         })
     }
 }
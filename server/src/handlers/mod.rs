@@ -2,10 +2,27 @@ pub mod auth_handler;
 pub mod server_handler;
 pub mod channel_handler;
 pub mod message_handler;
+pub mod dm_handler;
+pub mod role_handler;
+pub mod audit_handler;
+pub mod invitation_handler;
+pub mod thread_handler;
+pub mod mention_handler;
+pub mod search_handler;
+pub mod attachment_handler;
+pub mod user_handler;
 pub mod middleware;
 
 pub use auth_handler::*;
 pub use server_handler::*;
 pub use channel_handler::*;
 pub use message_handler::*;
-pub use middleware::*;
+pub use dm_handler::*;
+pub use role_handler::*;
+pub use audit_handler::*;
+pub use invitation_handler::*;
+pub use thread_handler::*;
+pub use mention_handler::*;
+pub use search_handler::*;
+pub use attachment_handler::*;
+pub use user_handler::*;
@@ -0,0 +1,67 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension,
+    Json,
+};
+use std::sync::Arc;
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{CreateRoleDto, Role, UpdateRoleDto};
+use crate::state::AppState;
+use crate::ws::Hub;
+
+/// Lister les rôles d'un serveur (GET /servers/:server_id/roles)
+pub async fn list_roles(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+) -> AppResult<Json<Vec<Role>>> {
+    let roles = app_state.role_service
+        .get_server_roles(server_id, auth_user.user_id)
+        .await?;
+    Ok(Json(roles))
+}
+
+/// Créer un rôle (POST /servers/:server_id/roles)
+pub async fn create_role(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+    Json(dto): Json<CreateRoleDto>,
+) -> AppResult<(StatusCode, Json<Role>)> {
+    let role = app_state.role_service
+        .create_role(server_id, dto, auth_user.user_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(role)))
+}
+
+/// Mettre à jour un rôle (PUT /servers/:server_id/roles/:role_id)
+pub async fn update_role(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, role_id)): Path<(i32, i32)>,
+    Json(dto): Json<UpdateRoleDto>,
+) -> AppResult<Json<Role>> {
+    let role = app_state.role_service
+        .update_role(server_id, role_id, dto, auth_user.user_id)
+        .await?;
+    // Les permissions du rôle peuvent changer la visibilité des canaux
+    hub.sync_channel_rooms(server_id).await;
+    Ok(Json(role))
+}
+
+/// Supprimer un rôle (DELETE /servers/:server_id/roles/:role_id)
+pub async fn delete_role(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, role_id)): Path<(i32, i32)>,
+) -> AppResult<StatusCode> {
+    app_state.role_service
+        .delete_role(server_id, role_id, auth_user.user_id)
+        .await?;
+    hub.sync_channel_rooms(server_id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
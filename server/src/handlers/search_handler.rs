@@ -0,0 +1,21 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{SearchQuery, SearchResults};
+use crate::state::AppState;
+
+/// Rechercher dans les messages d'un serveur (GET /servers/:id/messages/search)
+pub async fn search_messages(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+    Query(query): Query<SearchQuery>,
+) -> AppResult<Json<SearchResults>> {
+    let results = app_state.search_service
+        .search_messages(server_id, query, auth_user.user_id)
+        .await?;
+    Ok(Json(results))
+}
@@ -1,13 +1,18 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
+    Extension,
     Json,
 };
+use std::sync::Arc;
 use crate::errors::AppResult;
 use crate::handlers::middleware::AuthUser;
-use crate::models::{CreateServerDto, JoinServerDto, Server, ServerMemberDetails, UserRole};
+use crate::models::{
+    BanMemberDto, CreateServerDto, JoinServerDto, KickMemberDto, Role, Server, ServerBan,
+    ServerMemberDetails, ServerWithReadState, TimeoutMemberDto, TransferOwnershipDto, UpdateMemberRolesDto,
+};
 use crate::state::AppState;
-use serde::Deserialize;
+use crate::ws::{close_server_rooms, evict_from_server, Hub, SocketEvent};
 
 /// Créer un serveur (POST /servers)
 pub async fn create_server(
@@ -19,12 +24,15 @@ pub async fn create_server(
     Ok((StatusCode::CREATED, Json(server)))
 }
 
-/// Lister les serveurs de l'utilisateur (GET /servers)
+/// Lister les serveurs de l'utilisateur avec leurs non-lus (GET /servers)
 pub async fn list_servers(
     State(app_state): State<AppState>,
     auth_user: AuthUser,
-) -> AppResult<Json<Vec<Server>>> {
+) -> AppResult<Json<Vec<ServerWithReadState>>> {
     let servers = app_state.server_service.get_user_servers(auth_user.user_id).await?;
+    let servers = app_state.read_state_service
+        .with_server_read_states(auth_user.user_id, servers)
+        .await?;
     Ok(Json(servers))
 }
 
@@ -41,6 +49,7 @@ pub async fn get_server(
 /// Mettre à jour un serveur (PUT /servers/:id)
 pub async fn update_server(
     State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
     auth_user: AuthUser,
     Path(server_id): Path<i32>,
     Json(dto): Json<CreateServerDto>,
@@ -48,16 +57,41 @@ pub async fn update_server(
     let server = app_state.server_service
         .update_server(server_id, dto.name, auth_user.user_id)
         .await?;
+    emit_server_updated(&hub, &server).await;
+    Ok(Json(server))
+}
+
+/// Transmettre la propriété d'un serveur (POST /servers/:id/transfer-ownership)
+pub async fn transfer_ownership(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+    Json(dto): Json<TransferOwnershipDto>,
+) -> AppResult<Json<Server>> {
+    app_state.auth_service.confirm_password(auth_user.user_id, &dto.password).await?;
+    let server = app_state.server_service
+        .transfer_ownership(server_id, dto.user_id, auth_user.user_id)
+        .await?;
+
+    // Les rôles ont été échangés : les canaux visibles changent pour les deux membres
+    hub.sync_channel_rooms(server_id).await;
+    emit_server_updated(&hub, &server).await;
+
     Ok(Json(server))
 }
 
 /// Supprimer un serveur (DELETE /servers/:id)
 pub async fn delete_server(
     State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
     auth_user: AuthUser,
     Path(server_id): Path<i32>,
 ) -> AppResult<StatusCode> {
+    // Les canaux disparaissent avec le serveur : les lister avant
+    let channel_ids = app_state.channel_service.get_channel_ids(server_id).await?;
     app_state.server_service.delete_server(server_id, auth_user.user_id).await?;
+    close_server_rooms(&hub, server_id, &channel_ids).await;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -76,10 +110,16 @@ pub async fn join_server(
 /// Quitter un serveur (DELETE /servers/:id/leave)
 pub async fn leave_server(
     State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
     auth_user: AuthUser,
     Path(server_id): Path<i32>,
 ) -> AppResult<StatusCode> {
     app_state.server_service.leave_server(server_id, auth_user.user_id).await?;
+
+    // Retirer aussi les autres sessions de l'utilisateur
+    let channel_ids = app_state.channel_service.get_channel_ids(server_id).await?;
+    evict_from_server(&hub, server_id, auth_user.user_id, &channel_ids).await;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -93,21 +133,154 @@ pub async fn list_members(
     Ok(Json(members))
 }
 
-/// DTO pour mettre à jour le rôle d'un membre
-#[derive(Deserialize)]
-pub struct UpdateMemberRoleDto {
-    pub role: UserRole,
+/// Définir les rôles d'un membre (PUT /servers/:server_id/members/:user_id)
+pub async fn update_member_roles(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, target_user_id)): Path<(i32, i32)>,
+    Json(dto): Json<UpdateMemberRolesDto>,
+) -> AppResult<Json<Vec<Role>>> {
+    let roles = app_state.role_service
+        .set_member_roles(server_id, target_user_id, dto.role_ids, auth_user.user_id)
+        .await?;
+    hub.sync_channel_rooms(server_id).await;
+    Ok(Json(roles))
+}
+
+/// Expulser un membre (POST /servers/:server_id/members/:user_id/kick)
+pub async fn kick_member(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, target_user_id)): Path<(i32, i32)>,
+    dto: Option<Json<KickMemberDto>>,
+) -> AppResult<StatusCode> {
+    let Json(dto) = dto.unwrap_or_default();
+    let member = app_state.server_service
+        .kick_member(server_id, target_user_id, dto.reason, auth_user.user_id)
+        .await?;
+
+    let event = SocketEvent::MemberLeft {
+        server_id,
+        user_id: member.user_id,
+        username: member.username,
+    };
+    emit_moderation_event(&hub, server_id, target_user_id, event).await;
+
+    let channel_ids = app_state.channel_service.get_channel_ids(server_id).await?;
+    evict_from_server(&hub, server_id, target_user_id, &channel_ids).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Bannir un utilisateur (POST /servers/:server_id/members/:user_id/ban)
+pub async fn ban_member(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, target_user_id)): Path<(i32, i32)>,
+    dto: Option<Json<BanMemberDto>>,
+) -> AppResult<(StatusCode, Json<ServerBan>)> {
+    let Json(dto) = dto.unwrap_or_default();
+    let ban = app_state.server_service
+        .ban_member(server_id, target_user_id, dto, auth_user.user_id)
+        .await?;
+
+    let event = SocketEvent::MemberBanned {
+        server_id,
+        user_id: ban.user_id,
+        username: ban.username.clone(),
+        reason: ban.reason.clone(),
+        expires_at: ban.expires_at.map(|expires_at| expires_at.to_rfc3339()),
+    };
+    emit_moderation_event(&hub, server_id, target_user_id, event).await;
+
+    let channel_ids = app_state.channel_service.get_channel_ids(server_id).await?;
+    evict_from_server(&hub, server_id, target_user_id, &channel_ids).await;
+
+    Ok((StatusCode::CREATED, Json(ban)))
+}
+
+/// Lister les bannissements (GET /servers/:server_id/bans)
+pub async fn list_bans(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(server_id): Path<i32>,
+) -> AppResult<Json<Vec<ServerBan>>> {
+    let bans = app_state.server_service.get_bans(server_id, auth_user.user_id).await?;
+    Ok(Json(bans))
 }
 
-/// Mettre à jour le rôle d'un membre (PUT /servers/:server_id/members/:user_id)
-pub async fn update_member_role(
+/// Lever un bannissement (DELETE /servers/:server_id/bans/:user_id)
+pub async fn unban_member(
     State(app_state): State<AppState>,
     auth_user: AuthUser,
     Path((server_id, target_user_id)): Path<(i32, i32)>,
-    Json(dto): Json<UpdateMemberRoleDto>,
 ) -> AppResult<StatusCode> {
     app_state.server_service
-        .update_member_role(server_id, target_user_id, dto.role, auth_user.user_id)
+        .unban_member(server_id, target_user_id, auth_user.user_id)
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Exclure temporairement un membre (POST /servers/:server_id/members/:user_id/timeout)
+pub async fn timeout_member(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, target_user_id)): Path<(i32, i32)>,
+    Json(dto): Json<TimeoutMemberDto>,
+) -> AppResult<Json<ServerMemberDetails>> {
+    let member = app_state.server_service
+        .timeout_member(server_id, target_user_id, Some(dto.until), dto.reason, auth_user.user_id)
+        .await?;
+
+    emit_timeout_event(&hub, server_id, &member).await;
+
+    Ok(Json(member))
+}
+
+/// Lever l'exclusion temporaire d'un membre (DELETE /servers/:server_id/members/:user_id/timeout)
+pub async fn remove_timeout(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path((server_id, target_user_id)): Path<(i32, i32)>,
+) -> AppResult<Json<ServerMemberDetails>> {
+    let member = app_state.server_service
+        .timeout_member(server_id, target_user_id, None, None, auth_user.user_id)
+        .await?;
+
+    emit_timeout_event(&hub, server_id, &member).await;
+
+    Ok(Json(member))
+}
+
+/// Notifier le serveur et l'utilisateur sanctionné (avant son retrait des rooms)
+async fn emit_moderation_event(hub: &Hub, server_id: i32, target_user_id: i32, event: SocketEvent) {
+    // Les sockets de l'utilisateur qui n'ont pas rejoint la room du serveur sont inclus,
+    // chaque socket ne reçoit l'événement qu'une fois
+    let rooms = vec![format!("server:{}", server_id), format!("user:{}", target_user_id)];
+    hub.emit_to_rooms(rooms, event).await;
+}
+
+async fn emit_timeout_event(hub: &Hub, server_id: i32, member: &ServerMemberDetails) {
+    let event = SocketEvent::MemberTimedOut {
+        server_id,
+        user_id: member.user_id,
+        username: member.username.clone(),
+        until: member.timeout_until.map(|until| until.to_rfc3339()),
+    };
+    emit_moderation_event(hub, server_id, member.user_id, event).await;
+}
+
+/// Notifier les membres d'un changement de nom ou de propriétaire
+pub(crate) async fn emit_server_updated(hub: &Hub, server: &Server) {
+    let event = SocketEvent::ServerUpdated {
+        server_id: server.id,
+        name: server.name.clone(),
+        owner_id: server.owner_id,
+    };
+    hub.emit(format!("server:{}", server.id), event).await;
+}
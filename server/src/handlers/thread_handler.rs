@@ -0,0 +1,146 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension,
+    Json,
+};
+use std::sync::Arc;
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::models::{
+    CreateMessageDto, CreateThreadDto, Message, MessageQuery, MessageWithAuthor, Thread, ThreadMember,
+};
+use crate::state::AppState;
+use crate::ws::{self, Hub, SocketEvent};
+
+/// Ouvrir un fil sur un message (POST /messages/:id/threads)
+pub async fn create_thread(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path(message_id): Path<i32>,
+    dto: Option<Json<CreateThreadDto>>,
+) -> AppResult<(StatusCode, Json<Thread>)> {
+    let Json(dto) = dto.unwrap_or_default();
+    let thread = app_state.thread_service
+        .create_thread(message_id, dto, auth_user.user_id)
+        .await?;
+    
+    let event = SocketEvent::ThreadCreated {
+        channel_id: thread.channel_id,
+        thread_id: thread.id,
+        root_message_id: thread.root_message_id,
+        name: thread.name.clone(),
+        creator_id: auth_user.user_id,
+    };
+    
+    hub.emit(format!("channel:{}", thread.channel_id), event).await;
+    
+    Ok((StatusCode::CREATED, Json(thread)))
+}
+
+/// Lister les fils d'un canal (GET /channels/:id/threads)
+pub async fn list_threads(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<i32>,
+) -> AppResult<Json<Vec<Thread>>> {
+    let threads = app_state.thread_service
+        .get_channel_threads(channel_id, auth_user.user_id)
+        .await?;
+    Ok(Json(threads))
+}
+
+/// Récupérer un fil (GET /threads/:id)
+pub async fn get_thread(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(thread_id): Path<i32>,
+) -> AppResult<Json<Thread>> {
+    let thread = app_state.thread_service
+        .get_thread(thread_id, auth_user.user_id)
+        .await?;
+    Ok(Json(thread))
+}
+
+/// Poster un message dans un fil (POST /threads/:id/messages)
+pub async fn create_thread_message(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Path(thread_id): Path<i32>,
+    Json(dto): Json<CreateMessageDto>,
+) -> AppResult<(StatusCode, Json<Message>)> {
+    let message = app_state.message_service
+        .create_thread_message(thread_id, dto, auth_user.user_id)
+        .await?;
+    let mentions = app_state.mention_service.record_mentions(&message).await;
+    
+    // Les fils sont diffusés aux membres du canal parent
+    let event = SocketEvent::ThreadMessage {
+        channel_id: message.channel_id,
+        thread_id,
+        message_id: message.id,
+        content: message.content.clone(),
+        author_id: auth_user.user_id,
+        author_username: auth_user.username.clone(),
+        reply_to_id: message.reply_to_id,
+        attachments: message.attachments.clone(),
+        created_at: message.created_at.to_rfc3339(),
+    };
+    
+    hub.emit(format!("channel:{}", message.channel_id), event).await;
+    
+    ws::emit_mentions(&hub, &message, &auth_user.username, &mentions).await;
+    
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+/// Récupérer l'historique d'un fil (GET /threads/:id/messages)
+pub async fn get_thread_messages(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(thread_id): Path<i32>,
+    Query(query): Query<MessageQuery>,
+) -> AppResult<Json<Vec<MessageWithAuthor>>> {
+    let messages = app_state.message_service
+        .get_thread_messages(thread_id, auth_user.user_id, query)
+        .await?;
+    Ok(Json(messages))
+}
+
+/// Lister les participants d'un fil (GET /threads/:id/members)
+pub async fn list_thread_members(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(thread_id): Path<i32>,
+) -> AppResult<Json<Vec<ThreadMember>>> {
+    let members = app_state.thread_service
+        .get_members(thread_id, auth_user.user_id)
+        .await?;
+    Ok(Json(members))
+}
+
+/// Suivre un fil (PUT /threads/:id/members/@me)
+pub async fn join_thread(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(thread_id): Path<i32>,
+) -> AppResult<StatusCode> {
+    app_state.thread_service
+        .join_thread(thread_id, auth_user.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Ne plus suivre un fil (DELETE /threads/:id/members/@me)
+pub async fn leave_thread(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Path(thread_id): Path<i32>,
+) -> AppResult<StatusCode> {
+    app_state.thread_service
+        .leave_thread(thread_id, auth_user.user_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,71 @@
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use std::sync::Arc;
+use crate::errors::AppResult;
+use crate::handlers::middleware::AuthUser;
+use crate::handlers::server_handler::emit_server_updated;
+use crate::models::{DeleteAccountDto, UpdateUserDto, User};
+use crate::state::AppState;
+use crate::ws::{close_server_rooms, evict_from_server, Hub, SocketEvent};
+
+/// Modifier son compte et son profil (PATCH /users/me)
+pub async fn update_me(
+    State(app_state): State<AppState>,
+    auth_user: AuthUser,
+    Json(dto): Json<UpdateUserDto>,
+) -> AppResult<Json<User>> {
+    let user = app_state.auth_service
+        .update_user(auth_user.user_id, auth_user.session_id, dto)
+        .await?;
+    Ok(Json(user))
+}
+
+/// Supprimer son compte (DELETE /users/me)
+///
+/// Les serveurs possédés sont transmis ou supprimés d'abord ; les autres serveurs
+/// voient partir l'utilisateur, dont les sockets sont fermés.
+pub async fn delete_me(
+    State(app_state): State<AppState>,
+    Extension(hub): Extension<Arc<Hub>>,
+    auth_user: AuthUser,
+    Json(dto): Json<DeleteAccountDto>,
+) -> AppResult<StatusCode> {
+    let user_id = auth_user.user_id;
+    app_state.auth_service.confirm_password(user_id, &dto.password).await?;
+
+    for server in app_state.server_service.get_owned_servers(user_id).await? {
+        // Les canaux disparaissent avec le serveur : les lister avant
+        let channel_ids = app_state.channel_service.get_channel_ids(server.id).await?;
+        let transferred = app_state.server_service
+            .release_server(server.id, user_id, dto.owned_servers)
+            .await?;
+        match transferred {
+            Some(server) => {
+                hub.sync_channel_rooms(server.id).await;
+                emit_server_updated(&hub, &server).await;
+            }
+            None => close_server_rooms(&hub, server.id, &channel_ids).await,
+        }
+    }
+
+    // Adhésions restantes, supprimées avec le compte
+    let mut memberships = Vec::new();
+    for server in app_state.server_service.get_user_servers(user_id).await? {
+        let channel_ids = app_state.channel_service.get_channel_ids(server.id).await?;
+        memberships.push((server.id, channel_ids));
+    }
+
+    app_state.auth_service.delete_user(user_id).await?;
+
+    for (server_id, channel_ids) in memberships {
+        evict_from_server(&hub, server_id, user_id, &channel_ids).await;
+        let event = SocketEvent::MemberLeft {
+            server_id,
+            user_id,
+            username: auth_user.username.clone(),
+        };
+        hub.emit(format!("server:{}", server_id), event).await;
+    }
+    hub.disconnect_rooms(vec![format!("user:{}", user_id)]).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
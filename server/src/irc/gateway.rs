@@ -0,0 +1,326 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Notify, RwLock};
+use tracing::{error, info};
+use crate::irc::protocol::{user_prefix, SERVER_NAME};
+use crate::irc::session;
+use crate::models::{Message, MessageMentions};
+use crate::state::AppState;
+use crate::ws::{self, Hub, SocketEvent, UserInfo};
+
+/// File d'envoi d'une session IRC (une ligne par élément, sans CRLF)
+pub type IrcSender = mpsc::UnboundedSender<String>;
+
+/// Motif des KICK envoyés quand un utilisateur perd l'accès à un canal
+const ACCESS_REVOKED_REASON: &str = "Accès au canal retiré";
+
+/// Session IRC présente dans un canal
+pub(crate) struct IrcPeer {
+    pub user_id: i32,
+    pub nick: String,
+    pub tx: IrcSender,
+}
+
+/// Connexion IRC authentifiée, à fermer si sa session est révoquée
+struct IrcConnection {
+    user_id: i32,
+    auth_session_id: Option<i32>,
+    close: Arc<Notify>,
+}
+
+/// Canal suivi par la passerelle avec les sessions IRC qui l'ont rejoint
+struct IrcRoom {
+    name: String,
+    peers: HashMap<u64, IrcPeer>,
+}
+
+/// Passerelle IRC : expose les serveurs et canaux aux clients IRC classiques
+/// et relaie les messages entre IRC et Socket.IO
+pub struct IrcGateway {
+    pub(crate) app_state: AppState,
+    hub: Arc<Hub>,
+
+    /// Map channel_id -> sessions IRC présentes dans le canal
+    rooms: RwLock<HashMap<i32, IrcRoom>>,
+
+    /// Connexions authentifiées de cette instance, par session IRC
+    connections: RwLock<HashMap<u64, IrcConnection>>,
+
+    next_session_id: AtomicU64,
+}
+
+impl IrcGateway {
+    pub fn new(app_state: AppState, hub: Arc<Hub>) -> Self {
+        Self {
+            app_state,
+            hub,
+            rooms: RwLock::new(HashMap::new()),
+            connections: RwLock::new(HashMap::new()),
+            next_session_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Accepter les connexions IRC en boucle
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, addr)) => {
+                    info!("🔌 Nouveau client IRC connecté : {}", addr);
+                    tokio::spawn(session::run(self.clone(), stream));
+                }
+                Err(e) => {
+                    error!("Erreur lors de l'acceptation d'une connexion IRC : {}", e);
+                }
+            }
+        }
+    }
+
+    /// Attribuer un identifiant unique à une session IRC
+    pub(crate) fn next_session_id(&self) -> u64 {
+        self.next_session_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Enregistrer une connexion authentifiée ; `close` est notifié pour la fermer
+    pub(crate) async fn register(&self, session_id: u64, identity: &UserInfo, close: Arc<Notify>) {
+        self.connections.write().await.insert(
+            session_id,
+            IrcConnection {
+                user_id: identity.user_id,
+                auth_session_id: identity.auth_session_id,
+                close,
+            },
+        );
+    }
+
+    /// Oublier une connexion fermée
+    pub(crate) async fn unregister(&self, session_id: u64) {
+        self.connections.write().await.remove(&session_id);
+    }
+
+    /// Fermer les connexions de cette instance authentifiées avec une session révoquée,
+    /// et celles ouvertes avec le mot de passe du compte si `password_logins`
+    pub async fn close_sessions(&self, user_id: i32, session_ids: &[i32], password_logins: bool) {
+        let connections = self.connections.read().await;
+        for connection in connections.values() {
+            let revoked = connection.user_id == user_id
+                && match connection.auth_session_id {
+                    Some(session_id) => session_ids.contains(&session_id),
+                    None => password_logins,
+                };
+            if revoked {
+                connection.close.notify_one();
+            }
+        }
+    }
+
+    /// Pendant IRC de `BusMessage::Disconnect` : fermer les connexions des utilisateurs
+    /// dont la room `user:{id}` est visée (suppression du compte)
+    pub async fn disconnect_rooms(&self, rooms: &[String]) {
+        let user_ids: HashSet<i32> = rooms.iter().filter_map(|room| room_id(room, "user:")).collect();
+        if user_ids.is_empty() {
+            return;
+        }
+
+        let connections = self.connections.read().await;
+        for connection in connections.values() {
+            if user_ids.contains(&connection.user_id) {
+                connection.close.notify_one();
+            }
+        }
+    }
+
+    /// Ajouter une session à un canal
+    pub(crate) async fn join_room(&self, channel_id: i32, name: &str, session_id: u64, peer: IrcPeer) {
+        let mut rooms = self.rooms.write().await;
+        rooms
+            .entry(channel_id)
+            .or_insert_with(|| IrcRoom {
+                name: name.to_string(),
+                peers: HashMap::new(),
+            })
+            .peers
+            .insert(session_id, peer);
+    }
+
+    /// Retirer une session d'un canal
+    pub(crate) async fn leave_room(&self, channel_id: i32, session_id: u64) {
+        let mut rooms = self.rooms.write().await;
+        if let Some(room) = rooms.get_mut(&channel_id) {
+            room.peers.remove(&session_id);
+            if room.peers.is_empty() {
+                rooms.remove(&channel_id);
+            }
+        }
+    }
+
+    /// La session est-elle toujours dans le canal (elle a pu en être retirée de force)
+    pub(crate) async fn in_room(&self, channel_id: i32, session_id: u64) -> bool {
+        self.rooms
+            .read()
+            .await
+            .get(&channel_id)
+            .is_some_and(|room| room.peers.contains_key(&session_id))
+    }
+
+    /// Envoyer une ligne à toutes les sessions IRC d'un canal (sauf éventuellement l'émetteur)
+    pub(crate) async fn send_to_room(&self, channel_id: i32, line: &str, except: Option<u64>) {
+        let rooms = self.rooms.read().await;
+        if let Some(room) = rooms.get(&channel_id) {
+            for (session_id, peer) in &room.peers {
+                if Some(*session_id) != except {
+                    let _ = peer.tx.send(line.to_string());
+                }
+            }
+        }
+    }
+
+    /// Pendant IRC de `BusMessage::Leave` : retirer des canaux de `rooms` les sessions
+    /// présentes dans `within` (rooms `user:{id}` ou `channel:{id}`)
+    ///
+    /// Exécuté par chaque instance pour ses propres sessions (expulsion, bannissement,
+    /// suppression d'un canal ou d'un serveur).
+    pub async fn leave_rooms(&self, within: &[String], rooms: &[String]) {
+        let user_ids: HashSet<i32> = within.iter().filter_map(|room| room_id(room, "user:")).collect();
+        let within_channels: HashSet<i32> = within.iter().filter_map(|room| room_id(room, "channel:")).collect();
+        let channel_ids: Vec<i32> = rooms.iter().filter_map(|room| room_id(room, "channel:")).collect();
+
+        let sessions: HashSet<u64> = {
+            let user_ids = &user_ids;
+            let rooms = self.rooms.read().await;
+            rooms
+                .iter()
+                .flat_map(|(channel_id, room)| {
+                    let whole_room = within_channels.contains(channel_id);
+                    room.peers
+                        .iter()
+                        .filter(move |(_, peer)| whole_room || user_ids.contains(&peer.user_id))
+                        .map(|(session_id, _)| *session_id)
+                })
+                .collect()
+        };
+        if sessions.is_empty() {
+            return;
+        }
+
+        for channel_id in channel_ids {
+            self.kick(channel_id, |session_id, _| sessions.contains(&session_id)).await;
+        }
+    }
+
+    /// Pendant IRC de `ws::sync_local_channel_rooms` : retirer les sessions des canaux
+    /// d'un serveur devenus invisibles pour leur utilisateur (surcharges, rôles)
+    pub async fn sync_channel_rooms(&self, server_id: i32) {
+        let channel_service = &self.app_state.channel_service;
+        let Ok(channel_ids) = channel_service.get_channel_ids(server_id).await else {
+            return;
+        };
+
+        // Utilisateurs IRC présents dans les canaux du serveur
+        let user_ids: HashSet<i32> = {
+            let rooms = self.rooms.read().await;
+            channel_ids
+                .iter()
+                .filter_map(|channel_id| rooms.get(channel_id))
+                .flat_map(|room| room.peers.values().map(|peer| peer.user_id))
+                .collect()
+        };
+
+        let mut visible_by_user: HashMap<i32, HashSet<i32>> = HashMap::new();
+        for user_id in user_ids {
+            let visible = channel_service
+                .get_server_channels(server_id, user_id)
+                .await
+                .unwrap_or_default();
+            visible_by_user.insert(user_id, visible.iter().map(|channel| channel.id).collect());
+        }
+
+        for channel_id in channel_ids {
+            self.kick(channel_id, |_, user_id| {
+                !visible_by_user
+                    .get(&user_id)
+                    .is_some_and(|visible| visible.contains(&channel_id))
+            })
+            .await;
+        }
+    }
+
+    /// Retirer d'un canal les sessions désignées, en annonçant un KICK à tout le canal
+    async fn kick(&self, channel_id: i32, selected: impl Fn(u64, i32) -> bool) {
+        let mut rooms = self.rooms.write().await;
+        let Some(room) = rooms.get_mut(&channel_id) else {
+            return;
+        };
+
+        let kicked: Vec<u64> = room
+            .peers
+            .iter()
+            .filter(|(session_id, peer)| selected(**session_id, peer.user_id))
+            .map(|(session_id, _)| *session_id)
+            .collect();
+
+        for session_id in kicked {
+            let Some(peer) = room.peers.get(&session_id) else {
+                continue;
+            };
+            let line = format!(":{} KICK {} {} :{}", SERVER_NAME, room.name, peer.nick, ACCESS_REVOKED_REASON);
+            for other in room.peers.values() {
+                let _ = other.tx.send(line.clone());
+            }
+            room.peers.remove(&session_id);
+        }
+
+        if room.peers.is_empty() {
+            rooms.remove(&channel_id);
+        }
+    }
+
+    /// Diffuser un événement aux clients Socket.IO abonnés au canal
+    ///
+    /// Les autres instances le relaient aussi à leurs propres clients IRC.
+    pub(crate) async fn emit_to_web(&self, channel_id: i32, event: SocketEvent) {
+        self.hub.emit(format!("channel:{}", channel_id), event).await;
+    }
+
+    /// Notifier les utilisateurs web mentionnés dans un message venu d'IRC
+    pub(crate) async fn emit_mentions(&self, message: &Message, author_username: &str, mentions: &MessageMentions) {
+        ws::emit_mentions(&self.hub, message, author_username, mentions).await;
+    }
+
+    /// Relayer vers IRC un événement produit côté web (REST / Socket.IO) ou par une autre instance
+    pub async fn relay(&self, event: &SocketEvent) {
+        if let SocketEvent::NewMessage {
+            channel_id,
+            content,
+            author_username,
+            attachments,
+            ..
+        } = event
+        {
+            let rooms = self.rooms.read().await;
+            let Some(room) = rooms.get(channel_id) else {
+                return;
+            };
+
+            // Les pièces jointes sont annoncées par leur nom
+            let files = attachments
+                .iter()
+                .map(|attachment| format!("[pièce jointe : {}]", attachment.filename));
+
+            let prefix = user_prefix(author_username);
+            let lines = content.lines().filter(|line| !line.is_empty()).map(str::to_string);
+            for line in lines.chain(files) {
+                let line = format!(":{} PRIVMSG {} :{}", prefix, room.name, line);
+                for peer in room.peers.values() {
+                    let _ = peer.tx.send(line.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Identifiant d'une room `{prefix}{id}` (`user:3`, `channel:12`)
+fn room_id(room: &str, prefix: &str) -> Option<i32> {
+    room.strip_prefix(prefix)?.parse().ok()
+}
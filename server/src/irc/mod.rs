@@ -0,0 +1,8 @@
+// Passerelle IRC (RFC 1459/2812) pour les clients classiques (weechat, irssi...)
+// Les canaux sont exposés sous la forme `#serveur/canal`
+
+pub mod protocol;
+pub mod gateway;
+pub mod session;
+
+pub use gateway::IrcGateway;
@@ -0,0 +1,193 @@
+/// Nom annoncé par la passerelle dans les préfixes des réponses
+pub const SERVER_NAME: &str = "chatirc";
+
+/// Longueur maximale d'une ligne IRC acceptée (la RFC impose 512 octets, on tolère un peu plus)
+pub const MAX_LINE_LENGTH: u64 = 4096;
+
+// Réponses numériques (RFC 2812)
+pub const RPL_WELCOME: &str = "001";
+pub const RPL_YOURHOST: &str = "002";
+pub const RPL_CREATED: &str = "003";
+pub const RPL_MYINFO: &str = "004";
+pub const RPL_ENDOFWHO: &str = "315";
+pub const RPL_NOTOPIC: &str = "331";
+pub const RPL_WHOREPLY: &str = "352";
+pub const RPL_NAMREPLY: &str = "353";
+pub const RPL_ENDOFNAMES: &str = "366";
+pub const ERR_NOSUCHNICK: &str = "401";
+pub const ERR_NOSUCHCHANNEL: &str = "403";
+pub const ERR_CANNOTSENDTOCHAN: &str = "404";
+pub const ERR_NORECIPIENT: &str = "411";
+pub const ERR_NOTEXTTOSEND: &str = "412";
+pub const ERR_UNKNOWNCOMMAND: &str = "421";
+pub const ERR_NOMOTD: &str = "422";
+pub const ERR_NONICKNAMEGIVEN: &str = "431";
+pub const ERR_ERRONEUSNICKNAME: &str = "432";
+pub const ERR_NOTONCHANNEL: &str = "442";
+pub const ERR_NOTREGISTERED: &str = "451";
+pub const ERR_NEEDMOREPARAMS: &str = "461";
+pub const ERR_ALREADYREGISTRED: &str = "462";
+pub const ERR_PASSWDMISMATCH: &str = "464";
+pub const ERR_CHANOPRIVSNEEDED: &str = "482";
+
+/// Une ligne IRC décodée : `[@tags] [:prefix] COMMAND param1 param2 :trailing`
+/// Le préfixe envoyé par un client est ignoré, comme le prévoit la RFC, tout comme les tags IRCv3
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrcMessage {
+    pub command: String,
+    pub params: Vec<String>,
+}
+
+impl IrcMessage {
+    /// Décoder une ligne reçue d'un client (sans le CRLF final)
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line.trim_end_matches(['\r', '\n']).trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+
+        if let Some(stripped) = rest.strip_prefix('@') {
+            let (_, remaining) = stripped.split_once(' ')?;
+            rest = remaining.trim_start();
+        }
+
+        if let Some(stripped) = rest.strip_prefix(':') {
+            let (_, remaining) = stripped.split_once(' ')?;
+            rest = remaining.trim_start();
+        }
+
+        let (command, mut rest) = match rest.split_once(' ') {
+            Some((command, remaining)) => (command, remaining),
+            None => (rest, ""),
+        };
+        if command.is_empty() {
+            return None;
+        }
+
+        let mut params = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if rest.is_empty() {
+                break;
+            }
+            if let Some(trailing) = rest.strip_prefix(':') {
+                params.push(trailing.to_string());
+                break;
+            }
+            match rest.split_once(' ') {
+                Some((param, remaining)) => {
+                    params.push(param.to_string());
+                    rest = remaining;
+                }
+                None => {
+                    params.push(rest.to_string());
+                    break;
+                }
+            }
+        }
+
+        Some(Self {
+            command: command.to_ascii_uppercase(),
+            params,
+        })
+    }
+
+    /// Récupérer un paramètre par position
+    pub fn param(&self, index: usize) -> Option<&str> {
+        self.params.get(index).map(String::as_str)
+    }
+}
+
+/// Préfixe complet d'un utilisateur : `nick!user@host`
+pub fn user_prefix(nick: &str) -> String {
+    format!("{}!{}@{}", nick, nick, SERVER_NAME)
+}
+
+/// Construire une réponse numérique destinée à `target`
+pub fn numeric(code: &str, target: &str, params: &[&str], text: &str) -> String {
+    let mut line = format!(":{} {} {}", SERVER_NAME, code, target);
+    for param in params {
+        line.push(' ');
+        line.push_str(param);
+    }
+    line.push_str(" :");
+    line.push_str(text);
+    line
+}
+
+/// Transformer un nom libre (serveur, canal) en segment utilisable dans un nom de canal IRC
+pub fn slugify(name: &str) -> String {
+    name.trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' | '\t' => Some('-'),
+            ',' | '/' | ':' | '\x07' => None,
+            c if c.is_control() => None,
+            c => Some(c.to_lowercase().next().unwrap_or(c)),
+        })
+        .collect()
+}
+
+/// Nom de canal IRC canonique pour un canal d'un serveur : `#serveur/canal`
+pub fn channel_name(server_name: &str, channel_name: &str) -> String {
+    format!("#{}/{}", slugify(server_name), slugify(channel_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> (String, Vec<String>) {
+        let message = IrcMessage::parse(line).unwrap();
+        (message.command, message.params)
+    }
+
+    #[test]
+    fn trailing_parameter_keeps_spaces_and_colons() {
+        let (command, params) = parse("PRIVMSG #team/general :salut : ça va ?\r\n");
+
+        assert_eq!(command, "PRIVMSG");
+        assert_eq!(params, vec!["#team/general", "salut : ça va ?"]);
+    }
+
+    #[test]
+    fn empty_trailing_parameter_is_kept() {
+        assert_eq!(parse("TOPIC #a :").1, vec!["#a", ""]);
+    }
+
+    #[test]
+    fn middle_parameters_are_split_on_repeated_spaces() {
+        let (command, params) = parse("user  alice 0   *  :Alice Martin");
+
+        assert_eq!(command, "USER");
+        assert_eq!(params, vec!["alice", "0", "*", "Alice Martin"]);
+    }
+
+    #[test]
+    fn prefix_and_tags_are_ignored() {
+        let (command, params) = parse(":alice!alice@host JOIN #a,#b");
+        assert_eq!(command, "JOIN");
+        assert_eq!(params, vec!["#a,#b"]);
+
+        let (command, params) = parse("@time=2026-01-01T00:00:00Z;msgid=42 :alice PRIVMSG bob :coucou");
+        assert_eq!(command, "PRIVMSG");
+        assert_eq!(params, vec!["bob", "coucou"]);
+
+        let (command, params) = parse("@label=1 PING :token");
+        assert_eq!(command, "PING");
+        assert_eq!(params, vec!["token"]);
+    }
+
+    #[test]
+    fn incomplete_lines_are_rejected() {
+        assert!(IrcMessage::parse("").is_none());
+        assert!(IrcMessage::parse("   \r\n").is_none());
+        assert!(IrcMessage::parse(":alice").is_none());
+        assert!(IrcMessage::parse("@time=now").is_none());
+    }
+
+    #[test]
+    fn channel_names_are_slugified() {
+        assert_eq!(channel_name(" My Team ", "Annonces, news/Général"), "#my-team/annonces-newsgénéral");
+    }
+}
@@ -0,0 +1,627 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Notify};
+use tracing::{info, warn};
+use crate::errors::AppResult;
+use crate::irc::gateway::{IrcGateway, IrcPeer, IrcSender};
+use crate::irc::protocol::*;
+use crate::models::{Channel, CreateMessageDto, LoginDto, Permissions};
+use crate::utils::verify_token;
+use crate::ws::{SocketEvent, UserInfo};
+
+/// Boucle de vie d'une connexion IRC : lecture des lignes, écriture via un canal mpsc
+pub async fn run(gateway: Arc<IrcGateway>, stream: TcpStream) {
+    let (read_half, mut write_half) = stream.into_split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+    // Tâche d'écriture : toutes les réponses passent par le canal mpsc
+    let writer = tokio::spawn(async move {
+        while let Some(line) = rx.recv().await {
+            let line = format!("{}\r\n", line);
+            if write_half.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+        let _ = write_half.shutdown().await;
+    });
+
+    let mut session = IrcSession::new(gateway, tx);
+    let close = session.close_requested.clone();
+    let mut reader = BufReader::new(read_half);
+    let mut buf = Vec::new();
+    let mut quit_reason = "Connexion perdue".to_string();
+
+    loop {
+        buf.clear();
+        let mut line_reader = (&mut reader).take(MAX_LINE_LENGTH);
+        let read = tokio::select! {
+            read = line_reader.read_until(b'\n', &mut buf) => read,
+            // Session révoquée (déconnexion, changement de mot de passe, compte supprimé)
+            _ = close.notified() => {
+                session.send("ERROR :Closing Link: session révoquée".to_string());
+                quit_reason = "Session révoquée".to_string();
+                break;
+            }
+        };
+        match read {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        if !buf.ends_with(b"\n") && buf.len() as u64 >= MAX_LINE_LENGTH {
+            session.send("ERROR :Ligne trop longue".to_string());
+            quit_reason = "Ligne trop longue".to_string();
+            break;
+        }
+
+        let line = String::from_utf8_lossy(&buf);
+        if let Some(message) = IrcMessage::parse(&line) {
+            if let Some(reason) = session.handle(message).await {
+                quit_reason = reason;
+                break;
+            }
+        }
+    }
+
+    session.close(&quit_reason).await;
+    drop(session);
+    let _ = writer.await;
+}
+
+/// État d'une connexion IRC
+struct IrcSession {
+    id: u64,
+    gateway: Arc<IrcGateway>,
+    tx: IrcSender,
+    nick: Option<String>,
+    user_received: bool,
+    password: Option<String>,
+    identity: Option<UserInfo>,
+
+    /// Canaux rejoints : nom IRC -> channel_id
+    joined: HashMap<String, i32>,
+
+    /// Notifié par la passerelle pour fermer la connexion
+    close_requested: Arc<Notify>,
+}
+
+impl IrcSession {
+    fn new(gateway: Arc<IrcGateway>, tx: IrcSender) -> Self {
+        Self {
+            id: gateway.next_session_id(),
+            gateway,
+            tx,
+            nick: None,
+            user_received: false,
+            password: None,
+            identity: None,
+            joined: HashMap::new(),
+            close_requested: Arc::new(Notify::new()),
+        }
+    }
+
+    fn send(&self, line: String) {
+        let _ = self.tx.send(line);
+    }
+
+    /// Pseudo courant (ou `*` tant que l'enregistrement n'est pas terminé)
+    fn current_nick(&self) -> &str {
+        self.nick.as_deref().unwrap_or("*")
+    }
+
+    fn reply(&self, code: &str, params: &[&str], text: &str) {
+        self.send(numeric(code, self.current_nick(), params, text));
+    }
+
+    /// Traiter une commande. Retourne `Some(raison)` si la connexion doit être fermée.
+    async fn handle(&mut self, message: IrcMessage) -> Option<String> {
+        match message.command.as_str() {
+            "CAP" => self.on_cap(&message),
+            "PASS" => self.on_pass(&message),
+            "NICK" => return self.on_nick(&message).await,
+            "USER" => return self.on_user(&message).await,
+            "PING" => {
+                let token = message.param(0).unwrap_or(SERVER_NAME);
+                self.send(format!(":{} PONG {} :{}", SERVER_NAME, SERVER_NAME, token));
+            }
+            "PONG" => {}
+            "QUIT" => {
+                let reason = message.param(0).unwrap_or("Client Quit").to_string();
+                self.send(format!("ERROR :Closing Link: {}", reason));
+                return Some(reason);
+            }
+            _ if self.identity.is_none() => {
+                self.reply(ERR_NOTREGISTERED, &[], "Vous n'êtes pas enregistré");
+            }
+            "JOIN" => self.on_join(&message).await,
+            "PART" => self.on_part(&message).await,
+            "PRIVMSG" => self.on_privmsg(&message, false).await,
+            "NOTICE" => self.on_privmsg(&message, true).await,
+            "NAMES" => self.on_names(&message).await,
+            "TOPIC" => self.on_topic(&message).await,
+            "WHO" => self.on_who(&message).await,
+            command => {
+                self.reply(ERR_UNKNOWNCOMMAND, &[command], "Commande inconnue");
+            }
+        }
+        None
+    }
+
+    /// Négociation de capacités IRCv3 : aucune n'est proposée
+    fn on_cap(&self, message: &IrcMessage) {
+        if message.param(0).map(|sub| sub.eq_ignore_ascii_case("LS")).unwrap_or(false) {
+            self.send(format!(":{} CAP * LS :", SERVER_NAME));
+        }
+    }
+
+    fn on_pass(&mut self, message: &IrcMessage) {
+        if self.identity.is_some() {
+            self.reply(ERR_ALREADYREGISTRED, &[], "Vous êtes déjà enregistré");
+            return;
+        }
+        match message.param(0) {
+            Some(password) => self.password = Some(password.to_string()),
+            None => self.reply(ERR_NEEDMOREPARAMS, &["PASS"], "Paramètres manquants"),
+        }
+    }
+
+    async fn on_nick(&mut self, message: &IrcMessage) -> Option<String> {
+        let Some(nick) = message.param(0) else {
+            self.reply(ERR_NONICKNAMEGIVEN, &[], "Aucun pseudo fourni");
+            return None;
+        };
+
+        // Le pseudo IRC est toujours le nom d'utilisateur du compte
+        if self.identity.is_some() {
+            self.reply(ERR_ERRONEUSNICKNAME, &[nick], "Le pseudo doit rester votre nom d'utilisateur");
+            return None;
+        }
+
+        self.nick = Some(nick.to_string());
+        self.try_register().await
+    }
+
+    async fn on_user(&mut self, message: &IrcMessage) -> Option<String> {
+        if self.identity.is_some() {
+            self.reply(ERR_ALREADYREGISTRED, &[], "Vous êtes déjà enregistré");
+            return None;
+        }
+        if message.params.len() < 4 {
+            self.reply(ERR_NEEDMOREPARAMS, &["USER"], "Paramètres manquants");
+            return None;
+        }
+
+        self.user_received = true;
+        self.try_register().await
+    }
+
+    /// Terminer l'enregistrement une fois NICK et USER reçus
+    async fn try_register(&mut self) -> Option<String> {
+        let nick = self.nick.clone()?;
+        if !self.user_received {
+            return None;
+        }
+
+        let identity = match self.password.take() {
+            Some(password) => self.authenticate(&nick, &password).await,
+            None => None,
+        };
+
+        let Some(identity) = identity else {
+            self.reply(ERR_PASSWDMISMATCH, &[], "Mot de passe ou token invalide");
+            self.send("ERROR :Closing Link: authentification requise".to_string());
+            return Some("Authentification échouée".to_string());
+        };
+
+        if identity.username != nick {
+            self.send(format!(":{} NICK :{}", user_prefix(&nick), identity.username));
+        }
+        self.nick = Some(identity.username.clone());
+
+        info!("✓ Utilisateur {} authentifié via IRC", identity.username);
+
+        let nick = identity.username.clone();
+        self.gateway.register(self.id, &identity, self.close_requested.clone()).await;
+        self.identity = Some(identity);
+
+        self.reply(RPL_WELCOME, &[], &format!("Bienvenue sur chatIRC, {}", user_prefix(&nick)));
+        self.reply(RPL_YOURHOST, &[], &format!("Votre hôte est {}", SERVER_NAME));
+        self.reply(RPL_CREATED, &[], "Passerelle IRC du backend Chat RTC");
+        self.send(format!(":{} {} {} {} chatirc-0.1 o o", SERVER_NAME, RPL_MYINFO, nick, SERVER_NAME));
+        self.reply(
+            ERR_NOMOTD,
+            &[],
+            "Rejoignez un canal avec /join #serveur/canal",
+        );
+        None
+    }
+
+    /// PASS accepte soit un token JWT, soit le mot de passe du compte correspondant au pseudo
+    async fn authenticate(&self, nick: &str, password: &str) -> Option<UserInfo> {
+        let auth_service = &self.gateway.app_state.auth_service;
+        if let Ok(claims) = verify_token(password) {
+            if auth_service.check_session(&claims).await.is_err() {
+                warn!("❌ Échec d'authentification IRC pour {} : session révoquée", nick);
+                return None;
+            }
+            return Some(UserInfo {
+                user_id: claims.sub,
+                username: claims.username,
+                auth_session_id: Some(claims.sid),
+            });
+        }
+
+        let dto = LoginDto {
+            username: nick.to_string(),
+            password: password.to_string(),
+        };
+        match auth_service.verify_credentials(&dto).await {
+            Ok(user) => Some(UserInfo {
+                user_id: user.id,
+                username: user.username,
+                auth_session_id: None,
+            }),
+            Err(_) => {
+                warn!("❌ Échec d'authentification IRC pour {}", nick);
+                None
+            }
+        }
+    }
+
+    fn user_id(&self) -> i32 {
+        self.identity.as_ref().map(|identity| identity.user_id).unwrap_or_default()
+    }
+
+    /// Résoudre `#serveur/canal` (nom ou ID) vers un canal accessible à l'utilisateur
+    async fn resolve_channel(&self, name: &str) -> AppResult<Option<(Channel, String)>> {
+        let Some((server_part, channel_part)) = name.strip_prefix('#').and_then(|n| n.split_once('/')) else {
+            return Ok(None);
+        };
+        let server_part = slugify(server_part);
+        let channel_part = slugify(channel_part);
+
+        let app_state = &self.gateway.app_state;
+        let servers = app_state.server_service.get_user_servers(self.user_id()).await?;
+
+        // Plusieurs serveurs peuvent porter le même nom : on cherche le canal dans chacun
+        for server in servers
+            .into_iter()
+            .filter(|s| s.id.to_string() == server_part || slugify(&s.name) == server_part)
+        {
+            let channels = app_state
+                .channel_service
+                .get_server_channels(server.id, self.user_id())
+                .await?;
+
+            if let Some(channel) = channels
+                .into_iter()
+                .find(|c| c.id.to_string() == channel_part || slugify(&c.name) == channel_part)
+            {
+                let irc_name = channel_name(&server.name, &channel.name);
+                return Ok(Some((channel, irc_name)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Oublier les canaux dont la passerelle a retiré la session (KICK déjà envoyé)
+    async fn prune_joined(&mut self) {
+        let mut kicked = Vec::new();
+        for (irc_name, channel_id) in &self.joined {
+            if !self.gateway.in_room(*channel_id, self.id).await {
+                kicked.push(irc_name.clone());
+            }
+        }
+        for irc_name in kicked {
+            self.joined.remove(&irc_name);
+        }
+    }
+
+    /// Retrouver un canal déjà rejoint par son nom IRC
+    async fn joined_channel(&mut self, name: &str) -> Option<(i32, String)> {
+        self.prune_joined().await;
+        let lowered = name.to_lowercase();
+        if let Some(channel_id) = self.joined.get(&lowered) {
+            return Some((*channel_id, lowered));
+        }
+
+        // Le client peut utiliser la forme par ID (`#12/3`)
+        match self.resolve_channel(name).await {
+            Ok(Some((channel, irc_name))) if self.joined.contains_key(&irc_name) => {
+                Some((channel.id, irc_name))
+            }
+            _ => None,
+        }
+    }
+
+    async fn on_join(&mut self, message: &IrcMessage) {
+        let Some(targets) = message.param(0) else {
+            self.reply(ERR_NEEDMOREPARAMS, &["JOIN"], "Paramètres manquants");
+            return;
+        };
+
+        self.prune_joined().await;
+
+        // `JOIN 0` : quitter tous les canaux
+        if targets == "0" {
+            let names: Vec<String> = self.joined.keys().cloned().collect();
+            for name in names {
+                self.part(&name, "Left all channels").await;
+            }
+            return;
+        }
+
+        for target in targets.split(',').filter(|t| !t.is_empty()) {
+            let (channel, irc_name) = match self.resolve_channel(target).await {
+                Ok(Some(found)) => found,
+                _ => {
+                    self.reply(ERR_NOSUCHCHANNEL, &[target], "Canal introuvable");
+                    continue;
+                }
+            };
+
+            // Vérifier l'accès au canal comme le fait l'API REST
+            if self
+                .gateway
+                .app_state
+                .channel_service
+                .get_channel(channel.id, self.user_id())
+                .await
+                .is_err()
+            {
+                self.reply(ERR_NOSUCHCHANNEL, &[target], "Canal introuvable");
+                continue;
+            }
+
+            if self.joined.contains_key(&irc_name) {
+                continue;
+            }
+
+            let nick = self.current_nick().to_string();
+            let peer = IrcPeer {
+                user_id: self.user_id(),
+                nick: nick.clone(),
+                tx: self.tx.clone(),
+            };
+            self.gateway.join_room(channel.id, &irc_name, self.id, peer).await;
+            self.joined.insert(irc_name.clone(), channel.id);
+
+            let join_line = format!(":{} JOIN {}", user_prefix(&nick), irc_name);
+            self.gateway.send_to_room(channel.id, &join_line, None).await;
+
+            self.reply(RPL_NOTOPIC, &[&irc_name], "Aucun sujet défini");
+            self.send_names(&channel, &irc_name).await;
+        }
+    }
+
+    async fn on_part(&mut self, message: &IrcMessage) {
+        let Some(targets) = message.param(0) else {
+            self.reply(ERR_NEEDMOREPARAMS, &["PART"], "Paramètres manquants");
+            return;
+        };
+        let reason = message.param(1).unwrap_or("Leaving").to_string();
+
+        for target in targets.split(',').filter(|t| !t.is_empty()) {
+            match self.joined_channel(target).await {
+                Some((_, irc_name)) => self.part(&irc_name, &reason).await,
+                None => self.reply(ERR_NOTONCHANNEL, &[target], "Vous n'êtes pas dans ce canal"),
+            }
+        }
+    }
+
+    async fn part(&mut self, irc_name: &str, reason: &str) {
+        if let Some(channel_id) = self.joined.remove(irc_name) {
+            let line = format!(":{} PART {} :{}", user_prefix(self.current_nick()), irc_name, reason);
+            self.gateway.send_to_room(channel_id, &line, None).await;
+            self.gateway.leave_room(channel_id, self.id).await;
+        }
+    }
+
+    async fn on_privmsg(&mut self, message: &IrcMessage, is_notice: bool) {
+        let command = if is_notice { "NOTICE" } else { "PRIVMSG" };
+        let Some(targets) = message.param(0) else {
+            if !is_notice {
+                self.reply(ERR_NORECIPIENT, &[], &format!("Aucun destinataire ({})", command));
+            }
+            return;
+        };
+        let Some(text) = message.param(1).filter(|text| !text.is_empty()) else {
+            if !is_notice {
+                self.reply(ERR_NOTEXTTOSEND, &[], "Aucun texte à envoyer");
+            }
+            return;
+        };
+
+        // Les requêtes CTCP ne sont pas relayées, sauf ACTION (/me)
+        let content = match text.strip_prefix('\x01') {
+            Some(ctcp) => match ctcp.trim_end_matches('\x01').strip_prefix("ACTION ") {
+                Some(action) => format!("*{}*", action),
+                None => return,
+            },
+            None => text.to_string(),
+        };
+
+        for target in targets.split(',').filter(|t| !t.is_empty()) {
+            if !target.starts_with('#') {
+                if !is_notice {
+                    self.reply(ERR_NOSUCHNICK, &[target], "Les messages privés ne sont pas pris en charge");
+                }
+                continue;
+            }
+
+            let Some((channel_id, irc_name)) = self.joined_channel(target).await else {
+                if !is_notice {
+                    self.reply(ERR_CANNOTSENDTOCHAN, &[target], "Rejoignez le canal avant d'y écrire");
+                }
+                continue;
+            };
+
+            let dto = CreateMessageDto {
+                content: content.clone(),
+                reply_to_id: None,
+                attachment_ids: Vec::new(),
+            };
+            let message = match self
+                .gateway
+                .app_state
+                .message_service
+                .create_message(channel_id, dto, self.user_id())
+                .await
+            {
+                Ok(message) => message,
+                Err(e) => {
+                    if !is_notice {
+                        self.reply(ERR_CANNOTSENDTOCHAN, &[target], &e.to_string());
+                    }
+                    continue;
+                }
+            };
+
+            let nick = self.current_nick().to_string();
+            let mentions = self
+                .gateway
+                .app_state
+                .mention_service
+                .record_mentions(&message)
+                .await;
+
+            // Diffuser aux clients web
+            self.gateway.emit_to_web(
+                channel_id,
+                SocketEvent::NewMessage {
+                    channel_id,
+                    message_id: message.id,
+                    content: message.content.clone(),
+                    author_id: message.author_id,
+                    author_username: nick.clone(),
+                    reply_to_id: None,
+                    attachments: Vec::new(),
+                    nonce: None,
+                    created_at: message.created_at.to_rfc3339(),
+                },
+            ).await;
+            self.gateway.emit_mentions(&message, &nick, &mentions).await;
+
+            // Diffuser aux autres clients IRC (pas d'écho à l'émetteur)
+            let line = format!(":{} {} {} :{}", user_prefix(&nick), command, irc_name, text);
+            self.gateway.send_to_room(channel_id, &line, Some(self.id)).await;
+        }
+    }
+
+    async fn on_names(&mut self, message: &IrcMessage) {
+        let Some(targets) = message.param(0) else {
+            self.reply(RPL_ENDOFNAMES, &["*"], "Fin de la liste /NAMES");
+            return;
+        };
+
+        for target in targets.split(',').filter(|t| !t.is_empty()) {
+            match self.resolve_channel(target).await {
+                Ok(Some((channel, irc_name))) => self.send_names(&channel, &irc_name).await,
+                _ => self.reply(RPL_ENDOFNAMES, &[target], "Fin de la liste /NAMES"),
+            }
+        }
+    }
+
+    /// Envoyer la liste des membres du serveur propriétaire du canal
+    async fn send_names(&self, channel: &Channel, irc_name: &str) {
+        let members = self
+            .gateway
+            .app_state
+            .server_service
+            .get_members(channel.server_id, self.user_id())
+            .await
+            .unwrap_or_default();
+
+        let mut names = Vec::with_capacity(members.len());
+        for member in &members {
+            if self.is_operator(channel, member.user_id).await {
+                names.push(format!("@{}", member.username));
+            } else {
+                names.push(member.username.clone());
+            }
+        }
+
+        // Découper pour rester sous la limite de 512 octets par ligne
+        for chunk in names.chunks(30) {
+            self.reply(RPL_NAMREPLY, &["=", irc_name], &chunk.join(" "));
+        }
+        self.reply(RPL_ENDOFNAMES, &[irc_name], "Fin de la liste /NAMES");
+    }
+
+    async fn on_topic(&mut self, message: &IrcMessage) {
+        let Some(target) = message.param(0) else {
+            self.reply(ERR_NEEDMOREPARAMS, &["TOPIC"], "Paramètres manquants");
+            return;
+        };
+
+        let Some((_, irc_name)) = self.joined_channel(target).await else {
+            self.reply(ERR_NOTONCHANNEL, &[target], "Vous n'êtes pas dans ce canal");
+            return;
+        };
+
+        // Les canaux n'ont pas de sujet : on ne peut que le consulter
+        if message.params.len() > 1 {
+            self.reply(ERR_CHANOPRIVSNEEDED, &[&irc_name], "Les sujets de canal ne sont pas modifiables");
+        } else {
+            self.reply(RPL_NOTOPIC, &[&irc_name], "Aucun sujet défini");
+        }
+    }
+
+    async fn on_who(&mut self, message: &IrcMessage) {
+        let mask = message.param(0).unwrap_or("*").to_string();
+
+        if let Ok(Some((channel, irc_name))) = self.resolve_channel(&mask).await {
+            let members = self
+                .gateway
+                .app_state
+                .server_service
+                .get_members(channel.server_id, self.user_id())
+                .await
+                .unwrap_or_default();
+
+            for member in members {
+                let flags = if self.is_operator(&channel, member.user_id).await {
+                    "H@"
+                } else {
+                    "H"
+                };
+                self.reply(
+                    RPL_WHOREPLY,
+                    &[&irc_name, &member.username, SERVER_NAME, SERVER_NAME, &member.username, flags],
+                    &format!("0 {}", member.username),
+                );
+            }
+        }
+
+        self.reply(RPL_ENDOFWHO, &[&mask], "Fin de la liste /WHO");
+    }
+
+    /// Un membre est opérateur IRC s'il peut gérer le canal
+    async fn is_operator(&self, channel: &Channel, user_id: i32) -> bool {
+        self.gateway
+            .app_state
+            .permission_service
+            .compute(user_id, channel.server_id, Some(channel.id))
+            .await
+            .map(|permissions| permissions.contains(Permissions::MANAGE_CHANNELS))
+            .unwrap_or(false)
+    }
+
+    /// Quitter tous les canaux et prévenir les autres clients IRC
+    async fn close(&mut self, reason: &str) {
+        if self.identity.is_some() {
+            info!("Utilisateur {} déconnecté de l'IRC", self.current_nick());
+            self.gateway.unregister(self.id).await;
+        }
+
+        self.prune_joined().await;
+        let line = format!(":{} QUIT :{}", user_prefix(self.current_nick()), reason);
+        for (_, channel_id) in self.joined.drain() {
+            self.gateway.leave_room(channel_id, self.id).await;
+            self.gateway.send_to_room(channel_id, &line, None).await;
+        }
+    }
+}
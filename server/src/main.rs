@@ -1,22 +1,26 @@
 mod errors;
 mod handlers;
+mod irc;
 mod models;
 mod repositories;
 mod services;
 mod state;
+mod storage;
 mod utils;
 mod ws;
 
 use axum::{
-    routing::{delete, get, post, put},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, patch, post, put},
     Router,
 };
-use socketioxide::{extract::{Data, SocketRef}, SocketIo};
+use socketioxide::{extract::{AckSender, Data, SocketRef}, handler::ConnectHandler, SocketIo};
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{error, info};
 use state::AppState;
 
 #[tokio::main]
@@ -39,17 +43,153 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let user_repo = Arc::new(repositories::UserRepository::new(pool.clone()));
     let server_repo = Arc::new(repositories::ServerRepository::new(pool.clone()));
     let channel_repo = Arc::new(repositories::ChannelRepository::new(pool.clone()));
-    let message_repo = Arc::new(repositories::MessageRepository::new(pool.clone()));    // 5. Initialiser les services
-    let auth_service = Arc::new(services::AuthService::new(user_repo.clone()));
-    let server_service = Arc::new(services::ServerService::new(server_repo.clone()));
+    let message_repo = Arc::new(repositories::MessageRepository::new(pool.clone()));
+    let dm_repo = Arc::new(repositories::DmRepository::new(pool.clone()));
+    let role_repo = Arc::new(repositories::RoleRepository::new(pool.clone()));
+    let audit_repo = Arc::new(repositories::AuditLogRepository::new(pool.clone()));
+    let invitation_repo = Arc::new(repositories::InvitationRepository::new(pool.clone()));
+    let thread_repo = Arc::new(repositories::ThreadRepository::new(pool.clone()));
+    let reaction_repo = Arc::new(repositories::ReactionRepository::new(pool.clone()));
+    let mention_repo = Arc::new(repositories::MentionRepository::new(pool.clone()));
+    let read_state_repo = Arc::new(repositories::ReadStateRepository::new(pool.clone()));
+    let attachment_repo = Arc::new(repositories::AttachmentRepository::new(pool.clone()));
+    let session_repo = Arc::new(repositories::SessionRepository::new(pool.clone()));
+
+    // 5. Initialiser les services
+    // Bus d'événements temps réel (EVENT_BUS) : en mémoire pour une seule instance,
+    // ou Postgres LISTEN/NOTIFY pour plusieurs instances sur la même base
+    let (event_bus, bus_receiver): (Arc<dyn ws::bus::EventBus>, ws::bus::BusReceiver) =
+        match std::env::var("EVENT_BUS").as_deref() {
+            Ok("postgres") => {
+                let (bus, receiver) = ws::bus::PgBus::connect(pool.clone()).await?;
+                info!("✓ Bus d'événements Postgres (LISTEN/NOTIFY) prêt");
+                (Arc::new(bus), receiver)
+            }
+            _ => {
+                let (bus, receiver) = ws::bus::LocalBus::new();
+                (Arc::new(bus), receiver)
+            }
+        };
+    
+    // Reprise de session : RESUME_BUFFER_SIZE événements conservés par session,
+    // pendant RESUME_TIMEOUT_SECONDS après une coupure
+    let resume_buffer_size = std::env::var("RESUME_BUFFER_SIZE")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(500);
+    let resume_timeout_seconds = std::env::var("RESUME_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(120);
+    let sessions = ws::SessionStore::new(resume_buffer_size, Duration::from_secs(resume_timeout_seconds));
+    
+    // Créer le Hub pour gérer les connexions (partagé avec les mentions @here)
+    let hub = Arc::new(ws::Hub::new(event_bus, sessions));
+    let auth_service = Arc::new(services::AuthService::new(
+        user_repo.clone(),
+        session_repo.clone(),
+        attachment_repo.clone(),
+        hub.clone(),
+    ));
+    let permission_service = Arc::new(services::PermissionService::new(
+        server_repo.clone(),
+        role_repo.clone(),
+        channel_repo.clone(),
+    ));
+    let audit_service = Arc::new(services::AuditService::new(
+        audit_repo.clone(),
+        permission_service.clone(),
+    ));
+    let server_service = Arc::new(services::ServerService::new(
+        server_repo.clone(),
+        role_repo.clone(),
+        invitation_repo.clone(),
+        permission_service.clone(),
+        audit_service.clone(),
+    ));
+    let invitation_service = Arc::new(services::InvitationService::new(
+        invitation_repo.clone(),
+        permission_service.clone(),
+        audit_service.clone(),
+    ));
+    let role_service = Arc::new(services::RoleService::new(
+        role_repo.clone(),
+        server_repo.clone(),
+        permission_service.clone(),
+        audit_service.clone(),
+    ));
     let channel_service = Arc::new(services::ChannelService::new(
         channel_repo.clone(),
-        server_service.clone(),
+        role_repo.clone(),
+        permission_service.clone(),
+        audit_service.clone(),
     ));
+    // Nombre maximal de messages épinglés par canal
+    let max_pins_per_channel = std::env::var("MAX_PINS_PER_CHANNEL")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(50);
     let message_service = Arc::new(services::MessageService::new(
         message_repo.clone(),
         channel_repo.clone(),
-        server_service.clone(),
+        thread_repo.clone(),
+        reaction_repo.clone(),
+        attachment_repo.clone(),
+        permission_service.clone(),
+        audit_service.clone(),
+        max_pins_per_channel,
+    ));
+    let thread_service = Arc::new(services::ThreadService::new(
+        thread_repo.clone(),
+        message_repo.clone(),
+        channel_repo.clone(),
+        permission_service.clone(),
+    ));
+    let mention_service = Arc::new(services::MentionService::new(
+        mention_repo.clone(),
+        channel_repo.clone(),
+        permission_service.clone(),
+        hub.clone(),
+    ));
+    let read_state_service = Arc::new(services::ReadStateService::new(
+        read_state_repo.clone(),
+        message_repo.clone(),
+        channel_repo.clone(),
+        permission_service.clone(),
+    ));
+    let search_service = Arc::new(services::SearchService::new(
+        message_repo.clone(),
+        channel_repo.clone(),
+        user_repo.clone(),
+        permission_service.clone(),
+    ));
+
+    // Pièces jointes stockées sur disque (UPLOAD_DIR), taille limitée par fichier
+    let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "uploads".to_string());
+    let max_attachment_size_mb = std::env::var("MAX_ATTACHMENT_SIZE_MB")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(10);
+    let attachment_service = Arc::new(services::AttachmentService::new(
+        attachment_repo.clone(),
+        channel_repo.clone(),
+        message_repo.clone(),
+        permission_service.clone(),
+        Arc::new(storage::LocalStorage::new(upload_dir)),
+        max_attachment_size_mb * 1024 * 1024,
+    ));
+    let dm_service = Arc::new(services::DmService::new(dm_repo.clone(), user_repo.clone()));
+
+    // Présence : passage automatique en inactif après PRESENCE_IDLE_MINUTES sans activité
+    let presence_idle_minutes = std::env::var("PRESENCE_IDLE_MINUTES")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(5);
+    let presence_service = Arc::new(services::PresenceService::new(
+        user_repo.clone(),
+        server_repo.clone(),
+        hub.clone(),
+        Duration::from_secs(presence_idle_minutes * 60),
     ));
 
     // Créer l'AppState avec tous les services
@@ -58,52 +198,232 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         server_service.clone(),
         channel_service.clone(),
         message_service.clone(),
+        dm_service.clone(),
+        permission_service.clone(),
+        role_service.clone(),
+        audit_service.clone(),
+        invitation_service.clone(),
+        thread_service.clone(),
+        mention_service.clone(),
+        read_state_service.clone(),
+        search_service.clone(),
+        attachment_service.clone(),
     );// 6. Initialiser Socket.IO pour le temps réel
     let (socket_layer, io) = SocketIo::new_layer();
     
-    // Créer le Hub pour gérer les connexions
-    let hub = Arc::new(ws::Hub::new());    // Configuration des événements WebSocket
+    // Passerelle IRC pour les clients classiques (weechat, irssi...)
+    let irc_gateway = Arc::new(irc::IrcGateway::new(app_state.clone(), hub.clone()));
+    
+    // Configuration des événements WebSocket
     io.ns("/", {
         let hub = hub.clone();
         let server_service = server_service.clone();
+        let channel_service = channel_service.clone();
+        let read_state_service = read_state_service.clone();
+        let message_service = message_service.clone();
+        let mention_service = mention_service.clone();
+        let irc_gateway = irc_gateway.clone();
+        let presence_service = presence_service.clone();
         
-        move |socket: SocketRef| {            info!("🔌 Nouveau client Socket.IO connecté : {}", socket.id);
-              // Handler pour l'authentification
-            socket.on("authenticate", {
+        move |socket: SocketRef, Data(auth): Data<ws::HandshakeAuth>, io: SocketIo| {            info!("🔌 Nouveau client Socket.IO connecté : {}", socket.id);
+              // Handler pour reprendre une session après une reconnexion
+            socket.on("resume", {
                 let hub = hub.clone();
-                move |socket: SocketRef, data: Data<ws::AuthenticatePayload>| async move {
-                    ws::on_authenticate(socket, data, hub.clone()).await;
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, data: Data<ws::ResumePayload>, io: SocketIo| async move {
+                    ws::on_resume(socket, data, io, hub.clone(), presence_service.clone()).await;
                 }
             });
               // Handler pour rejoindre un serveur
             socket.on("join_server", {
                 let hub = hub.clone();
                 let server_service = server_service.clone();
-                move |socket: SocketRef, data: Data<ws::JoinServerPayload>| async move {
-                    ws::on_join_server(socket, data, hub.clone(), server_service.clone()).await;
+                let channel_service = channel_service.clone();
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, data: Data<ws::JoinServerPayload>, io: SocketIo| async move {
+                    ws::on_join_server(
+                        socket,
+                        data,
+                        io,
+                        hub.clone(),
+                        server_service.clone(),
+                        channel_service.clone(),
+                        presence_service.clone(),
+                    )
+                    .await;
                 }
             });
               // Handler pour quitter un serveur
             socket.on("leave_server", {
                 let hub = hub.clone();
-                move |socket: SocketRef, data: Data<ws::LeaveServerPayload>| async move {
-                    ws::on_leave_server(socket, data, hub.clone()).await;
+                let channel_service = channel_service.clone();
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, data: Data<ws::LeaveServerPayload>, io: SocketIo| async move {
+                    ws::on_leave_server(
+                        socket,
+                        data,
+                        io,
+                        hub.clone(),
+                        channel_service.clone(),
+                        presence_service.clone(),
+                    )
+                    .await;
+                }
+            });
+              // Handlers pour s'abonner / se désabonner d'un canal
+            socket.on("join_channel", {
+                let channel_service = channel_service.clone();
+                move |socket: SocketRef, data: Data<ws::JoinChannelPayload>| async move {
+                    ws::on_join_channel(socket, data, channel_service.clone()).await;
                 }
+            });
+            socket.on("leave_channel", |socket: SocketRef, data: Data<ws::LeaveChannelPayload>| async move {
+                ws::on_leave_channel(socket, data).await;
             });
               // Handler pour "typing"
             socket.on("typing_start", {
                 let hub = hub.clone();
-                move |socket: SocketRef, data: Data<ws::TypingPayload>| async move {
-                    ws::on_typing_start(socket, data, hub.clone()).await;
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, data: Data<ws::TypingPayload>, io: SocketIo| async move {
+                    ws::on_typing_start(socket, data, io, hub.clone(), presence_service.clone()).await;
+                }
+            });
+              // Handler pour envoyer un message (réponse par ack)
+            socket.on("send_message", {
+                let hub = hub.clone();
+                let message_service = message_service.clone();
+                let mention_service = mention_service.clone();
+                let presence_service = presence_service.clone();
+                let irc_gateway = irc_gateway.clone();
+                move |socket: SocketRef, data: Data<ws::SendMessagePayload>, ack: AckSender, io: SocketIo| async move {
+                    ws::on_send_message(
+                        socket,
+                        data,
+                        ack,
+                        io,
+                        hub.clone(),
+                        message_service.clone(),
+                        mention_service.clone(),
+                        presence_service.clone(),
+                        irc_gateway.clone(),
+                    )
+                    .await;
+                }
+            });
+              // Handler pour marquer un canal comme lu
+            socket.on("ack", {
+                let hub = hub.clone();
+                let read_state_service = read_state_service.clone();
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, data: Data<ws::AckPayload>, io: SocketIo| async move {
+                    ws::on_ack(socket, data, io, hub.clone(), read_state_service.clone(), presence_service.clone()).await;
+                }
+            });
+              // Handlers de présence : statut choisi et activité de l'utilisateur
+            socket.on("update_presence", {
+                let hub = hub.clone();
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, data: Data<models::UpdatePresenceDto>, io: SocketIo| async move {
+                    ws::on_update_presence(socket, data, io, hub.clone(), presence_service.clone()).await;
+                }
+            });
+            socket.on("activity", {
+                let hub = hub.clone();
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, io: SocketIo| async move {
+                    ws::on_activity(socket, io, hub.clone(), presence_service.clone()).await;
                 }
             });
               // Handler pour la déconnexion
             socket.on_disconnect({
                 let hub = hub.clone();
-                move |socket: SocketRef| async move {
-                    ws::on_disconnect(socket, hub.clone()).await;
+                let server_service = server_service.clone();
+                let presence_service = presence_service.clone();
+                move |socket: SocketRef, io: SocketIo| {
+                    // Handler synchrone : la session garde les rooms du socket avant qu'il ne les quitte
+                    hub.sessions().detach(&socket);
+                    tokio::spawn(ws::on_disconnect(
+                        socket,
+                        io,
+                        hub.clone(),
+                        server_service.clone(),
+                        presence_service.clone(),
+                    ));
                 }
             });
+              // Ouvrir la session du socket, ou reprendre celle d'avant la reconnexion
+            tokio::spawn(ws::on_connect(socket, auth, io, hub.clone(), presence_service.clone()));
+        }
+    }
+    // Token JWT (et sa session) vérifié dès la connexion : les sockets non authentifiés sont refusés
+    .with({
+        let auth_service = auth_service.clone();
+        move |socket: SocketRef, io: SocketIo, auth: Data<ws::HandshakeAuth>| {
+            ws::authenticate_handshake(socket, io, auth, auth_service.clone())
+        }
+    }));
+
+    // Appliquer aux sockets de cette instance les événements du bus (toutes instances)
+    ws::Dispatcher::new(
+        io.clone(),
+        hub.clone(),
+        channel_service.clone(),
+        presence_service.clone(),
+        irc_gateway.clone(),
+    )
+    .spawn(bus_receiver);
+
+    // Écoute de la passerelle IRC
+    let irc_port = std::env::var("IRC_PORT").unwrap_or_else(|_| "6667".to_string());
+    let irc_addr = format!("0.0.0.0:{}", irc_port);
+    let irc_listener = TcpListener::bind(&irc_addr).await?;
+    info!("💬 Passerelle IRC lancée sur {}", irc_addr);
+    tokio::spawn(irc_gateway.clone().serve(irc_listener));
+
+    // Purger toutes les heures les pièces jointes jamais rattachées à un message
+    let purge_service = attachment_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_service.purge_pending().await {
+                error!("Échec de la purge des pièces jointes: {}", e);
+            }
+        }
+    });
+
+    // Purger toutes les heures les sessions de connexion expirées ou révoquées
+    let session_purge_service = auth_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = session_purge_service.purge_sessions().await {
+                error!("Échec de la purge des sessions: {}", e);
+            }
+        }
+    });
+
+    // Passer en inactif les utilisateurs sans activité récente
+    let idle_io = io.clone();
+    let idle_hub = hub.clone();
+    let idle_presence_service = presence_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            let effects = idle_presence_service.mark_idle().await;
+            ws::notify_hub_effects(&idle_io, &idle_hub, &idle_presence_service, effects).await;
+        }
+    });
+
+    // Oublier les sessions coupées qui n'ont pas été reprises à temps
+    let session_hub = hub.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            session_hub.sessions().expire();
         }
     });
 
@@ -119,7 +439,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Routes d'authentification
         .route("/auth/signup", post(handlers::signup))
         .route("/auth/login", post(handlers::login))
+        .route("/auth/refresh", post(handlers::refresh))
+        .route("/auth/logout", post(handlers::logout))
         .route("/auth/me", get(handlers::get_me))
+        .route("/auth/sessions", get(handlers::list_sessions))
+        .route("/auth/sessions/:session_id", delete(handlers::revoke_session))
+        .route("/users/me", patch(handlers::update_me))
+        .route("/users/me", delete(handlers::delete_me))
+        .route("/users/me/mentions", get(handlers::list_mentions))
+        .route("/users/me/mentions/:message_id", delete(handlers::dismiss_mention))
 
        
         
@@ -132,8 +460,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/servers/:id", put(handlers::update_server))
         .route("/servers/:id", delete(handlers::delete_server))
         .route("/servers/:id/leave", delete(handlers::leave_server))
+        .route("/servers/:id/transfer-ownership", post(handlers::transfer_ownership))
         .route("/servers/:id/members", get(handlers::list_members))
-        .route("/servers/:server_id/members/:user_id", put(handlers::update_member_role))
+        .route("/servers/:server_id/members/:user_id", put(handlers::update_member_roles))
+        .route("/servers/:server_id/members/:user_id/kick", post(handlers::kick_member))
+        .route("/servers/:server_id/members/:user_id/ban", post(handlers::ban_member))
+        .route("/servers/:server_id/members/:user_id/timeout", post(handlers::timeout_member))
+        .route("/servers/:server_id/members/:user_id/timeout", delete(handlers::remove_timeout))
+        .route("/servers/:server_id/bans", get(handlers::list_bans))
+        .route("/servers/:server_id/bans/:user_id", delete(handlers::unban_member))
+        .route("/servers/:server_id/audit-log", get(handlers::get_audit_log))
+        .route("/servers/:server_id/invites", get(handlers::list_invites))
+        .route("/servers/:server_id/invites", post(handlers::create_invite))
+        .route("/servers/:server_id/invites/:code", delete(handlers::revoke_invite))
+        .route("/invites/:code", get(handlers::preview_invite))
+        
+        // Routes des rôles
+        .route("/servers/:server_id/roles", get(handlers::list_roles))
+        .route("/servers/:server_id/roles", post(handlers::create_role))
+        .route("/servers/:server_id/roles/:role_id", put(handlers::update_role))
+        .route("/servers/:server_id/roles/:role_id", delete(handlers::delete_role))
         
         // Routes des canaux
         .route("/servers/:server_id/channels", post(handlers::create_channel))
@@ -141,17 +487,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/channels/:id", get(handlers::get_channel))
         .route("/channels/:id", put(handlers::update_channel))
         .route("/channels/:id", delete(handlers::delete_channel))
+        .route("/channels/:id/ack", post(handlers::ack_channel))
+        .route("/channels/:id/pins", get(handlers::list_pins))
+        .route("/channels/:id/pins/:message_id", put(handlers::pin_message))
+        .route("/channels/:id/pins/:message_id", delete(handlers::unpin_message))
+        .route("/channels/:id/permissions", get(handlers::list_channel_overwrites))
+        .route("/channels/:id/permissions/roles/:role_id", put(handlers::put_role_overwrite))
+        .route("/channels/:id/permissions/roles/:role_id", delete(handlers::delete_role_overwrite))
+        .route("/channels/:id/permissions/members/:user_id", put(handlers::put_member_overwrite))
+        .route("/channels/:id/permissions/members/:user_id", delete(handlers::delete_member_overwrite))
         
         // Routes des messages
         .route("/channels/:channel_id/messages", post(handlers::create_message))
         .route("/channels/:channel_id/messages", get(handlers::get_messages))
+        .route(
+            "/channels/:channel_id/attachments",
+            // Tous les fichiers d'un envoi, plus l'enveloppe multipart
+            post(handlers::upload_attachments).layer(DefaultBodyLimit::max(
+                attachment_service.max_size() * services::attachment_service::MAX_ATTACHMENTS_PER_MESSAGE
+                    + 1024 * 1024,
+            )),
+        )
+        .route("/attachments/:id", get(handlers::download_attachment))
+        .route("/attachments/:id/thumbnail", get(handlers::download_thumbnail))
+        .route("/servers/:server_id/messages/search", get(handlers::search_messages))
+        .route("/messages/:id", patch(handlers::update_message))
         .route("/messages/:id", delete(handlers::delete_message))
+        .route("/messages/:id/revisions", get(handlers::get_message_revisions))
+        .route("/messages/:id/reactions/:emoji", put(handlers::add_reaction))
+        .route("/messages/:id/reactions/:emoji", delete(handlers::remove_reaction))
+        
+        // Routes des fils de discussion
+        .route("/messages/:id/threads", post(handlers::create_thread))
+        .route("/channels/:id/threads", get(handlers::list_threads))
+        .route("/threads/:id", get(handlers::get_thread))
+        .route("/threads/:id/messages", post(handlers::create_thread_message))
+        .route("/threads/:id/messages", get(handlers::get_thread_messages))
+        .route("/threads/:id/members", get(handlers::list_thread_members))
+        .route("/threads/:id/members/@me", put(handlers::join_thread))
+        .route("/threads/:id/members/@me", delete(handlers::leave_thread))
+        
+        // Routes des messages privés
+        .route("/dms", post(handlers::open_dm))
+        .route("/dms", get(handlers::list_dms))
+        .route("/dms/:id", get(handlers::get_dm))
+        .route("/dms/:id/messages", post(handlers::send_dm_message))
+        .route("/dms/:id/messages", get(handlers::get_dm_messages))
         
         // Injection de l'état global avec tous les services
         .with_state(app_state)
         
-        // Ajouter SocketIo comme Extension pour l'utiliser dans les handlers
-        .layer(axum::Extension(io.clone()))
+        // Ajouter le Hub et la passerelle IRC comme Extensions pour les handlers
+        .layer(axum::Extension(irc_gateway.clone()))
+        .layer(axum::Extension(hub.clone()))
         
         // Middlewares
         .layer(cors)
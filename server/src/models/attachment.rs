@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Fichier joint à un message
+///
+/// Une pièce jointe est d'abord envoyée seule (`message_id` NULL), puis rattachée
+/// au message qui la référence dans `attachment_ids`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Attachment {
+    pub id: i32,
+    pub message_id: Option<i32>,
+    pub channel_id: i32,
+    /// NULL si le compte de l'expéditeur a été supprimé
+    pub uploader_id: Option<i32>,
+    pub filename: String,
+    pub content_type: String,
+    /// Taille en octets
+    pub size: i64,
+    /// Dimensions (images uniquement)
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Empreinte SHA-256 du contenu (hexadécimal)
+    pub checksum: String,
+    /// Une miniature est disponible sur `GET /attachments/:id/thumbnail`
+    pub has_thumbnail: bool,
+    pub created_at: DateTime<Utc>,
+    /// Emplacements dans le stockage (jamais exposés)
+    #[serde(skip)]
+    pub storage_key: String,
+    #[serde(skip)]
+    pub thumbnail_key: Option<String>,
+}
+
+/// Fichier reçu dans une requête multipart, avant traitement
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    pub filename: String,
+    /// Type annoncé par le client (remplacé par le format détecté pour les images)
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Pièce jointe à enregistrer, une fois le fichier stocké
+#[derive(Debug, Clone)]
+pub struct NewAttachment {
+    pub channel_id: i32,
+    pub uploader_id: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub checksum: String,
+    pub storage_key: String,
+    pub thumbnail_key: Option<String>,
+}
+
+/// Contenu d'une pièce jointe (ou de sa miniature) à renvoyer au client
+#[derive(Debug, Clone)]
+pub struct AttachmentContent {
+    pub attachment: Attachment,
+    /// Type du contenu renvoyé (celui de la miniature le cas échéant)
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
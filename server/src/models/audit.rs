@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+/// Actions d'administration enregistrées dans le journal d'audit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    ServerUpdate,
+    OwnershipTransfer,
+    ChannelCreate,
+    ChannelUpdate,
+    ChannelDelete,
+    ChannelOverwriteUpdate,
+    ChannelOverwriteDelete,
+    RoleCreate,
+    RoleUpdate,
+    RoleDelete,
+    MemberRolesUpdate,
+    MemberKick,
+    MemberBan,
+    MemberUnban,
+    MemberTimeout,
+    MessageDelete,
+    MessagePin,
+    MessageUnpin,
+    InviteCreate,
+    InviteDelete,
+}
+
+impl AuditAction {
+    /// Nom stocké en base (identique à la sérialisation JSON)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::ServerUpdate => "server_update",
+            AuditAction::OwnershipTransfer => "ownership_transfer",
+            AuditAction::ChannelCreate => "channel_create",
+            AuditAction::ChannelUpdate => "channel_update",
+            AuditAction::ChannelDelete => "channel_delete",
+            AuditAction::ChannelOverwriteUpdate => "channel_overwrite_update",
+            AuditAction::ChannelOverwriteDelete => "channel_overwrite_delete",
+            AuditAction::RoleCreate => "role_create",
+            AuditAction::RoleUpdate => "role_update",
+            AuditAction::RoleDelete => "role_delete",
+            AuditAction::MemberRolesUpdate => "member_roles_update",
+            AuditAction::MemberKick => "member_kick",
+            AuditAction::MemberBan => "member_ban",
+            AuditAction::MemberUnban => "member_unban",
+            AuditAction::MemberTimeout => "member_timeout",
+            AuditAction::MessageDelete => "message_delete",
+            AuditAction::MessagePin => "message_pin",
+            AuditAction::MessageUnpin => "message_unpin",
+            AuditAction::InviteCreate => "invite_create",
+            AuditAction::InviteDelete => "invite_delete",
+        }
+    }
+
+    /// Type de la cible de l'action
+    pub fn target_type(self) -> &'static str {
+        match self {
+            AuditAction::ServerUpdate => "server",
+            AuditAction::ChannelCreate
+            | AuditAction::ChannelUpdate
+            | AuditAction::ChannelDelete
+            | AuditAction::ChannelOverwriteUpdate
+            | AuditAction::ChannelOverwriteDelete => "channel",
+            AuditAction::RoleCreate | AuditAction::RoleUpdate | AuditAction::RoleDelete => "role",
+            AuditAction::OwnershipTransfer
+            | AuditAction::MemberRolesUpdate
+            | AuditAction::MemberKick
+            | AuditAction::MemberBan
+            | AuditAction::MemberUnban
+            | AuditAction::MemberTimeout => "user",
+            AuditAction::MessageDelete | AuditAction::MessagePin | AuditAction::MessageUnpin => "message",
+            AuditAction::InviteCreate | AuditAction::InviteDelete => "invite",
+        }
+    }
+}
+
+/// Entrée du journal d'audit, avec le nom de l'auteur
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub server_id: i32,
+    /// NULL si le compte de l'auteur a été supprimé
+    pub actor_id: Option<i32>,
+    pub actor_username: Option<String>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    /// État avant / après l'action (JSON libre)
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Entrée à enregistrer dans le journal d'audit
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub server_id: i32,
+    pub actor_id: i32,
+    pub action: AuditAction,
+    pub target_id: Option<i32>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub reason: Option<String>,
+}
+
+impl NewAuditEntry {
+    pub fn new(server_id: i32, actor_id: i32, action: AuditAction, target_id: Option<i32>) -> Self {
+        Self {
+            server_id,
+            actor_id,
+            action,
+            target_id,
+            before: None,
+            after: None,
+            reason: None,
+        }
+    }
+
+    /// État de la cible avant l'action (`None` sérialisé n'est pas conservé)
+    pub fn before(mut self, before: impl Serialize) -> Self {
+        self.before = to_snapshot(before);
+        self
+    }
+
+    /// État de la cible après l'action
+    pub fn after(mut self, after: impl Serialize) -> Self {
+        self.after = to_snapshot(after);
+        self
+    }
+
+    /// Motif donné par l'auteur
+    pub fn reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason;
+        self
+    }
+}
+
+fn to_snapshot(value: impl Serialize) -> Option<Value> {
+    serde_json::to_value(value).ok().filter(|value| !value.is_null())
+}
+
+/// Filtres et curseur du journal d'audit
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditLogQuery {
+    pub action: Option<AuditAction>,
+    pub actor_id: Option<i32>,
+    /// Curseur : ne retourner que les entrées d'id strictement inférieur
+    pub before: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+/// Page du journal d'audit (du plus récent au plus ancien)
+#[derive(Debug, Serialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditLogEntry>,
+    /// Curseur à passer dans `before` pour la page suivante (absent en fin de journal)
+    pub next_cursor: Option<i32>,
+}
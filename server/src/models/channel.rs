@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::models::Permissions;
 
 /// Représente un canal dans un serveur
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -31,3 +32,23 @@ fn default_channel_type() -> String {
 pub struct UpdateChannelDto {
     pub name: String,
 }
+
+/// Surcharge de permissions d'un canal, pour un rôle ou pour un membre
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PermissionOverwrite {
+    pub id: i32,
+    pub channel_id: i32,
+    pub role_id: Option<i32>,
+    pub user_id: Option<i32>,
+    pub allow: Permissions,
+    pub deny: Permissions,
+}
+
+/// DTO pour définir une surcharge de permissions
+#[derive(Debug, Deserialize)]
+pub struct PermissionOverwriteDto {
+    #[serde(default)]
+    pub allow: Permissions,
+    #[serde(default)]
+    pub deny: Permissions,
+}
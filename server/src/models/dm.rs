@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Représente une conversation privée (1:1 ou groupe) hors des serveurs
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DmConversation {
+    pub id: i32,
+    pub name: Option<String>,
+    pub is_group: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Participant d'une conversation privée
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DmParticipant {
+    pub user_id: i32,
+    pub username: String,
+}
+
+/// Conversation avec la liste de ses participants
+#[derive(Debug, Clone, Serialize)]
+pub struct DmConversationDetails {
+    #[serde(flatten)]
+    pub conversation: DmConversation,
+    pub participants: Vec<DmParticipant>,
+}
+
+/// DTO pour ouvrir une conversation privée
+#[derive(Debug, Deserialize)]
+pub struct OpenDmDto {
+    /// Destinataires (l'utilisateur courant est ajouté automatiquement)
+    pub user_ids: Vec<i32>,
+    pub name: Option<String>,
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Invitation à rejoindre un serveur
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Invitation {
+    pub id: i32,
+    pub code: String,
+    pub server_id: i32,
+    /// NULL si le compte du créateur a été supprimé
+    pub creator_id: Option<i32>,
+    /// Nombre maximal d'utilisations (NULL = illimité)
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    /// Date d'expiration (NULL = permanente)
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Adhésion temporaire : retirée à la déconnexion sans rôle attribué
+    pub temporary: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Invitation {
+    /// L'invitation peut-elle encore être utilisée ?
+    pub fn is_usable(&self) -> bool {
+        let expired = matches!(self.expires_at, Some(expires_at) if expires_at <= Utc::now());
+        let exhausted = matches!(self.max_uses, Some(max_uses) if self.uses >= max_uses);
+        !expired && !exhausted
+    }
+}
+
+/// Résultat de la consommation d'une invitation
+#[derive(Debug)]
+pub enum InvitationUse {
+    /// Le membre a été ajouté et une utilisation décomptée
+    Joined,
+    /// L'utilisateur était déjà membre : aucune utilisation décomptée
+    AlreadyMember,
+    /// Invitation expirée ou épuisée
+    Unavailable,
+}
+
+/// DTO pour la création d'une invitation
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateInvitationDto {
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_uses: Option<i32>,
+    #[serde(default)]
+    pub temporary: bool,
+}
+
+/// Aperçu public d'une invitation (sans authentification)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct InvitationPreview {
+    pub code: String,
+    pub server_id: i32,
+    pub server_name: String,
+    pub member_count: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub temporary: bool,
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Raison pour laquelle un utilisateur est mentionné, de la plus directe à la plus large
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionKind {
+    User,
+    Role,
+    Here,
+    Everyone,
+}
+
+impl MentionKind {
+    /// Nom stocké en base (identique à la sérialisation JSON)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MentionKind::User => "user",
+            MentionKind::Role => "role",
+            MentionKind::Here => "here",
+            MentionKind::Everyone => "everyone",
+        }
+    }
+}
+
+/// Mention non vue, avec de quoi l'afficher dans la boîte de réception
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Mention {
+    pub message_id: i32,
+    pub server_id: i32,
+    pub server_name: String,
+    pub channel_id: i32,
+    pub channel_name: String,
+    pub thread_id: Option<i32>,
+    pub author_id: i32,
+    pub author_username: String,
+    pub content: String,
+    pub kind: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Curseur de la boîte de réception des mentions
+#[derive(Debug, Default, Deserialize)]
+pub struct MentionQuery {
+    /// Ne retourner que les mentions de messages d'id strictement inférieur
+    pub before: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+/// Utilisateurs notifiés par un message
+#[derive(Debug, Clone, Default)]
+pub struct MessageMentions {
+    pub server_id: i32,
+    pub recipients: Vec<(i32, MentionKind)>,
+}
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
+use crate::models::{Attachment, ReactionCount};
 
 /// Représente un message dans un canal
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -12,12 +14,54 @@ pub struct Message {
     pub is_deleted: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Message auquel celui-ci répond
+    pub reply_to_id: Option<i32>,
+    /// Fil de discussion auquel appartient le message (NULL = canal)
+    pub thread_id: Option<i32>,
+    /// Date d'épinglage dans le canal (NULL = non épinglé)
+    pub pinned_at: Option<DateTime<Utc>>,
+    /// Pièces jointes (renseignées par le service, absentes de la table)
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
 /// DTO pour la création d'un message
 #[derive(Debug, Deserialize)]
 pub struct CreateMessageDto {
     pub content: String,
+    /// Répondre à un message du même canal (ou du même fil)
+    #[serde(default)]
+    pub reply_to_id: Option<i32>,
+    /// Pièces jointes envoyées au préalable sur `POST /channels/:id/attachments`
+    #[serde(default)]
+    pub attachment_ids: Vec<i32>,
+}
+
+/// Aperçu du message auquel on répond
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReference {
+    pub id: i32,
+    pub author_id: i32,
+    pub author_username: String,
+    /// Début du contenu (vide si le message a été supprimé)
+    pub snippet: String,
+    pub is_deleted: bool,
+}
+
+/// DTO pour la modification d'un message
+#[derive(Debug, Deserialize)]
+pub struct UpdateMessageDto {
+    pub content: String,
+}
+
+/// Ancienne version d'un message, conservée à chaque modification
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MessageRevision {
+    pub id: i32,
+    pub message_id: i32,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
 }
 
 /// Message avec les détails de l'auteur
@@ -31,4 +75,70 @@ pub struct MessageWithAuthor {
     pub is_deleted: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
+    // Absents des messages privés
+    #[sqlx(default)]
+    pub reply_to_id: Option<i32>,
+    #[sqlx(default)]
+    pub reply_to: Option<Json<MessageReference>>,
+    #[sqlx(default)]
+    pub thread_id: Option<i32>,
+    #[sqlx(default)]
+    pub reactions: Json<Vec<ReactionCount>>,
+    #[sqlx(default)]
+    pub pinned_at: Option<DateTime<Utc>>,
+    #[sqlx(default)]
+    pub attachments: Json<Vec<Attachment>>,
+}
+
+/// Pagination de l'historique par curseur (au plus un de `before` / `after` / `around`)
+#[derive(Debug, Default, Deserialize)]
+pub struct MessageQuery {
+    /// Messages d'id strictement inférieur (défilement vers le passé)
+    pub before: Option<i32>,
+    /// Messages d'id strictement supérieur (défilement vers le présent)
+    pub after: Option<i32>,
+    /// Messages autour de celui-ci, inclus (« aller au message »)
+    pub around: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+impl MessageQuery {
+    /// Curseur demandé, `None` si plusieurs paramètres sont combinés
+    pub fn cursor(&self) -> Option<MessageCursor> {
+        match (self.before, self.after, self.around) {
+            (None, None, None) => Some(MessageCursor::Latest),
+            (Some(id), None, None) => Some(MessageCursor::Before(id)),
+            (None, Some(id), None) => Some(MessageCursor::After(id)),
+            (None, None, Some(id)) => Some(MessageCursor::Around(id)),
+            _ => None,
+        }
+    }
+}
+
+/// Résultat d'une demande d'épinglage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinOutcome {
+    Pinned(DateTime<Utc>),
+    AlreadyPinned,
+    /// Le canal a atteint le nombre maximal de messages épinglés
+    LimitReached,
+}
+
+/// Position d'une page d'historique
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageCursor {
+    Latest,
+    Before(i32),
+    After(i32),
+    Around(i32),
+}
+
+impl MessageCursor {
+    /// Message de référence du curseur
+    pub fn message_id(self) -> Option<i32> {
+        match self {
+            MessageCursor::Latest => None,
+            MessageCursor::Before(id) | MessageCursor::After(id) | MessageCursor::Around(id) => Some(id),
+        }
+    }
 }
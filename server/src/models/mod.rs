@@ -3,9 +3,33 @@ pub mod user;
 pub mod server;
 pub mod channel;
 pub mod message;
+pub mod dm;
+pub mod role;
+pub mod audit;
+pub mod invitation;
+pub mod thread;
+pub mod reaction;
+pub mod mention;
+pub mod read_state;
+pub mod search;
+pub mod attachment;
+pub mod presence;
+pub mod session;
 
 // Re-exports pour faciliter l'usage
-pub use user::{User, UserRole, CreateUserDto, LoginDto, AuthResponse, UpdateUserDto};
-pub use server::{Server, CreateServerDto, JoinServerDto, ServerMember, ServerMemberDetails};
-pub use channel::{Channel, CreateChannelDto, UpdateChannelDto};
-pub use message::{Message, CreateMessageDto, MessageWithAuthor};
+pub use user::{User, CreateUserDto, LoginDto, AuthResponse, UpdateUserDto, DeleteAccountDto, OwnedServersAction};
+pub use session::{Session, SessionInfo, RefreshTokenDto};
+pub use server::{Server, CreateServerDto, JoinServerDto, ServerMember, ServerMemberDetails, ServerBan, KickMemberDto, BanMemberDto, TimeoutMemberDto, TransferOwnershipDto};
+pub use channel::{Channel, CreateChannelDto, UpdateChannelDto, PermissionOverwrite, PermissionOverwriteDto};
+pub use message::{Message, CreateMessageDto, UpdateMessageDto, MessageRevision, MessageWithAuthor, MessageQuery, MessageCursor, PinOutcome};
+pub use dm::{DmConversation, DmParticipant, DmConversationDetails, OpenDmDto};
+pub use role::{Permissions, Role, CreateRoleDto, UpdateRoleDto, UpdateMemberRolesDto};
+pub use audit::{AuditAction, AuditLogEntry, NewAuditEntry, AuditLogQuery, AuditLogPage};
+pub use invitation::{Invitation, CreateInvitationDto, InvitationPreview, InvitationUse};
+pub use thread::{Thread, CreateThreadDto, ThreadMember};
+pub use reaction::{ReactionCount, ReactionChange};
+pub use mention::{Mention, MentionKind, MentionQuery, MessageMentions};
+pub use read_state::{ChannelReadState, AckChannelDto, ChannelWithReadState, ServerWithReadState};
+pub use search::{SearchQuery, HasFilter, MessageSearch, SearchHit, SearchResults};
+pub use attachment::{Attachment, AttachmentContent, UploadedFile, NewAttachment};
+pub use presence::{Presence, PresenceChange, PresenceSettings, PresenceSettingsRow, PresenceStatus, UpdatePresenceDto};
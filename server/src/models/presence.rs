@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Statut de présence d'un utilisateur
+///
+/// `offline` n'est jamais choisi : il est déduit de l'absence de connexion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    #[default]
+    Online,
+    Idle,
+    Dnd,
+    Invisible,
+    Offline,
+}
+
+impl PresenceStatus {
+    /// Nom stocké en base (identique à la sérialisation JSON)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PresenceStatus::Online => "online",
+            PresenceStatus::Idle => "idle",
+            PresenceStatus::Dnd => "dnd",
+            PresenceStatus::Invisible => "invisible",
+            PresenceStatus::Offline => "offline",
+        }
+    }
+
+    /// Statut lu en base (`online` pour une valeur inconnue)
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "idle" => PresenceStatus::Idle,
+            "dnd" => PresenceStatus::Dnd,
+            "invisible" => PresenceStatus::Invisible,
+            _ => PresenceStatus::Online,
+        }
+    }
+}
+
+/// Statut choisi par l'utilisateur, conservé d'une connexion à l'autre
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceSettings {
+    pub status: PresenceStatus,
+    pub custom_status: Option<String>,
+}
+
+/// Ligne `users` (presence_status, custom_status)
+#[derive(Debug, Clone, FromRow)]
+pub struct PresenceSettingsRow {
+    pub presence_status: String,
+    pub custom_status: Option<String>,
+}
+
+impl From<PresenceSettingsRow> for PresenceSettings {
+    fn from(row: PresenceSettingsRow) -> Self {
+        Self {
+            status: PresenceStatus::from_db(&row.presence_status),
+            custom_status: row.custom_status,
+        }
+    }
+}
+
+/// Présence d'un utilisateur, tous appareils confondus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+    pub user_id: i32,
+    pub status: PresenceStatus,
+    pub custom_status: Option<String>,
+}
+
+impl Presence {
+    /// Utilisateur sans aucune connexion
+    pub fn offline(user_id: i32) -> Self {
+        Self {
+            user_id,
+            status: PresenceStatus::Offline,
+            custom_status: None,
+        }
+    }
+
+    /// Présence telle que la voient les autres : un utilisateur invisible paraît hors ligne
+    pub fn public(&self) -> Self {
+        match self.status {
+            PresenceStatus::Invisible | PresenceStatus::Offline => Self::offline(self.user_id),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Changement de présence d'un utilisateur (vue de l'utilisateur lui-même)
+#[derive(Debug, Clone)]
+pub struct PresenceChange {
+    pub previous: Presence,
+    pub current: Presence,
+}
+
+impl PresenceChange {
+    /// Le changement est-il visible des autres membres ?
+    pub fn is_public(&self) -> bool {
+        self.previous.public() != self.current.public()
+    }
+}
+
+/// DTO pour changer de statut (événement `update_presence`)
+///
+/// Champ absent : inchangé. `custom_status` vide : statut personnalisé retiré.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdatePresenceDto {
+    pub status: Option<PresenceStatus>,
+    pub custom_status: Option<String>,
+}
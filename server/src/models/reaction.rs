@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// Réactions d'un message, agrégées par emoji
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: i64,
+    /// L'utilisateur courant a réagi avec cet emoji
+    pub me: bool,
+}
+
+/// Réaction ajoutée ou retirée, avec le nouveau total pour l'emoji
+#[derive(Debug, Clone)]
+pub struct ReactionChange {
+    pub channel_id: i32,
+    pub message_id: i32,
+    pub emoji: String,
+    pub count: i64,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use crate::models::{Channel, Server};
+
+/// État de lecture d'un canal pour un utilisateur
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChannelReadState {
+    pub channel_id: i32,
+    /// NULL si l'utilisateur n'a jamais lu le canal
+    pub last_read_message_id: Option<i32>,
+    /// Messages des autres membres postés après le dernier message lu
+    pub unread_count: i64,
+    /// Mentions non vues dans le canal
+    pub mention_count: i64,
+}
+
+/// DTO pour marquer un canal comme lu (dernier message du canal par défaut)
+#[derive(Debug, Default, Deserialize)]
+pub struct AckChannelDto {
+    pub message_id: Option<i32>,
+}
+
+/// Canal avec son état de lecture
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelWithReadState {
+    #[serde(flatten)]
+    pub channel: Channel,
+    pub last_read_message_id: Option<i32>,
+    pub unread_count: i64,
+    pub mention_count: i64,
+}
+
+/// Serveur avec le total des non-lus de ses canaux visibles
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerWithReadState {
+    #[serde(flatten)]
+    pub server: Server,
+    pub unread_count: i64,
+    pub mention_count: i64,
+}
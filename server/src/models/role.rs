@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::ops::{BitAnd, BitOr, BitOrAssign, Not};
+
+/// Permissions d'un rôle, stockées sous forme de champ de bits (BIGINT)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(transparent)]
+#[serde(transparent)]
+pub struct Permissions(pub i64);
+
+impl Permissions {
+    pub const NONE: Self = Self(0);
+    pub const VIEW_CHANNELS: Self = Self(1 << 0);
+    pub const SEND_MESSAGES: Self = Self(1 << 1);
+    pub const READ_MESSAGE_HISTORY: Self = Self(1 << 2);
+    pub const MENTION_EVERYONE: Self = Self(1 << 3);
+    pub const MANAGE_MESSAGES: Self = Self(1 << 4);
+    pub const MANAGE_CHANNELS: Self = Self(1 << 5);
+    pub const MANAGE_SERVER: Self = Self(1 << 6);
+    pub const MANAGE_ROLES: Self = Self(1 << 7);
+    pub const KICK_MEMBERS: Self = Self(1 << 8);
+    pub const BAN_MEMBERS: Self = Self(1 << 9);
+    /// Accorde toutes les permissions
+    pub const ADMINISTRATOR: Self = Self(1 << 10);
+    pub const VIEW_AUDIT_LOG: Self = Self(1 << 11);
+    pub const CREATE_INVITE: Self = Self(1 << 12);
+
+    /// Permissions du rôle @everyone à la création d'un serveur
+    pub const DEFAULT: Self = Self(
+        Self::VIEW_CHANNELS.0
+            | Self::SEND_MESSAGES.0
+            | Self::READ_MESSAGE_HISTORY.0
+            | Self::CREATE_INVITE.0,
+    );
+
+    /// Nom de chaque permission (utilisé dans les messages d'erreur)
+    const NAMES: [(Self, &'static str); 13] = [
+        (Self::VIEW_CHANNELS, "VIEW_CHANNELS"),
+        (Self::SEND_MESSAGES, "SEND_MESSAGES"),
+        (Self::READ_MESSAGE_HISTORY, "READ_MESSAGE_HISTORY"),
+        (Self::MENTION_EVERYONE, "MENTION_EVERYONE"),
+        (Self::MANAGE_MESSAGES, "MANAGE_MESSAGES"),
+        (Self::MANAGE_CHANNELS, "MANAGE_CHANNELS"),
+        (Self::MANAGE_SERVER, "MANAGE_SERVER"),
+        (Self::MANAGE_ROLES, "MANAGE_ROLES"),
+        (Self::KICK_MEMBERS, "KICK_MEMBERS"),
+        (Self::BAN_MEMBERS, "BAN_MEMBERS"),
+        (Self::ADMINISTRATOR, "ADMINISTRATOR"),
+        (Self::VIEW_AUDIT_LOG, "VIEW_AUDIT_LOG"),
+        (Self::CREATE_INVITE, "CREATE_INVITE"),
+    ];
+
+    /// Toutes les permissions connues
+    pub fn all() -> Self {
+        Self::NAMES.iter().fold(Self::NONE, |acc, (permission, _)| acc | *permission)
+    }
+
+    /// Vérifier que toutes les permissions de `other` sont présentes
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Noms des permissions présentes
+    pub fn names(self) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .filter(|(permission, _)| self.contains(*permission))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Cumuler les permissions d'un ensemble de rôles (ADMINISTRATOR donne tout)
+    pub fn from_roles<'a>(roles: impl IntoIterator<Item = &'a Role>) -> Self {
+        let permissions = roles
+            .into_iter()
+            .fold(Self::NONE, |acc, role| acc | role.permissions);
+
+        if permissions.contains(Self::ADMINISTRATOR) {
+            Self::all()
+        } else {
+            permissions
+        }
+    }
+}
+
+impl BitOr for Permissions {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Permissions {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Permissions {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for Permissions {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+/// Représente un rôle personnalisé d'un serveur
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Role {
+    pub id: i32,
+    pub server_id: i32,
+    pub name: String,
+    pub permissions: Permissions,
+    pub position: i32,
+    /// Rôle @everyone, attribué implicitement à tous les membres
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// DTO pour la création d'un rôle
+#[derive(Debug, Deserialize)]
+pub struct CreateRoleDto {
+    pub name: String,
+    #[serde(default)]
+    pub permissions: Permissions,
+    #[serde(default = "default_role_position")]
+    pub position: i32,
+}
+
+fn default_role_position() -> i32 {
+    1
+}
+
+/// DTO pour la mise à jour d'un rôle
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoleDto {
+    pub name: Option<String>,
+    pub permissions: Option<Permissions>,
+    pub position: Option<i32>,
+}
+
+/// DTO pour définir les rôles d'un membre
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberRolesDto {
+    pub role_ids: Vec<i32>,
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::models::MessageWithAuthor;
+
+/// Paramètres de recherche dans les messages d'un serveur
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    /// Texte et filtres : `from:alice in:general has:link after:2026-01-31 "phrase exacte"`
+    #[serde(default)]
+    pub q: String,
+    /// Curseur : ne retourner que les messages d'id strictement inférieur
+    pub before: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+/// Contenu recherché avec le filtre `has:`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HasFilter {
+    Link,
+    Mention,
+    Reaction,
+    Reply,
+    File,
+    Image,
+}
+
+impl HasFilter {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "link" => Some(HasFilter::Link),
+            "mention" => Some(HasFilter::Mention),
+            "reaction" => Some(HasFilter::Reaction),
+            "reply" => Some(HasFilter::Reply),
+            "file" => Some(HasFilter::File),
+            "image" => Some(HasFilter::Image),
+            _ => None,
+        }
+    }
+}
+
+/// Critères de recherche résolus (canaux visibles, auteurs, période)
+#[derive(Debug, Clone, Default)]
+pub struct MessageSearch {
+    /// Canaux dans lesquels chercher, déjà filtrés selon la visibilité
+    pub channel_ids: Vec<i32>,
+    /// Texte à chercher (syntaxe de `websearch_to_tsquery`)
+    pub text: Option<String>,
+    /// Auteurs acceptés (`None` = tous)
+    pub author_ids: Option<Vec<i32>>,
+    /// Messages postés à partir de cette date (incluse)
+    pub since: Option<DateTime<Utc>>,
+    /// Messages postés avant cette date (exclue)
+    pub until: Option<DateTime<Utc>>,
+    pub has: Vec<HasFilter>,
+}
+
+/// Message trouvé, avec quelques messages autour dans la même conversation
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub message: MessageWithAuthor,
+    /// Messages précédents et suivants, du plus ancien au plus récent
+    pub context_before: Vec<MessageWithAuthor>,
+    pub context_after: Vec<MessageWithAuthor>,
+}
+
+/// Page de résultats de recherche (du plus récent au plus ancien)
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    /// Nombre total de messages correspondants
+    pub total: i64,
+    pub hits: Vec<SearchHit>,
+    /// Curseur à passer dans `before` pour la page suivante (absent en fin de résultats)
+    pub next_cursor: Option<i32>,
+}
@@ -24,20 +24,72 @@ pub struct JoinServerDto {
     pub invitation_code: String,
 }
 
-/// Représente un membre d'un serveur avec son rôle
+/// DTO pour transmettre la propriété d'un serveur (POST /servers/:id/transfer-ownership)
+#[derive(Debug, Deserialize)]
+pub struct TransferOwnershipDto {
+    /// Nouveau propriétaire, membre permanent du serveur
+    pub user_id: i32,
+    /// Mot de passe du propriétaire actuel
+    pub password: String,
+}
+
+/// Représente l'appartenance d'un utilisateur à un serveur
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ServerMember {
     pub server_id: i32,
     pub user_id: i32,
-    pub role: crate::models::UserRole,
     pub joined_at: DateTime<Utc>,
 }
 
-/// Détails complets d'un membre (utilisateur + rôle)
+/// Détails complets d'un membre (utilisateur + rôles)
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ServerMemberDetails {
     pub user_id: i32,
     pub username: String,
-    pub role: crate::models::UserRole,
+    /// Profil de l'utilisateur
+    pub display_name: Option<String>,
+    pub avatar_attachment_id: Option<i32>,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
+    pub is_owner: bool,
+    /// Rôles explicitement attribués (le rôle @everyone est implicite)
+    pub role_ids: Vec<i32>,
+    /// Fin de l'exclusion temporaire en cours, le cas échéant
+    pub timeout_until: Option<DateTime<Utc>>,
+    /// Entré par une invitation temporaire (retiré à la déconnexion sans rôle)
+    pub is_temporary: bool,
     pub joined_at: DateTime<Utc>,
 }
+
+/// Bannissement d'un utilisateur
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ServerBan {
+    pub server_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub reason: Option<String>,
+    pub banned_by: Option<i32>,
+    /// `None` pour un bannissement définitif
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// DTO pour expulser un membre
+#[derive(Debug, Default, Deserialize)]
+pub struct KickMemberDto {
+    pub reason: Option<String>,
+}
+
+/// DTO pour bannir un utilisateur
+#[derive(Debug, Default, Deserialize)]
+pub struct BanMemberDto {
+    pub reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// DTO pour exclure temporairement un membre
+#[derive(Debug, Deserialize)]
+pub struct TimeoutMemberDto {
+    pub until: DateTime<Utc>,
+    pub reason: Option<String>,
+}
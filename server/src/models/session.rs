@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Session de connexion d'un utilisateur (un appareil), renouvelée par refresh token
+///
+/// Les hash des refresh tokens ne sont lus qu'en SQL, jamais chargés.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Session active telle que listée à son utilisateur (GET /auth/sessions)
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: i32,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Session du token utilisé pour la requête
+    pub current: bool,
+}
+
+impl SessionInfo {
+    pub fn new(session: Session, current_session_id: i32) -> Self {
+        Self {
+            current: session.id == current_session_id,
+            id: session.id,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+/// DTO pour le renouvellement des tokens (POST /auth/refresh)
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenDto {
+    pub refresh_token: String,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Fil de discussion ouvert à partir d'un message d'un canal
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Thread {
+    pub id: i32,
+    pub channel_id: i32,
+    pub root_message_id: i32,
+    pub name: String,
+    pub creator_id: Option<i32>,
+    pub message_count: i64,
+    pub member_count: i64,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// DTO pour la création d'un fil (nom déduit du message d'origine par défaut)
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateThreadDto {
+    pub name: Option<String>,
+}
+
+/// Participant d'un fil
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ThreadMember {
+    pub user_id: i32,
+    pub username: String,
+    pub joined_at: DateTime<Utc>,
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 
 /// Représente un utilisateur dans le système
@@ -11,15 +11,12 @@ pub struct User {
     pub password_hash: String,
     pub email: String,
     pub created_at: DateTime<Utc>,
-}
-
-/// Rôles disponibles pour un utilisateur dans un serveur
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
-#[sqlx(type_name = "user_role", rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum UserRole {
-    Owner,
-    Admin,
-    Member,
+    /// Nom affiché à la place du pseudo, s'il est défini
+    pub display_name: Option<String>,
+    /// Image de profil (`GET /attachments/:id`, miniature comprise)
+    pub avatar_attachment_id: Option<i32>,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
 }
 
 /// DTO pour la création d'un utilisateur (inscription)
@@ -42,13 +39,55 @@ pub struct LoginDto {
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub user: User,
+    /// Access token JWT, de courte durée
     pub token: String,
+    /// Durée de validité de `token`, en secondes
+    pub expires_in: i64,
+    /// Refresh token de la session, à échanger contre de nouveaux tokens (POST /auth/refresh)
+    pub refresh_token: String,
 }
 
-/// DTO pour la mise à jour d'un utilisateur
-#[derive(Debug, Deserialize)]
+/// DTO pour la mise à jour du compte et du profil (PATCH /users/me)
+///
+/// Champ absent : inchangé. Texte vide : champ de profil retiré ; `avatar_attachment_id`
+/// à `null` : avatar retiré. Changer d'email ou de mot de passe demande `current_password`.
+#[derive(Debug, Default, Deserialize)]
 pub struct UpdateUserDto {
     pub username: Option<String>,
     pub email: Option<String>,
+    /// Nouveau mot de passe
     pub password: Option<String>,
+    pub current_password: Option<String>,
+    pub display_name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub avatar_attachment_id: Option<Option<i32>>,
+    pub bio: Option<String>,
+    pub pronouns: Option<String>,
+}
+
+/// Sort des serveurs possédés lors de la suppression d'un compte
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnedServersAction {
+    /// Transmis au membre le plus haut placé (supprimés s'il n'y a pas d'autre membre)
+    #[default]
+    Transfer,
+    Delete,
+}
+
+/// DTO pour la suppression du compte (DELETE /users/me)
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountDto {
+    pub password: String,
+    #[serde(default)]
+    pub owned_servers: OwnedServersAction,
+}
+
+/// Distinguer un champ `null` (`Some(None)`) d'un champ absent (`None`)
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{Attachment, NewAttachment};
+
+pub(crate) const ATTACHMENT_COLUMNS: &str =
+    "id, message_id, channel_id, uploader_id, filename, content_type, size, width, height, \
+     checksum, thumbnail_key IS NOT NULL as has_thumbnail, created_at, storage_key, thumbnail_key";
+
+/// Trait définissant les opérations sur les pièces jointes
+#[async_trait]
+pub trait AttachmentRepositoryTrait: Send + Sync {
+    async fn create(&self, attachment: &NewAttachment) -> AppResult<Attachment>;
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<Attachment>>;
+    async fn find_by_ids(&self, ids: &[i32]) -> AppResult<Vec<Attachment>>;
+
+    /// Supprimer les pièces jointes jamais rattachées à un message, envoyées avant une date
+    /// (hors avatars)
+    async fn delete_pending_before(&self, before: DateTime<Utc>) -> AppResult<Vec<Attachment>>;
+
+    /// La pièce jointe est l'avatar d'un utilisateur
+    async fn is_avatar(&self, id: i32) -> AppResult<bool>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct AttachmentRepository {
+    pool: PgPool,
+}
+
+impl AttachmentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AttachmentRepositoryTrait for AttachmentRepository {
+    async fn create(&self, attachment: &NewAttachment) -> AppResult<Attachment> {
+        let attachment = sqlx::query_as::<_, Attachment>(&format!(
+            "INSERT INTO attachments (channel_id, uploader_id, filename, content_type, size, \
+             width, height, checksum, storage_key, thumbnail_key) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING {}",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(attachment.channel_id)
+        .bind(attachment.uploader_id)
+        .bind(&attachment.filename)
+        .bind(&attachment.content_type)
+        .bind(attachment.size)
+        .bind(attachment.width)
+        .bind(attachment.height)
+        .bind(&attachment.checksum)
+        .bind(&attachment.storage_key)
+        .bind(&attachment.thumbnail_key)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(attachment)
+    }
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<Attachment>> {
+        let attachment = sqlx::query_as::<_, Attachment>(&format!(
+            "SELECT {} FROM attachments WHERE id = $1",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(attachment)
+    }
+
+    async fn find_by_ids(&self, ids: &[i32]) -> AppResult<Vec<Attachment>> {
+        let attachments = sqlx::query_as::<_, Attachment>(&format!(
+            "SELECT {} FROM attachments WHERE id = ANY($1) ORDER BY id",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attachments)
+    }
+
+    async fn delete_pending_before(&self, before: DateTime<Utc>) -> AppResult<Vec<Attachment>> {
+        let attachments = sqlx::query_as::<_, Attachment>(&format!(
+            "DELETE FROM attachments WHERE message_id IS NULL AND created_at < $1 \
+             AND NOT EXISTS (SELECT 1 FROM users WHERE avatar_attachment_id = attachments.id) \
+             RETURNING {}",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attachments)
+    }
+
+    async fn is_avatar(&self, id: i32) -> AppResult<bool> {
+        let is_avatar: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE avatar_attachment_id = $1)",
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(is_avatar)
+    }
+}
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{AuditAction, AuditLogEntry, NewAuditEntry};
+
+/// Trait définissant les opérations sur le journal d'audit
+#[async_trait]
+pub trait AuditLogRepositoryTrait: Send + Sync {
+    async fn create(&self, entry: &NewAuditEntry) -> AppResult<()>;
+    async fn find_by_server(
+        &self,
+        server_id: i32,
+        action: Option<AuditAction>,
+        actor_id: Option<i32>,
+        before: Option<i32>,
+        limit: i64,
+    ) -> AppResult<Vec<AuditLogEntry>>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct AuditLogRepository {
+    pool: PgPool,
+}
+
+impl AuditLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditLogRepositoryTrait for AuditLogRepository {
+    async fn create(&self, entry: &NewAuditEntry) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO audit_log (server_id, actor_id, action, target_type, target_id, before, after, reason) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        )
+        .bind(entry.server_id)
+        .bind(entry.actor_id)
+        .bind(entry.action.as_str())
+        .bind(entry.action.target_type())
+        .bind(entry.target_id)
+        .bind(&entry.before)
+        .bind(&entry.after)
+        .bind(&entry.reason)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_server(
+        &self,
+        server_id: i32,
+        action: Option<AuditAction>,
+        actor_id: Option<i32>,
+        before: Option<i32>,
+        limit: i64,
+    ) -> AppResult<Vec<AuditLogEntry>> {
+        let entries = sqlx::query_as::<_, AuditLogEntry>(
+            "SELECT a.id, a.server_id, a.actor_id, u.username as actor_username, a.action, \
+             a.target_type, a.target_id, a.before, a.after, a.reason, a.created_at \
+             FROM audit_log a \
+             LEFT JOIN users u ON u.id = a.actor_id \
+             WHERE a.server_id = $1 \
+             AND ($2::varchar IS NULL OR a.action = $2) \
+             AND ($3::int IS NULL OR a.actor_id = $3) \
+             AND ($4::int IS NULL OR a.id < $4) \
+             ORDER BY a.id DESC \
+             LIMIT $5"
+        )
+        .bind(server_id)
+        .bind(action.map(AuditAction::as_str))
+        .bind(actor_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use sqlx::PgPool;
 use crate::errors::AppResult;
-use crate::models::Channel;
+use crate::models::{Channel, PermissionOverwrite, Permissions};
 
 /// Trait définissant les opérations sur les canaux
 #[async_trait]
@@ -11,6 +11,14 @@ pub trait ChannelRepositoryTrait: Send + Sync {
     async fn find_by_server(&self, server_id: i32) -> AppResult<Vec<Channel>>;
     async fn update(&self, id: i32, name: &str) -> AppResult<Channel>;
     async fn delete(&self, id: i32) -> AppResult<()>;
+
+    // Surcharges de permissions
+    async fn find_overwrites(&self, channel_id: i32) -> AppResult<Vec<PermissionOverwrite>>;
+    async fn find_overwrites_by_server(&self, server_id: i32) -> AppResult<Vec<PermissionOverwrite>>;
+    async fn upsert_role_overwrite(&self, channel_id: i32, role_id: i32, allow: Permissions, deny: Permissions) -> AppResult<PermissionOverwrite>;
+    async fn upsert_member_overwrite(&self, channel_id: i32, user_id: i32, allow: Permissions, deny: Permissions) -> AppResult<PermissionOverwrite>;
+    async fn delete_role_overwrite(&self, channel_id: i32, role_id: i32) -> AppResult<bool>;
+    async fn delete_member_overwrite(&self, channel_id: i32, user_id: i32) -> AppResult<bool>;
 }
 
 /// Implémentation PostgreSQL
@@ -85,4 +93,79 @@ impl ChannelRepositoryTrait for ChannelRepository {
 
         Ok(())
     }
+
+    async fn find_overwrites(&self, channel_id: i32) -> AppResult<Vec<PermissionOverwrite>> {
+        let overwrites = sqlx::query_as::<_, PermissionOverwrite>(
+            "SELECT id, channel_id, role_id, user_id, allow, deny FROM channel_permission_overwrites WHERE channel_id = $1 ORDER BY id"
+        )
+        .bind(channel_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(overwrites)
+    }
+
+    async fn find_overwrites_by_server(&self, server_id: i32) -> AppResult<Vec<PermissionOverwrite>> {
+        let overwrites = sqlx::query_as::<_, PermissionOverwrite>(
+            "SELECT o.id, o.channel_id, o.role_id, o.user_id, o.allow, o.deny FROM channel_permission_overwrites o \
+             INNER JOIN channels c ON c.id = o.channel_id WHERE c.server_id = $1 ORDER BY o.id"
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(overwrites)
+    }
+
+    async fn upsert_role_overwrite(&self, channel_id: i32, role_id: i32, allow: Permissions, deny: Permissions) -> AppResult<PermissionOverwrite> {
+        let overwrite = sqlx::query_as::<_, PermissionOverwrite>(
+            "INSERT INTO channel_permission_overwrites (channel_id, role_id, allow, deny) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (channel_id, role_id) WHERE role_id IS NOT NULL DO UPDATE SET allow = EXCLUDED.allow, deny = EXCLUDED.deny \
+             RETURNING id, channel_id, role_id, user_id, allow, deny"
+        )
+        .bind(channel_id)
+        .bind(role_id)
+        .bind(allow)
+        .bind(deny)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(overwrite)
+    }
+
+    async fn upsert_member_overwrite(&self, channel_id: i32, user_id: i32, allow: Permissions, deny: Permissions) -> AppResult<PermissionOverwrite> {
+        let overwrite = sqlx::query_as::<_, PermissionOverwrite>(
+            "INSERT INTO channel_permission_overwrites (channel_id, user_id, allow, deny) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (channel_id, user_id) WHERE user_id IS NOT NULL DO UPDATE SET allow = EXCLUDED.allow, deny = EXCLUDED.deny \
+             RETURNING id, channel_id, role_id, user_id, allow, deny"
+        )
+        .bind(channel_id)
+        .bind(user_id)
+        .bind(allow)
+        .bind(deny)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(overwrite)
+    }
+
+    async fn delete_role_overwrite(&self, channel_id: i32, role_id: i32) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM channel_permission_overwrites WHERE channel_id = $1 AND role_id = $2")
+            .bind(channel_id)
+            .bind(role_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_member_overwrite(&self, channel_id: i32, user_id: i32) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM channel_permission_overwrites WHERE channel_id = $1 AND user_id = $2")
+            .bind(channel_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
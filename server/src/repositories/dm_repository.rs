@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{DmConversation, DmParticipant, MessageCursor, MessageWithAuthor};
+use crate::repositories::message_repository::keyset_query;
+
+/// Trait définissant les opérations sur les conversations privées
+#[async_trait]
+pub trait DmRepositoryTrait: Send + Sync {
+    async fn create(&self, name: Option<&str>, is_group: bool, participant_ids: &[i32]) -> AppResult<DmConversation>;
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<DmConversation>>;
+    async fn find_direct(&self, user_id: i32, other_user_id: i32) -> AppResult<Option<DmConversation>>;
+    async fn find_by_user(&self, user_id: i32) -> AppResult<Vec<DmConversation>>;
+    async fn get_participants(&self, conversation_id: i32) -> AppResult<Vec<DmParticipant>>;
+    async fn is_participant(&self, conversation_id: i32, user_id: i32) -> AppResult<bool>;
+
+    // Messages
+    async fn create_message(&self, content: &str, conversation_id: i32, author_id: i32) -> AppResult<MessageWithAuthor>;
+    async fn find_messages(&self, conversation_id: i32, cursor: MessageCursor, limit: i64) -> AppResult<Vec<MessageWithAuthor>>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct DmRepository {
+    pool: PgPool,
+}
+
+impl DmRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DmRepositoryTrait for DmRepository {
+    async fn create(&self, name: Option<&str>, is_group: bool, participant_ids: &[i32]) -> AppResult<DmConversation> {
+        let mut tx = self.pool.begin().await?;
+
+        let conversation = sqlx::query_as::<_, DmConversation>(
+            "INSERT INTO dm_conversations (name, is_group) VALUES ($1, $2) RETURNING id, name, is_group, created_at"
+        )
+        .bind(name)
+        .bind(is_group)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO dm_participants (conversation_id, user_id) SELECT $1, UNNEST($2::int[])"
+        )
+        .bind(conversation.id)
+        .bind(participant_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(conversation)
+    }
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<DmConversation>> {
+        let conversation = sqlx::query_as::<_, DmConversation>(
+            "SELECT id, name, is_group, created_at FROM dm_conversations WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(conversation)
+    }
+
+    async fn find_direct(&self, user_id: i32, other_user_id: i32) -> AppResult<Option<DmConversation>> {
+        let conversation = sqlx::query_as::<_, DmConversation>(
+            "SELECT c.id, c.name, c.is_group, c.created_at FROM dm_conversations c \
+             INNER JOIN dm_participants a ON a.conversation_id = c.id AND a.user_id = $1 \
+             INNER JOIN dm_participants b ON b.conversation_id = c.id AND b.user_id = $2 \
+             WHERE c.is_group = false LIMIT 1"
+        )
+        .bind(user_id)
+        .bind(other_user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(conversation)
+    }
+
+    async fn find_by_user(&self, user_id: i32) -> AppResult<Vec<DmConversation>> {
+        let conversations = sqlx::query_as::<_, DmConversation>(
+            "SELECT c.id, c.name, c.is_group, c.created_at FROM dm_conversations c \
+             INNER JOIN dm_participants p ON p.conversation_id = c.id \
+             WHERE p.user_id = $1 \
+             ORDER BY COALESCE((SELECT MAX(m.created_at) FROM direct_messages m WHERE m.conversation_id = c.id), c.created_at) DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(conversations)
+    }
+
+    async fn get_participants(&self, conversation_id: i32) -> AppResult<Vec<DmParticipant>> {
+        let participants = sqlx::query_as::<_, DmParticipant>(
+            "SELECT u.id as user_id, u.username FROM users u INNER JOIN dm_participants p ON u.id = p.user_id WHERE p.conversation_id = $1 ORDER BY p.joined_at, u.id"
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(participants)
+    }
+
+    async fn is_participant(&self, conversation_id: i32, user_id: i32) -> AppResult<bool> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM dm_participants WHERE conversation_id = $1 AND user_id = $2)"
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    async fn create_message(&self, content: &str, conversation_id: i32, author_id: i32) -> AppResult<MessageWithAuthor> {
+        // Les messages privés réutilisent la forme MessageWithAuthor : `channel_id` porte l'ID de la conversation
+        let message = sqlx::query_as::<_, MessageWithAuthor>(
+            "WITH m AS (INSERT INTO direct_messages (content, conversation_id, author_id) VALUES ($1, $2, $3) RETURNING *) \
+             SELECT m.id, m.content, m.conversation_id as channel_id, m.author_id, u.username as author_username, m.is_deleted, m.created_at, m.updated_at \
+             FROM m INNER JOIN users u ON m.author_id = u.id"
+        )
+        .bind(content)
+        .bind(conversation_id)
+        .bind(author_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(message)
+    }
+
+    async fn find_messages(&self, conversation_id: i32, cursor: MessageCursor, limit: i64) -> AppResult<Vec<MessageWithAuthor>> {
+        let messages = sqlx::query_as::<_, MessageWithAuthor>(&keyset_query(
+            "SELECT m.id, m.content, m.conversation_id as channel_id, m.author_id, u.username as author_username, m.is_deleted, m.created_at, m.updated_at \
+             FROM direct_messages m INNER JOIN users u ON m.author_id = u.id",
+            "m.conversation_id = $1 AND m.is_deleted = false",
+            cursor,
+            2,
+        ))
+        .bind(conversation_id)
+        .bind(cursor.message_id())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+}
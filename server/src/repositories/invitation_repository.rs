@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{Invitation, InvitationPreview, InvitationUse};
+
+const INVITATION_COLUMNS: &str =
+    "id, code, server_id, creator_id, max_uses, uses, expires_at, temporary, created_at";
+
+/// Trait définissant les opérations sur les invitations
+#[async_trait]
+pub trait InvitationRepositoryTrait: Send + Sync {
+    async fn create(
+        &self,
+        server_id: i32,
+        code: &str,
+        creator_id: i32,
+        max_uses: Option<i32>,
+        expires_at: Option<DateTime<Utc>>,
+        temporary: bool,
+    ) -> AppResult<Invitation>;
+    async fn find_by_code(&self, code: &str) -> AppResult<Option<Invitation>>;
+    async fn find_by_server(&self, server_id: i32) -> AppResult<Vec<Invitation>>;
+    async fn find_preview(&self, code: &str) -> AppResult<Option<InvitationPreview>>;
+    async fn delete(&self, id: i32) -> AppResult<()>;
+
+    /// Consommer une utilisation et ajouter le membre dans la même transaction
+    async fn consume(&self, id: i32, user_id: i32) -> AppResult<InvitationUse>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct InvitationRepository {
+    pool: PgPool,
+}
+
+impl InvitationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl InvitationRepositoryTrait for InvitationRepository {
+    async fn create(
+        &self,
+        server_id: i32,
+        code: &str,
+        creator_id: i32,
+        max_uses: Option<i32>,
+        expires_at: Option<DateTime<Utc>>,
+        temporary: bool,
+    ) -> AppResult<Invitation> {
+        let invitation = sqlx::query_as::<_, Invitation>(&format!(
+            "INSERT INTO invitations (server_id, code, creator_id, max_uses, expires_at, temporary) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+            INVITATION_COLUMNS
+        ))
+        .bind(server_id)
+        .bind(code)
+        .bind(creator_id)
+        .bind(max_uses)
+        .bind(expires_at)
+        .bind(temporary)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(invitation)
+    }
+
+    async fn find_by_code(&self, code: &str) -> AppResult<Option<Invitation>> {
+        let invitation = sqlx::query_as::<_, Invitation>(&format!(
+            "SELECT {} FROM invitations WHERE code = $1",
+            INVITATION_COLUMNS
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(invitation)
+    }
+
+    async fn find_by_server(&self, server_id: i32) -> AppResult<Vec<Invitation>> {
+        let invitations = sqlx::query_as::<_, Invitation>(&format!(
+            "SELECT {} FROM invitations WHERE server_id = $1 ORDER BY created_at DESC, id DESC",
+            INVITATION_COLUMNS
+        ))
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invitations)
+    }
+
+    async fn find_preview(&self, code: &str) -> AppResult<Option<InvitationPreview>> {
+        let preview = sqlx::query_as::<_, InvitationPreview>(
+            "SELECT i.code, s.id as server_id, s.name as server_name, \
+             (SELECT COUNT(*) FROM server_members sm WHERE sm.server_id = s.id) as member_count, \
+             i.expires_at, i.temporary \
+             FROM invitations i INNER JOIN servers s ON s.id = i.server_id \
+             WHERE i.code = $1"
+        )
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(preview)
+    }
+
+    async fn delete(&self, id: i32) -> AppResult<()> {
+        sqlx::query("DELETE FROM invitations WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn consume(&self, id: i32, user_id: i32) -> AppResult<InvitationUse> {
+        let mut tx = self.pool.begin().await?;
+
+        // Le verrou de la ligne garantit qu'une invitation à usage limité n'est pas dépassée
+        let consumed = sqlx::query_as::<_, (i32, bool)>(
+            "UPDATE invitations SET uses = uses + 1 \
+             WHERE id = $1 \
+             AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP) \
+             AND (max_uses IS NULL OR uses < max_uses) \
+             RETURNING server_id, temporary"
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((server_id, temporary)) = consumed else {
+            return Ok(InvitationUse::Unavailable);
+        };
+
+        // Deux adhésions concurrentes du même utilisateur : la seconde n'insère rien
+        let inserted = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO server_members (server_id, user_id, is_temporary) VALUES ($1, $2, $3) \
+             ON CONFLICT (server_id, user_id) DO NOTHING \
+             RETURNING user_id"
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .bind(temporary)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if inserted.is_none() {
+            // Annuler l'incrément de `uses`
+            tx.rollback().await?;
+            return Ok(InvitationUse::AlreadyMember);
+        }
+
+        tx.commit().await?;
+
+        Ok(InvitationUse::Joined)
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{Mention, MentionKind};
+
+/// Trait définissant les opérations sur les mentions
+#[async_trait]
+pub trait MentionRepositoryTrait: Send + Sync {
+    async fn create_many(&self, message_id: i32, recipients: &[(i32, MentionKind)]) -> AppResult<()>;
+    /// Membres du serveur parmi les utilisateurs donnés
+    async fn find_member_ids(&self, server_id: i32, user_ids: &[i32]) -> AppResult<Vec<i32>>;
+    /// Membres du serveur possédant au moins un des rôles donnés
+    async fn find_role_member_ids(&self, server_id: i32, role_ids: &[i32]) -> AppResult<Vec<i32>>;
+    async fn find_all_member_ids(&self, server_id: i32) -> AppResult<Vec<i32>>;
+    async fn find_unseen(&self, user_id: i32, before: Option<i32>, limit: i64) -> AppResult<Vec<Mention>>;
+    async fn mark_seen(&self, user_id: i32, message_id: i32) -> AppResult<bool>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct MentionRepository {
+    pool: PgPool,
+}
+
+impl MentionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl MentionRepositoryTrait for MentionRepository {
+    async fn create_many(&self, message_id: i32, recipients: &[(i32, MentionKind)]) -> AppResult<()> {
+        let user_ids: Vec<i32> = recipients.iter().map(|(user_id, _)| *user_id).collect();
+        let kinds: Vec<&str> = recipients.iter().map(|(_, kind)| kind.as_str()).collect();
+
+        sqlx::query(
+            "INSERT INTO message_mentions (message_id, user_id, kind) \
+             SELECT $1, UNNEST($2::int[]), UNNEST($3::varchar[]) \
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(message_id)
+        .bind(&user_ids)
+        .bind(&kinds)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_member_ids(&self, server_id: i32, user_ids: &[i32]) -> AppResult<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            "SELECT user_id FROM server_members WHERE server_id = $1 AND user_id = ANY($2)"
+        )
+        .bind(server_id)
+        .bind(user_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn find_role_member_ids(&self, server_id: i32, role_ids: &[i32]) -> AppResult<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            "SELECT DISTINCT user_id FROM member_roles WHERE server_id = $1 AND role_id = ANY($2)"
+        )
+        .bind(server_id)
+        .bind(role_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn find_all_member_ids(&self, server_id: i32) -> AppResult<Vec<i32>> {
+        let ids = sqlx::query_scalar::<_, i32>(
+            "SELECT user_id FROM server_members WHERE server_id = $1"
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn find_unseen(&self, user_id: i32, before: Option<i32>, limit: i64) -> AppResult<Vec<Mention>> {
+        // Seuls les serveurs dont l'utilisateur est toujours membre
+        let mentions = sqlx::query_as::<_, Mention>(
+            "SELECT mm.message_id, s.id as server_id, s.name as server_name, \
+             c.id as channel_id, c.name as channel_name, m.thread_id, \
+             m.author_id, u.username as author_username, m.content, mm.kind, m.created_at \
+             FROM message_mentions mm \
+             INNER JOIN messages m ON m.id = mm.message_id \
+             INNER JOIN channels c ON c.id = m.channel_id \
+             INNER JOIN servers s ON s.id = c.server_id \
+             INNER JOIN users u ON u.id = m.author_id \
+             INNER JOIN server_members sm ON sm.server_id = s.id AND sm.user_id = mm.user_id \
+             WHERE mm.user_id = $1 AND mm.seen_at IS NULL AND m.is_deleted = false \
+             AND ($2::int IS NULL OR mm.message_id < $2) \
+             ORDER BY mm.message_id DESC \
+             LIMIT $3"
+        )
+        .bind(user_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(mentions)
+    }
+
+    async fn mark_seen(&self, user_id: i32, message_id: i32) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE message_mentions SET seen_at = CURRENT_TIMESTAMP \
+             WHERE user_id = $1 AND message_id = $2 AND seen_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,16 +1,162 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use crate::errors::AppResult;
-use crate::models::{Message, MessageWithAuthor};
+use crate::repositories::attachment_repository::ATTACHMENT_COLUMNS;
+use crate::models::{
+    Attachment, HasFilter, Message, MessageCursor, MessageRevision, MessageSearch, MessageWithAuthor, PinOutcome,
+};
+
+const MESSAGE_COLUMNS: &str =
+    "id, content, channel_id, author_id, created_at, updated_at, is_deleted, reply_to_id, thread_id, pinned_at";
+
+/// Message avec son auteur, l'aperçu du message auquel il répond et ses réactions
+///
+/// `$1` est l'utilisateur qui consulte (drapeau `me` des réactions).
+const MESSAGE_WITH_AUTHOR_SELECT: &str =
+    "SELECT m.id, m.content, m.channel_id, m.author_id, u.username as author_username, \
+     m.is_deleted, m.created_at, m.updated_at, m.reply_to_id, m.thread_id, m.pinned_at, \
+     CASE WHEN r.id IS NULL THEN NULL ELSE jsonb_build_object( \
+         'id', r.id, 'author_id', r.author_id, 'author_username', ru.username, \
+         'snippet', CASE WHEN r.is_deleted THEN '' ELSE LEFT(r.content, 100) END, \
+         'is_deleted', r.is_deleted \
+     ) END as reply_to, \
+     COALESCE(( \
+         SELECT jsonb_agg(jsonb_build_object('emoji', r.emoji, 'count', r.count, 'me', r.me) ORDER BY r.first_at) \
+         FROM ( \
+             SELECT emoji, COUNT(*) as count, bool_or(user_id = $1) as me, MIN(created_at) as first_at \
+             FROM message_reactions WHERE message_id = m.id GROUP BY emoji \
+         ) r \
+     ), '[]'::jsonb) as reactions, \
+     COALESCE(( \
+         SELECT jsonb_agg(jsonb_build_object( \
+             'id', a.id, 'message_id', a.message_id, 'channel_id', a.channel_id, \
+             'uploader_id', a.uploader_id, 'filename', a.filename, 'content_type', a.content_type, \
+             'size', a.size, 'width', a.width, 'height', a.height, 'checksum', a.checksum, \
+             'has_thumbnail', a.thumbnail_key IS NOT NULL, 'created_at', a.created_at \
+         ) ORDER BY a.id) \
+         FROM attachments a WHERE a.message_id = m.id \
+     ), '[]'::jsonb) as attachments \
+     FROM messages m \
+     INNER JOIN users u ON m.author_id = u.id \
+     LEFT JOIN messages r ON r.id = m.reply_to_id \
+     LEFT JOIN users ru ON ru.id = r.author_id";
+
+/// Requête paginée par curseur sur l'id des messages (alias `m`)
+///
+/// `$cursor` est l'id de référence (ignoré pour `Latest`) et `$cursor + 1` la limite.
+/// Les pages sont toujours retournées du plus récent au plus ancien.
+pub(crate) fn keyset_query(select: &str, filter: &str, cursor: MessageCursor, param: usize) -> String {
+    let (cursor_param, limit_param) = (param, param + 1);
+    match cursor {
+        MessageCursor::Latest => format!(
+            "{select} WHERE {filter} ORDER BY m.id DESC LIMIT ${limit_param}"
+        ),
+        MessageCursor::Before(_) => format!(
+            "{select} WHERE {filter} AND m.id < ${cursor_param} ORDER BY m.id DESC LIMIT ${limit_param}"
+        ),
+        MessageCursor::After(_) => format!(
+            "SELECT * FROM ({select} WHERE {filter} AND m.id > ${cursor_param} \
+             ORDER BY m.id ASC LIMIT ${limit_param}) page ORDER BY id DESC"
+        ),
+        // La moitié de la page avant le message, le message et la suite après
+        MessageCursor::Around(_) => format!(
+            "SELECT * FROM ( \
+             ({select} WHERE {filter} AND m.id < ${cursor_param} \
+              ORDER BY m.id DESC LIMIT ${limit_param} / 2) \
+             UNION ALL \
+             ({select} WHERE {filter} AND m.id >= ${cursor_param} \
+              ORDER BY m.id ASC LIMIT ${limit_param} - ${limit_param} / 2) \
+             ) page ORDER BY id DESC"
+        ),
+    }
+}
+
+/// Conditions de recherche sur `m` : `$2` canaux, `$3` texte, `$4` auteurs, `$5` / `$6` période
+fn search_filter(search: &MessageSearch) -> String {
+    let mut filter = String::from(
+        "m.channel_id = ANY($2) AND m.is_deleted = false \
+         AND ($3::text IS NULL OR m.search_vector @@ websearch_to_tsquery('simple', $3)) \
+         AND ($4::int[] IS NULL OR m.author_id = ANY($4)) \
+         AND ($5::timestamptz IS NULL OR m.created_at >= $5) \
+         AND ($6::timestamptz IS NULL OR m.created_at < $6)",
+    );
+
+    for has in &search.has {
+        filter.push_str(match has {
+            HasFilter::Link => " AND m.content ~* 'https?://'",
+            HasFilter::Mention => " AND m.content ~ '<@&?[0-9]+>|@everyone|@here'",
+            HasFilter::Reaction => {
+                " AND EXISTS (SELECT 1 FROM message_reactions mr WHERE mr.message_id = m.id)"
+            }
+            HasFilter::Reply => " AND m.reply_to_id IS NOT NULL",
+            HasFilter::File => " AND EXISTS (SELECT 1 FROM attachments a WHERE a.message_id = m.id)",
+            HasFilter::Image => {
+                " AND EXISTS (SELECT 1 FROM attachments a WHERE a.message_id = m.id AND a.width IS NOT NULL)"
+            }
+        });
+    }
+
+    filter
+}
 
 /// Trait définissant les opérations sur les messages
 #[async_trait]
 pub trait MessageRepositoryTrait: Send + Sync {
-    async fn create(&self, content: &str, channel_id: i32, author_id: i32) -> AppResult<Message>;
+    /// Créer un message et lui rattacher les pièces jointes en attente de l'auteur, dans le canal
+    ///
+    /// `None` si une pièce jointe n'est plus disponible : rien n'est alors enregistré.
+    async fn create(
+        &self,
+        content: &str,
+        channel_id: i32,
+        author_id: i32,
+        reply_to_id: Option<i32>,
+        thread_id: Option<i32>,
+        attachment_ids: &[i32],
+    ) -> AppResult<Option<Message>>;
     async fn find_by_id(&self, id: i32) -> AppResult<Option<Message>>;
-    async fn find_by_channel(&self, channel_id: i32, limit: i64, offset: i64) -> AppResult<Vec<MessageWithAuthor>>;
-    async fn delete(&self, id: i32) -> AppResult<()>;
+    /// Messages du canal lui-même, hors fils de discussion
+    async fn find_by_channel(
+        &self,
+        channel_id: i32,
+        viewer_id: i32,
+        cursor: MessageCursor,
+        limit: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>>;
+    async fn find_by_thread(
+        &self,
+        thread_id: i32,
+        viewer_id: i32,
+        cursor: MessageCursor,
+        limit: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>>;
+    /// Recherche plein texte, du plus récent au plus ancien
+    async fn search(
+        &self,
+        search: &MessageSearch,
+        viewer_id: i32,
+        before: Option<i32>,
+        limit: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>>;
+    async fn count_search(&self, search: &MessageSearch) -> AppResult<i64>;
+    /// Jusqu'à `size` messages avant et après chacun des messages donnés, dans leur
+    /// conversation (canal ou fil), sans doublons
+    async fn find_context(
+        &self,
+        message_ids: &[i32],
+        viewer_id: i32,
+        size: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>>;
+    async fn update(&self, id: i32, content: &str) -> AppResult<Message>;
+    async fn find_revisions(&self, message_id: i32) -> AppResult<Vec<MessageRevision>>;
     async fn soft_delete(&self, id: i32) -> AppResult<()>;
+    /// Messages épinglés d'un canal, du plus récemment épinglé au plus ancien
+    async fn find_pins(&self, channel_id: i32, viewer_id: i32) -> AppResult<Vec<MessageWithAuthor>>;
+    /// Épingler un message si le canal compte moins de `max_pins` messages épinglés
+    async fn pin(&self, id: i32, channel_id: i32, pinned_by: i32, max_pins: i64) -> AppResult<PinOutcome>;
+    /// Désépingler un message (`false` s'il ne l'était pas)
+    async fn unpin(&self, id: i32) -> AppResult<bool>;
 }
 
 /// Implémentation PostgreSQL
@@ -27,52 +173,225 @@ impl MessageRepository {
 
 #[async_trait]
 impl MessageRepositoryTrait for MessageRepository {
-    async fn create(&self, content: &str, channel_id: i32, author_id: i32) -> AppResult<Message> {
-        let message = sqlx::query_as!(
-            Message,
-            "INSERT INTO messages (content, channel_id, author_id) VALUES ($1, $2, $3) RETURNING id, content, channel_id, author_id, created_at, updated_at, is_deleted",
-            content,
-            channel_id,
-            author_id
-        )
-        .fetch_one(&self.pool)
+    async fn create(
+        &self,
+        content: &str,
+        channel_id: i32,
+        author_id: i32,
+        reply_to_id: Option<i32>,
+        thread_id: Option<i32>,
+        attachment_ids: &[i32],
+    ) -> AppResult<Option<Message>> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut message = sqlx::query_as::<_, Message>(&format!(
+            "INSERT INTO messages (content, channel_id, author_id, reply_to_id, thread_id) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+            MESSAGE_COLUMNS
+        ))
+        .bind(content)
+        .bind(channel_id)
+        .bind(author_id)
+        .bind(reply_to_id)
+        .bind(thread_id)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(message)
+        if !attachment_ids.is_empty() {
+            let mut ids = attachment_ids.to_vec();
+            ids.sort_unstable();
+            ids.dedup();
+
+            let mut attachments = sqlx::query_as::<_, Attachment>(&format!(
+                "UPDATE attachments SET message_id = $2 \
+                 WHERE id = ANY($1) AND message_id IS NULL AND uploader_id = $3 AND channel_id = $4 \
+                 RETURNING {}",
+                ATTACHMENT_COLUMNS
+            ))
+            .bind(&ids)
+            .bind(message.id)
+            .bind(author_id)
+            .bind(channel_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            // Une pièce jointe rattachée entre-temps à un autre message : tout annuler
+            if attachments.len() != ids.len() {
+                tx.rollback().await?;
+                return Ok(None);
+            }
+
+            attachments.sort_by_key(|attachment| attachment.id);
+            message.attachments = attachments;
+        }
+
+        tx.commit().await?;
+
+        Ok(Some(message))
     }
 
     async fn find_by_id(&self, id: i32) -> AppResult<Option<Message>> {
-        let message = sqlx::query_as!(
-            Message,
-            "SELECT id, content, channel_id, author_id, created_at, updated_at, is_deleted FROM messages WHERE id = $1",
-            id
-        )
+        let message = sqlx::query_as::<_, Message>(&format!(
+            "SELECT {} FROM messages WHERE id = $1",
+            MESSAGE_COLUMNS
+        ))
+        .bind(id)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(message)
     }
 
-    async fn find_by_channel(&self, channel_id: i32, limit: i64, offset: i64) -> AppResult<Vec<MessageWithAuthor>> {
-        let messages = sqlx::query_as!(
-            MessageWithAuthor,
-            "SELECT m.id, m.content, m.channel_id, m.author_id, m.created_at, m.is_deleted, m.updated_at, u.username as author_username FROM messages m INNER JOIN users u ON m.author_id = u.id WHERE m.channel_id = $1 AND m.is_deleted = false ORDER BY m.created_at DESC LIMIT $2 OFFSET $3",
-            channel_id,
-            limit,
-            offset
-        )
+    async fn find_by_channel(
+        &self,
+        channel_id: i32,
+        viewer_id: i32,
+        cursor: MessageCursor,
+        limit: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>> {
+        let messages = sqlx::query_as::<_, MessageWithAuthor>(&keyset_query(
+            MESSAGE_WITH_AUTHOR_SELECT,
+            "m.channel_id = $2 AND m.thread_id IS NULL AND m.is_deleted = false",
+            cursor,
+            3,
+        ))
+        .bind(viewer_id)
+        .bind(channel_id)
+        .bind(cursor.message_id())
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(messages)
     }
 
-    async fn delete(&self, id: i32) -> AppResult<()> {
-        sqlx::query!("DELETE FROM messages WHERE id = $1", id)
-            .execute(&self.pool)
-            .await?;
+    async fn find_by_thread(
+        &self,
+        thread_id: i32,
+        viewer_id: i32,
+        cursor: MessageCursor,
+        limit: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>> {
+        let messages = sqlx::query_as::<_, MessageWithAuthor>(&keyset_query(
+            MESSAGE_WITH_AUTHOR_SELECT,
+            "m.thread_id = $2 AND m.is_deleted = false",
+            cursor,
+            3,
+        ))
+        .bind(viewer_id)
+        .bind(thread_id)
+        .bind(cursor.message_id())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(())
+        Ok(messages)
+    }
+
+    async fn search(
+        &self,
+        search: &MessageSearch,
+        viewer_id: i32,
+        before: Option<i32>,
+        limit: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>> {
+        let messages = sqlx::query_as::<_, MessageWithAuthor>(&format!(
+            "{} WHERE {} AND ($7::int IS NULL OR m.id < $7) ORDER BY m.id DESC LIMIT $8",
+            MESSAGE_WITH_AUTHOR_SELECT,
+            search_filter(search)
+        ))
+        .bind(viewer_id)
+        .bind(&search.channel_ids)
+        .bind(&search.text)
+        .bind(&search.author_ids)
+        .bind(search.since)
+        .bind(search.until)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    async fn count_search(&self, search: &MessageSearch) -> AppResult<i64> {
+        // `$1` n'est pas utilisé : la numérotation est partagée avec `search`
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM messages m WHERE {}",
+            search_filter(search)
+        ))
+        .bind(None::<i32>)
+        .bind(&search.channel_ids)
+        .bind(&search.text)
+        .bind(&search.author_ids)
+        .bind(search.since)
+        .bind(search.until)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn find_context(
+        &self,
+        message_ids: &[i32],
+        viewer_id: i32,
+        size: i64,
+    ) -> AppResult<Vec<MessageWithAuthor>> {
+        let conversation = "m.channel_id = hit.channel_id \
+             AND m.thread_id IS NOT DISTINCT FROM hit.thread_id AND m.is_deleted = false";
+        let messages = sqlx::query_as::<_, MessageWithAuthor>(&format!(
+            "SELECT DISTINCT ON (context.id) context.* FROM messages hit CROSS JOIN LATERAL ( \
+             ({select} WHERE {conversation} AND m.id < hit.id ORDER BY m.id DESC LIMIT $3) \
+             UNION ALL \
+             ({select} WHERE {conversation} AND m.id > hit.id ORDER BY m.id ASC LIMIT $3) \
+             ) context \
+             WHERE hit.id = ANY($2) ORDER BY context.id",
+            select = MESSAGE_WITH_AUTHOR_SELECT,
+        ))
+        .bind(viewer_id)
+        .bind(message_ids)
+        .bind(size)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    async fn update(&self, id: i32, content: &str) -> AppResult<Message> {
+        let mut tx = self.pool.begin().await?;
+
+        // Archiver le contenu actuel avant de le remplacer
+        sqlx::query(
+            "INSERT INTO message_revisions (message_id, content) SELECT id, content FROM messages WHERE id = $1"
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        let message = sqlx::query_as::<_, Message>(&format!(
+            "UPDATE messages SET content = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2 RETURNING {}",
+            MESSAGE_COLUMNS
+        ))
+        .bind(content)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(message)
+    }
+
+    async fn find_revisions(&self, message_id: i32) -> AppResult<Vec<MessageRevision>> {
+        let revisions = sqlx::query_as::<_, MessageRevision>(
+            "SELECT id, message_id, content, created_at FROM message_revisions WHERE message_id = $1 ORDER BY created_at DESC, id DESC"
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(revisions)
     }
 
     async fn soft_delete(&self, id: i32) -> AppResult<()> {
@@ -82,4 +401,71 @@ impl MessageRepositoryTrait for MessageRepository {
 
         Ok(())
     }
+
+    async fn find_pins(&self, channel_id: i32, viewer_id: i32) -> AppResult<Vec<MessageWithAuthor>> {
+        let messages = sqlx::query_as::<_, MessageWithAuthor>(&format!(
+            "{} WHERE m.channel_id = $2 AND m.pinned_at IS NOT NULL AND m.is_deleted = false \
+             ORDER BY m.pinned_at DESC",
+            MESSAGE_WITH_AUTHOR_SELECT
+        ))
+        .bind(viewer_id)
+        .bind(channel_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    async fn pin(&self, id: i32, channel_id: i32, pinned_by: i32, max_pins: i64) -> AppResult<PinOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        // Le verrou du canal sérialise les épinglages concurrents : le décompte reste exact
+        sqlx::query("SELECT id FROM channels WHERE id = $1 FOR UPDATE")
+            .bind(channel_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let pinned_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "UPDATE messages SET pinned_at = CURRENT_TIMESTAMP, pinned_by = $2 \
+             WHERE id = $1 AND pinned_at IS NULL \
+             AND (SELECT COUNT(*) FROM messages \
+                  WHERE channel_id = $3 AND pinned_at IS NOT NULL AND is_deleted = false) < $4 \
+             RETURNING pinned_at"
+        )
+        .bind(id)
+        .bind(pinned_by)
+        .bind(channel_id)
+        .bind(max_pins)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let outcome = match pinned_at {
+            Some(pinned_at) => PinOutcome::Pinned(pinned_at),
+            None => {
+                let already_pinned = sqlx::query_scalar::<_, bool>(
+                    "SELECT pinned_at IS NOT NULL FROM messages WHERE id = $1"
+                )
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                if already_pinned { PinOutcome::AlreadyPinned } else { PinOutcome::LimitReached }
+            }
+        };
+
+        tx.commit().await?;
+
+        Ok(outcome)
+    }
+
+    async fn unpin(&self, id: i32) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE messages SET pinned_at = NULL, pinned_by = NULL WHERE id = $1 AND pinned_at IS NOT NULL"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
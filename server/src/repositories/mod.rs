@@ -2,8 +2,28 @@ pub mod user_repository;
 pub mod server_repository;
 pub mod channel_repository;
 pub mod message_repository;
+pub mod dm_repository;
+pub mod role_repository;
+pub mod audit_repository;
+pub mod invitation_repository;
+pub mod thread_repository;
+pub mod reaction_repository;
+pub mod mention_repository;
+pub mod read_state_repository;
+pub mod attachment_repository;
+pub mod session_repository;
 
 pub use user_repository::UserRepository;
 pub use server_repository::ServerRepository;
 pub use channel_repository::ChannelRepository;
 pub use message_repository::MessageRepository;
+pub use dm_repository::DmRepository;
+pub use role_repository::RoleRepository;
+pub use audit_repository::AuditLogRepository;
+pub use invitation_repository::InvitationRepository;
+pub use thread_repository::ThreadRepository;
+pub use reaction_repository::ReactionRepository;
+pub use mention_repository::MentionRepository;
+pub use read_state_repository::ReadStateRepository;
+pub use attachment_repository::AttachmentRepository;
+pub use session_repository::SessionRepository;
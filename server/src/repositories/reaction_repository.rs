@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+
+/// Trait définissant les opérations sur les réactions
+#[async_trait]
+pub trait ReactionRepositoryTrait: Send + Sync {
+    /// Ajouter une réaction (false si elle existait déjà)
+    async fn add(&self, message_id: i32, user_id: i32, emoji: &str) -> AppResult<bool>;
+    /// Retirer une réaction (false si elle n'existait pas)
+    async fn remove(&self, message_id: i32, user_id: i32, emoji: &str) -> AppResult<bool>;
+    async fn count(&self, message_id: i32, emoji: &str) -> AppResult<i64>;
+    /// Emojis distincts déjà utilisés sur un message
+    async fn find_emojis(&self, message_id: i32) -> AppResult<Vec<String>>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct ReactionRepository {
+    pool: PgPool,
+}
+
+impl ReactionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ReactionRepositoryTrait for ReactionRepository {
+    async fn add(&self, message_id: i32, user_id: i32, emoji: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO message_reactions (message_id, user_id, emoji) VALUES ($1, $2, $3) \
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn remove(&self, message_id: i32, user_id: i32, emoji: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "DELETE FROM message_reactions WHERE message_id = $1 AND user_id = $2 AND emoji = $3"
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn count(&self, message_id: i32, emoji: &str) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM message_reactions WHERE message_id = $1 AND emoji = $2"
+        )
+        .bind(message_id)
+        .bind(emoji)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn find_emojis(&self, message_id: i32) -> AppResult<Vec<String>> {
+        let emojis = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT emoji FROM message_reactions WHERE message_id = $1"
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(emojis)
+    }
+}
@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::ChannelReadState;
+
+/// Trait définissant les opérations sur les états de lecture
+#[async_trait]
+pub trait ReadStateRepositoryTrait: Send + Sync {
+    /// États de lecture d'un utilisateur pour plusieurs canaux
+    async fn find_states(&self, user_id: i32, channel_ids: &[i32]) -> AppResult<Vec<ChannelReadState>>;
+    /// Dernier message du canal lui-même (hors fils)
+    async fn find_last_message_id(&self, channel_id: i32) -> AppResult<Option<i32>>;
+    /// Avancer le dernier message lu (jamais en arrière) et marquer les mentions antérieures comme vues
+    async fn ack(&self, user_id: i32, channel_id: i32, message_id: i32) -> AppResult<()>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct ReadStateRepository {
+    pool: PgPool,
+}
+
+impl ReadStateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ReadStateRepositoryTrait for ReadStateRepository {
+    async fn find_states(&self, user_id: i32, channel_ids: &[i32]) -> AppResult<Vec<ChannelReadState>> {
+        let states = sqlx::query_as::<_, ChannelReadState>(
+            "SELECT c.id as channel_id, rs.last_read_message_id, \
+             (SELECT COUNT(*) FROM messages m \
+              WHERE m.channel_id = c.id AND m.thread_id IS NULL AND m.is_deleted = false \
+              AND m.author_id <> $1 AND m.id > COALESCE(rs.last_read_message_id, 0)) as unread_count, \
+             (SELECT COUNT(*) FROM message_mentions mm INNER JOIN messages m ON m.id = mm.message_id \
+              WHERE mm.user_id = $1 AND mm.seen_at IS NULL \
+              AND m.channel_id = c.id AND m.thread_id IS NULL AND m.is_deleted = false) as mention_count \
+             FROM channels c \
+             LEFT JOIN channel_read_states rs ON rs.channel_id = c.id AND rs.user_id = $1 \
+             WHERE c.id = ANY($2)"
+        )
+        .bind(user_id)
+        .bind(channel_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(states)
+    }
+
+    async fn find_last_message_id(&self, channel_id: i32) -> AppResult<Option<i32>> {
+        let id = sqlx::query_scalar::<_, Option<i32>>(
+            "SELECT MAX(id) FROM messages WHERE channel_id = $1 AND thread_id IS NULL"
+        )
+        .bind(channel_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn ack(&self, user_id: i32, channel_id: i32, message_id: i32) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO channel_read_states (user_id, channel_id, last_read_message_id) \
+             VALUES ($1, $2, $3) \
+             ON CONFLICT (user_id, channel_id) DO UPDATE SET \
+             last_read_message_id = GREATEST(channel_read_states.last_read_message_id, EXCLUDED.last_read_message_id), \
+             updated_at = CURRENT_TIMESTAMP"
+        )
+        .bind(user_id)
+        .bind(channel_id)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE message_mentions mm SET seen_at = CURRENT_TIMESTAMP \
+             FROM messages m \
+             WHERE m.id = mm.message_id AND mm.user_id = $1 AND mm.seen_at IS NULL \
+             AND m.channel_id = $2 AND m.thread_id IS NULL AND m.id <= $3"
+        )
+        .bind(user_id)
+        .bind(channel_id)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{Permissions, Role};
+
+/// Trait définissant les opérations sur les rôles
+#[async_trait]
+pub trait RoleRepositoryTrait: Send + Sync {
+    async fn create(&self, server_id: i32, name: &str, permissions: Permissions, position: i32, is_default: bool) -> AppResult<Role>;
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<Role>>;
+    async fn find_by_server(&self, server_id: i32) -> AppResult<Vec<Role>>;
+    async fn find_default(&self, server_id: i32) -> AppResult<Option<Role>>;
+    async fn update(&self, id: i32, name: &str, permissions: Permissions, position: i32) -> AppResult<Role>;
+    async fn delete(&self, id: i32) -> AppResult<()>;
+
+    // Rôles des membres
+    async fn get_member_roles(&self, server_id: i32, user_id: i32) -> AppResult<Vec<Role>>;
+    async fn set_member_roles(&self, server_id: i32, user_id: i32, role_ids: &[i32]) -> AppResult<()>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct RoleRepository {
+    pool: PgPool,
+}
+
+impl RoleRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RoleRepositoryTrait for RoleRepository {
+    async fn create(&self, server_id: i32, name: &str, permissions: Permissions, position: i32, is_default: bool) -> AppResult<Role> {
+        let role = sqlx::query_as::<_, Role>(
+            "INSERT INTO roles (server_id, name, permissions, position, is_default) VALUES ($1, $2, $3, $4, $5) \
+             RETURNING id, server_id, name, permissions, position, is_default, created_at"
+        )
+        .bind(server_id)
+        .bind(name)
+        .bind(permissions)
+        .bind(position)
+        .bind(is_default)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(role)
+    }
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<Role>> {
+        let role = sqlx::query_as::<_, Role>(
+            "SELECT id, server_id, name, permissions, position, is_default, created_at FROM roles WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(role)
+    }
+
+    async fn find_by_server(&self, server_id: i32) -> AppResult<Vec<Role>> {
+        let roles = sqlx::query_as::<_, Role>(
+            "SELECT id, server_id, name, permissions, position, is_default, created_at FROM roles \
+             WHERE server_id = $1 ORDER BY position DESC, id"
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(roles)
+    }
+
+    async fn find_default(&self, server_id: i32) -> AppResult<Option<Role>> {
+        let role = sqlx::query_as::<_, Role>(
+            "SELECT id, server_id, name, permissions, position, is_default, created_at FROM roles \
+             WHERE server_id = $1 AND is_default = true"
+        )
+        .bind(server_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(role)
+    }
+
+    async fn update(&self, id: i32, name: &str, permissions: Permissions, position: i32) -> AppResult<Role> {
+        let role = sqlx::query_as::<_, Role>(
+            "UPDATE roles SET name = $1, permissions = $2, position = $3 WHERE id = $4 \
+             RETURNING id, server_id, name, permissions, position, is_default, created_at"
+        )
+        .bind(name)
+        .bind(permissions)
+        .bind(position)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(role)
+    }
+
+    async fn delete(&self, id: i32) -> AppResult<()> {
+        sqlx::query("DELETE FROM roles WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_member_roles(&self, server_id: i32, user_id: i32) -> AppResult<Vec<Role>> {
+        let roles = sqlx::query_as::<_, Role>(
+            "SELECT r.id, r.server_id, r.name, r.permissions, r.position, r.is_default, r.created_at FROM roles r \
+             INNER JOIN member_roles mr ON mr.role_id = r.id \
+             WHERE mr.server_id = $1 AND mr.user_id = $2 ORDER BY r.position DESC, r.id"
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(roles)
+    }
+
+    async fn set_member_roles(&self, server_id: i32, user_id: i32, role_ids: &[i32]) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM member_roles WHERE server_id = $1 AND user_id = $2")
+            .bind(server_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO member_roles (server_id, user_id, role_id) SELECT $1, $2, UNNEST($3::int[])"
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .bind(role_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
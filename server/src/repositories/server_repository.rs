@@ -1,25 +1,56 @@
 use async_trait::async_trait;
-use sqlx::{PgPool, Row};
+use sqlx::PgPool;
 use crate::errors::AppResult;
-use crate::models::{Server, ServerMember, ServerMemberDetails, UserRole};
+use chrono::{DateTime, Utc};
+use crate::models::{Server, ServerBan, ServerMember, ServerMemberDetails};
+
+/// Sélection des détails d'un membre (rôles agrégés, exclusion temporaire active)
+const MEMBER_DETAILS_SELECT: &str = "SELECT u.id as user_id, u.username, \
+     u.display_name, u.avatar_attachment_id, u.bio, u.pronouns, (s.owner_id = u.id) as is_owner, \
+     COALESCE(ARRAY_AGG(mr.role_id ORDER BY mr.role_id) FILTER (WHERE mr.role_id IS NOT NULL), '{}') as role_ids, \
+     CASE WHEN sm.timeout_until > CURRENT_TIMESTAMP THEN sm.timeout_until END as timeout_until, \
+     sm.is_temporary, sm.joined_at \
+     FROM server_members sm \
+     INNER JOIN users u ON u.id = sm.user_id \
+     INNER JOIN servers s ON s.id = sm.server_id \
+     LEFT JOIN member_roles mr ON mr.server_id = sm.server_id AND mr.user_id = sm.user_id";
+
+/// Sélection d'un bannissement avec le nom de l'utilisateur banni
+const BAN_SELECT: &str = "SELECT b.server_id, b.user_id, u.username, b.reason, b.banned_by, b.expires_at, b.created_at \
+     FROM server_bans b INNER JOIN users u ON u.id = b.user_id";
 
 /// Trait définissant les opérations sur les serveurs
 #[async_trait]
 pub trait ServerRepositoryTrait: Send + Sync {
     async fn create(&self, name: &str, owner_id: i32, invitation_code: &str) -> AppResult<Server>;
     async fn find_by_id(&self, id: i32) -> AppResult<Option<Server>>;
-    async fn find_by_invitation_code(&self, code: &str) -> AppResult<Option<Server>>;
     async fn find_by_user(&self, user_id: i32) -> AppResult<Vec<Server>>;
     async fn update(&self, id: i32, name: &str) -> AppResult<Server>;
     async fn delete(&self, id: i32) -> AppResult<()>;
+
+    // Propriété
+    async fn find_owned(&self, owner_id: i32) -> AppResult<Vec<Server>>;
+    /// Membre permanent le plus haut placé (rôle de plus haute position, puis ancienneté)
+    async fn find_successor(&self, server_id: i32, owner_id: i32) -> AppResult<Option<i32>>;
+    /// Changer de propriétaire et échanger leurs rôles, si `owner_id` l'est toujours
+    /// et `new_owner_id` est membre permanent
+    async fn transfer_ownership(&self, server_id: i32, owner_id: i32, new_owner_id: i32) -> AppResult<Option<Server>>;
     
     // Gestion des membres
-    async fn add_member(&self, server_id: i32, user_id: i32, role: UserRole) -> AppResult<ServerMember>;
+    async fn add_member(&self, server_id: i32, user_id: i32) -> AppResult<ServerMember>;
     async fn remove_member(&self, server_id: i32, user_id: i32) -> AppResult<()>;
-    async fn get_member_role(&self, server_id: i32, user_id: i32) -> AppResult<Option<UserRole>>;
-    async fn update_member_role(&self, server_id: i32, user_id: i32, role: UserRole) -> AppResult<()>;
     async fn get_members(&self, server_id: i32) -> AppResult<Vec<ServerMemberDetails>>;
+    async fn find_member(&self, server_id: i32, user_id: i32) -> AppResult<Option<ServerMemberDetails>>;
     async fn is_member(&self, server_id: i32, user_id: i32) -> AppResult<bool>;
+
+    // Modération
+    async fn ban_member(&self, server_id: i32, user_id: i32, reason: Option<&str>, banned_by: i32, expires_at: Option<DateTime<Utc>>) -> AppResult<Option<ServerBan>>;
+    async fn find_active_ban(&self, server_id: i32, user_id: i32) -> AppResult<Option<ServerBan>>;
+    async fn find_bans(&self, server_id: i32) -> AppResult<Vec<ServerBan>>;
+    async fn delete_ban(&self, server_id: i32, user_id: i32) -> AppResult<bool>;
+    async fn set_timeout(&self, server_id: i32, user_id: i32, until: Option<DateTime<Utc>>) -> AppResult<()>;
+    async fn get_timeout(&self, server_id: i32, user_id: i32) -> AppResult<Option<DateTime<Utc>>>;
+    async fn remove_temporary_memberships(&self, user_id: i32) -> AppResult<Vec<i32>>;
 }
 
 /// Implémentation PostgreSQL
@@ -62,18 +93,6 @@ impl ServerRepositoryTrait for ServerRepository {
         Ok(server)
     }
 
-    async fn find_by_invitation_code(&self, code: &str) -> AppResult<Option<Server>> {
-        let server = sqlx::query_as!(
-            Server,
-            "SELECT id, name, owner_id, invitation_code, created_at FROM servers WHERE invitation_code = $1",
-            code
-        )
-        .fetch_optional(&self.pool)
-        .await?;
-
-        Ok(server)
-    }
-
     async fn find_by_user(&self, user_id: i32) -> AppResult<Vec<Server>> {
         let servers = sqlx::query_as!(
             Server,
@@ -107,36 +126,87 @@ impl ServerRepositoryTrait for ServerRepository {
         Ok(())
     }
 
-    async fn add_member(&self, server_id: i32, user_id: i32, role: UserRole) -> AppResult<ServerMember> {
-        let role_str = match role {
-            UserRole::Owner => "OWNER",
-            UserRole::Admin => "ADMIN",
-            UserRole::Member => "MEMBER",
+    async fn find_owned(&self, owner_id: i32) -> AppResult<Vec<Server>> {
+        let servers = sqlx::query_as::<_, Server>(
+            "SELECT id, name, owner_id, invitation_code, created_at FROM servers WHERE owner_id = $1 ORDER BY id"
+        )
+        .bind(owner_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    async fn find_successor(&self, server_id: i32, owner_id: i32) -> AppResult<Option<i32>> {
+        let user_id = sqlx::query_scalar::<_, i32>(
+            "SELECT sm.user_id FROM server_members sm \
+             LEFT JOIN member_roles mr ON mr.server_id = sm.server_id AND mr.user_id = sm.user_id \
+             LEFT JOIN roles r ON r.id = mr.role_id \
+             WHERE sm.server_id = $1 AND sm.user_id <> $2 AND sm.is_temporary = false \
+             GROUP BY sm.user_id, sm.joined_at \
+             ORDER BY COALESCE(MAX(r.position), 0) DESC, sm.joined_at, sm.user_id \
+             LIMIT 1"
+        )
+        .bind(server_id)
+        .bind(owner_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user_id)
+    }
+
+    async fn transfer_ownership(&self, server_id: i32, owner_id: i32, new_owner_id: i32) -> AppResult<Option<Server>> {
+        let mut tx = self.pool.begin().await?;
+
+        let server = sqlx::query_as::<_, Server>(
+            "UPDATE servers SET owner_id = $3 WHERE id = $1 AND owner_id = $2 \
+             AND EXISTS (SELECT 1 FROM server_members \
+                         WHERE server_id = $1 AND user_id = $3 AND is_temporary = false) \
+             RETURNING id, name, owner_id, invitation_code, created_at"
+        )
+        .bind(server_id)
+        .bind(owner_id)
+        .bind(new_owner_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(server) = server else {
+            return Ok(None);
         };
 
-        let row = sqlx::query(
-            "INSERT INTO server_members (server_id, user_id, role) VALUES ($1, $2, $3) RETURNING server_id, user_id, role, joined_at"
+        // L'ancien propriétaire prend les rôles du nouveau et inversement ;
+        // les rôles communs restent en place (la clé primaire interdit le doublon)
+        sqlx::query(
+            "UPDATE member_roles \
+             SET user_id = CASE WHEN user_id = $2 THEN $3 ELSE $2 END \
+             WHERE server_id = $1 AND user_id IN ($2, $3) \
+             AND role_id NOT IN ( \
+                 SELECT role_id FROM member_roles \
+                 WHERE server_id = $1 AND user_id IN ($2, $3) \
+                 GROUP BY role_id HAVING COUNT(*) = 2 \
+             )"
+        )
+        .bind(server_id)
+        .bind(owner_id)
+        .bind(new_owner_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(server))
+    }
+
+    async fn add_member(&self, server_id: i32, user_id: i32) -> AppResult<ServerMember> {
+        let member = sqlx::query_as::<_, ServerMember>(
+            "INSERT INTO server_members (server_id, user_id) VALUES ($1, $2) RETURNING server_id, user_id, joined_at"
         )
         .bind(server_id)
         .bind(user_id)
-        .bind(role_str)
         .fetch_one(&self.pool)
         .await?;
 
-        let role_db: String = row.get("role");
-        let role_enum = match role_db.as_str() {
-            "OWNER" => UserRole::Owner,
-            "ADMIN" => UserRole::Admin,
-            "MEMBER" => UserRole::Member,
-            _ => UserRole::Member,
-        };
-
-        Ok(ServerMember {
-            server_id: row.get("server_id"),
-            user_id: row.get("user_id"),
-            role: role_enum,
-            joined_at: row.get("joined_at"),
-        })
+        Ok(member)
     }
 
     async fn remove_member(&self, server_id: i32, user_id: i32) -> AppResult<()> {
@@ -151,86 +221,154 @@ impl ServerRepositoryTrait for ServerRepository {
         Ok(())
     }
 
-    async fn get_member_role(&self, server_id: i32, user_id: i32) -> AppResult<Option<UserRole>> {
-        let row = sqlx::query(
-            "SELECT role FROM server_members WHERE server_id = $1 AND user_id = $2"
-        )
+    async fn get_members(&self, server_id: i32) -> AppResult<Vec<ServerMemberDetails>> {
+        let members = sqlx::query_as::<_, ServerMemberDetails>(&format!(
+            "{} WHERE sm.server_id = $1 \
+             GROUP BY u.id, s.owner_id, sm.timeout_until, sm.is_temporary, sm.joined_at \
+             ORDER BY sm.joined_at, u.id",
+            MEMBER_DETAILS_SELECT
+        ))
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+
+    async fn find_member(&self, server_id: i32, user_id: i32) -> AppResult<Option<ServerMemberDetails>> {
+        let member = sqlx::query_as::<_, ServerMemberDetails>(&format!(
+            "{} WHERE sm.server_id = $1 AND sm.user_id = $2 \
+             GROUP BY u.id, s.owner_id, sm.timeout_until, sm.is_temporary, sm.joined_at",
+            MEMBER_DETAILS_SELECT
+        ))
         .bind(server_id)
         .bind(user_id)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.and_then(|r| {
-            let role_str: String = r.get("role");
-            match role_str.as_str() {
-                "OWNER" => Some(UserRole::Owner),
-                "ADMIN" => Some(UserRole::Admin),
-                "MEMBER" => Some(UserRole::Member),
-                _ => None,
-            }
-        }))
+        Ok(member)
     }
 
-    async fn update_member_role(&self, server_id: i32, user_id: i32, role: UserRole) -> AppResult<()> {
-        let role_str = match role {
-            UserRole::Owner => "OWNER",
-            UserRole::Admin => "ADMIN",
-            UserRole::Member => "MEMBER",
-        };
+    async fn is_member(&self, server_id: i32, user_id: i32) -> AppResult<bool> {
+        let result = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM server_members WHERE server_id = $1 AND user_id = $2) as exists",
+            server_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
 
-        sqlx::query(
-            "UPDATE server_members SET role = $1 WHERE server_id = $2 AND user_id = $3"
+        Ok(result.exists.unwrap_or(false))
+    }
+
+    async fn ban_member(&self, server_id: i32, user_id: i32, reason: Option<&str>, banned_by: i32, expires_at: Option<DateTime<Utc>>) -> AppResult<Option<ServerBan>> {
+        let mut tx = self.pool.begin().await?;
+
+        // Le bannissement retire aussi l'appartenance au serveur
+        sqlx::query("DELETE FROM server_members WHERE server_id = $1 AND user_id = $2")
+            .bind(server_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let ban = sqlx::query_as::<_, ServerBan>(
+            "WITH b AS ( \
+                 INSERT INTO server_bans (server_id, user_id, reason, banned_by, expires_at) \
+                 SELECT $1, id, $3, $4, $5 FROM users WHERE id = $2 \
+                 ON CONFLICT (server_id, user_id) DO UPDATE \
+                 SET reason = EXCLUDED.reason, banned_by = EXCLUDED.banned_by, expires_at = EXCLUDED.expires_at, created_at = CURRENT_TIMESTAMP \
+                 RETURNING * \
+             ) \
+             SELECT b.server_id, b.user_id, u.username, b.reason, b.banned_by, b.expires_at, b.created_at \
+             FROM b INNER JOIN users u ON u.id = b.user_id"
         )
-        .bind(role_str)
         .bind(server_id)
         .bind(user_id)
-        .execute(&self.pool)
+        .bind(reason)
+        .bind(banned_by)
+        .bind(expires_at)
+        .fetch_optional(&mut *tx)
         .await?;
 
-        Ok(())
+        tx.commit().await?;
+
+        Ok(ban)
     }
 
-    async fn get_members(&self, server_id: i32) -> AppResult<Vec<ServerMemberDetails>> {
-        let rows = sqlx::query(
-            "SELECT u.id, u.username, sm.role, sm.joined_at FROM users u INNER JOIN server_members sm ON u.id = sm.user_id WHERE sm.server_id = $1"
-        )
+    async fn find_active_ban(&self, server_id: i32, user_id: i32) -> AppResult<Option<ServerBan>> {
+        let ban = sqlx::query_as::<_, ServerBan>(&format!(
+            "{} WHERE b.server_id = $1 AND b.user_id = $2 \
+             AND (b.expires_at IS NULL OR b.expires_at > CURRENT_TIMESTAMP)",
+            BAN_SELECT
+        ))
+        .bind(server_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(ban)
+    }
+
+    async fn find_bans(&self, server_id: i32) -> AppResult<Vec<ServerBan>> {
+        let bans = sqlx::query_as::<_, ServerBan>(&format!(
+            "{} WHERE b.server_id = $1 \
+             AND (b.expires_at IS NULL OR b.expires_at > CURRENT_TIMESTAMP) \
+             ORDER BY b.created_at DESC",
+            BAN_SELECT
+        ))
         .bind(server_id)
         .fetch_all(&self.pool)
         .await?;
 
-        let members = rows.into_iter().filter_map(|row| {
-            let user_id: i32 = row.get("id");
-            let username: String = row.get("username");
-            let role_str: String = row.get("role");
-            let joined_at: chrono::DateTime<chrono::Utc> = row.get("joined_at");
-            
-            let role = match role_str.as_str() {
-                "OWNER" => UserRole::Owner,
-                "ADMIN" => UserRole::Admin,
-                "MEMBER" => UserRole::Member,
-                _ => return None,
-            };
-            
-            Some(ServerMemberDetails {
-                user_id,
-                username,
-                role,
-                joined_at,
-            })
-        }).collect();
+        Ok(bans)
+    }
 
-        Ok(members)
+    async fn delete_ban(&self, server_id: i32, user_id: i32) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM server_bans WHERE server_id = $1 AND user_id = $2")
+            .bind(server_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 
-    async fn is_member(&self, server_id: i32, user_id: i32) -> AppResult<bool> {
-        let result = sqlx::query!(
-            "SELECT EXISTS(SELECT 1 FROM server_members WHERE server_id = $1 AND user_id = $2) as exists",
-            server_id,
-            user_id
+    async fn set_timeout(&self, server_id: i32, user_id: i32, until: Option<DateTime<Utc>>) -> AppResult<()> {
+        sqlx::query("UPDATE server_members SET timeout_until = $1 WHERE server_id = $2 AND user_id = $3")
+            .bind(until)
+            .bind(server_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_timeout(&self, server_id: i32, user_id: i32) -> AppResult<Option<DateTime<Utc>>> {
+        let until: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT timeout_until FROM server_members \
+             WHERE server_id = $1 AND user_id = $2 AND timeout_until > CURRENT_TIMESTAMP"
         )
-        .fetch_one(&self.pool)
+        .bind(server_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(until)
+    }
+
+    async fn remove_temporary_memberships(&self, user_id: i32) -> AppResult<Vec<i32>> {
+        // Les membres temporaires qui ont reçu un rôle sont conservés
+        let server_ids = sqlx::query_scalar::<_, i32>(
+            "DELETE FROM server_members sm WHERE sm.user_id = $1 AND sm.is_temporary = true \
+             AND NOT EXISTS (SELECT 1 FROM member_roles mr WHERE mr.server_id = sm.server_id AND mr.user_id = sm.user_id) \
+             RETURNING sm.server_id"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(result.exists.unwrap_or(false))
+        Ok(server_ids)
     }
 }
@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::Session;
+
+/// Trait définissant les opérations sur les sessions de connexion
+#[async_trait]
+pub trait SessionRepositoryTrait: Send + Sync {
+    async fn create(
+        &self,
+        user_id: i32,
+        refresh_token_hash: &str,
+        user_agent: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> AppResult<Session>;
+
+    /// Session active dont le refresh token actuel a ce hash
+    async fn find_active_by_token_hash(&self, refresh_token_hash: &str) -> AppResult<Option<Session>>;
+
+    /// Session dont le refresh token précédent a ce hash (token déjà renouvelé)
+    async fn find_by_previous_hash(&self, refresh_token_hash: &str) -> AppResult<Option<Session>>;
+
+    /// Remplacer le refresh token d'une session, s'il est toujours `current_hash`
+    async fn rotate(
+        &self,
+        id: i32,
+        current_hash: &str,
+        new_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> AppResult<Option<Session>>;
+
+    /// La session existe, appartient à l'utilisateur, n'est ni révoquée ni expirée
+    async fn is_active(&self, id: i32, user_id: i32) -> AppResult<bool>;
+    async fn list_active(&self, user_id: i32) -> AppResult<Vec<Session>>;
+
+    /// Révoquer une session de l'utilisateur ; `false` si elle n'était pas active
+    async fn revoke(&self, id: i32, user_id: i32) -> AppResult<bool>;
+    /// Révoquer toutes les sessions de l'utilisateur sauf `keep_id` ; retourne les sessions révoquées
+    async fn revoke_others(&self, user_id: i32, keep_id: i32) -> AppResult<Vec<i32>>;
+
+    /// Supprimer les sessions expirées ou révoquées avant une date
+    async fn delete_inactive_before(&self, before: DateTime<Utc>) -> AppResult<u64>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct SessionRepository {
+    pool: PgPool,
+}
+
+impl SessionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SessionRepositoryTrait for SessionRepository {
+    async fn create(
+        &self,
+        user_id: i32,
+        refresh_token_hash: &str,
+        user_agent: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> AppResult<Session> {
+        let session = sqlx::query_as::<_, Session>(
+            "INSERT INTO sessions (user_id, refresh_token_hash, user_agent, expires_at) \
+             VALUES ($1, $2, $3, $4) RETURNING *",
+        )
+        .bind(user_id)
+        .bind(refresh_token_hash)
+        .bind(user_agent)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn find_active_by_token_hash(&self, refresh_token_hash: &str) -> AppResult<Option<Session>> {
+        let session = sqlx::query_as::<_, Session>(
+            "SELECT * FROM sessions \
+             WHERE refresh_token_hash = $1 AND revoked_at IS NULL AND expires_at > NOW()",
+        )
+        .bind(refresh_token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn find_by_previous_hash(&self, refresh_token_hash: &str) -> AppResult<Option<Session>> {
+        let session = sqlx::query_as::<_, Session>(
+            "SELECT * FROM sessions WHERE previous_token_hash = $1",
+        )
+        .bind(refresh_token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn rotate(
+        &self,
+        id: i32,
+        current_hash: &str,
+        new_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> AppResult<Option<Session>> {
+        // Conditionné au token actuel : deux renouvellements simultanés ne passent pas tous les deux
+        let session = sqlx::query_as::<_, Session>(
+            "UPDATE sessions \
+             SET previous_token_hash = refresh_token_hash, refresh_token_hash = $3, \
+                 last_used_at = NOW(), expires_at = $4 \
+             WHERE id = $1 AND refresh_token_hash = $2 AND revoked_at IS NULL \
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(current_hash)
+        .bind(new_hash)
+        .bind(expires_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn is_active(&self, id: i32, user_id: i32) -> AppResult<bool> {
+        let active: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM sessions \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW())",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(active)
+    }
+
+    async fn list_active(&self, user_id: i32) -> AppResult<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(
+            "SELECT * FROM sessions \
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW() \
+             ORDER BY last_used_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    async fn revoke(&self, id: i32, user_id: i32) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE sessions SET revoked_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn revoke_others(&self, user_id: i32, keep_id: i32) -> AppResult<Vec<i32>> {
+        let session_ids = sqlx::query_scalar::<_, i32>(
+            "UPDATE sessions SET revoked_at = NOW() \
+             WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL \
+             RETURNING id",
+        )
+        .bind(user_id)
+        .bind(keep_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(session_ids)
+    }
+
+    async fn delete_inactive_before(&self, before: DateTime<Utc>) -> AppResult<u64> {
+        let result = sqlx::query(
+            "DELETE FROM sessions WHERE expires_at < $1 OR revoked_at < $1",
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
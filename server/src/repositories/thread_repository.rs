@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use crate::errors::AppResult;
+use crate::models::{Thread, ThreadMember};
+
+/// Fil avec son activité (messages visibles, participants, dernier message)
+const THREAD_SELECT: &str =
+    "SELECT t.id, t.channel_id, t.root_message_id, t.name, t.creator_id, \
+     (SELECT COUNT(*) FROM messages m WHERE m.thread_id = t.id AND m.is_deleted = false) as message_count, \
+     (SELECT COUNT(*) FROM thread_members tm WHERE tm.thread_id = t.id) as member_count, \
+     (SELECT MAX(m.created_at) FROM messages m WHERE m.thread_id = t.id AND m.is_deleted = false) as last_message_at, \
+     t.created_at \
+     FROM threads t";
+
+/// Trait définissant les opérations sur les fils de discussion
+#[async_trait]
+pub trait ThreadRepositoryTrait: Send + Sync {
+    /// Créer un fil ; son créateur et l'auteur du message d'origine y participent d'office
+    async fn create(
+        &self,
+        channel_id: i32,
+        root_message_id: i32,
+        root_author_id: i32,
+        name: &str,
+        creator_id: i32,
+    ) -> AppResult<Thread>;
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<Thread>>;
+    async fn find_by_root_message(&self, root_message_id: i32) -> AppResult<Option<Thread>>;
+    async fn find_by_channel(&self, channel_id: i32) -> AppResult<Vec<Thread>>;
+    async fn add_member(&self, thread_id: i32, user_id: i32) -> AppResult<()>;
+    async fn remove_member(&self, thread_id: i32, user_id: i32) -> AppResult<()>;
+    async fn get_members(&self, thread_id: i32) -> AppResult<Vec<ThreadMember>>;
+}
+
+/// Implémentation PostgreSQL
+#[derive(Clone)]
+pub struct ThreadRepository {
+    pool: PgPool,
+}
+
+impl ThreadRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ThreadRepositoryTrait for ThreadRepository {
+    async fn create(
+        &self,
+        channel_id: i32,
+        root_message_id: i32,
+        root_author_id: i32,
+        name: &str,
+        creator_id: i32,
+    ) -> AppResult<Thread> {
+        let mut tx = self.pool.begin().await?;
+
+        let thread_id = sqlx::query_scalar::<_, i32>(
+            "INSERT INTO threads (channel_id, root_message_id, name, creator_id) \
+             VALUES ($1, $2, $3, $4) RETURNING id"
+        )
+        .bind(channel_id)
+        .bind(root_message_id)
+        .bind(name)
+        .bind(creator_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO thread_members (thread_id, user_id) \
+             SELECT $1, u.id FROM users u WHERE u.id IN ($2, $3) \
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(thread_id)
+        .bind(creator_id)
+        .bind(root_author_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let thread = sqlx::query_as::<_, Thread>(&format!("{} WHERE t.id = $1", THREAD_SELECT))
+            .bind(thread_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(thread)
+    }
+
+    async fn find_by_id(&self, id: i32) -> AppResult<Option<Thread>> {
+        let thread = sqlx::query_as::<_, Thread>(&format!("{} WHERE t.id = $1", THREAD_SELECT))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(thread)
+    }
+
+    async fn find_by_root_message(&self, root_message_id: i32) -> AppResult<Option<Thread>> {
+        let thread = sqlx::query_as::<_, Thread>(&format!(
+            "{} WHERE t.root_message_id = $1",
+            THREAD_SELECT
+        ))
+        .bind(root_message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(thread)
+    }
+
+    async fn find_by_channel(&self, channel_id: i32) -> AppResult<Vec<Thread>> {
+        // Les fils les plus actifs en premier
+        let threads = sqlx::query_as::<_, Thread>(&format!(
+            "SELECT * FROM ({} WHERE t.channel_id = $1) threads \
+             ORDER BY COALESCE(last_message_at, created_at) DESC, id DESC",
+            THREAD_SELECT
+        ))
+        .bind(channel_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(threads)
+    }
+
+    async fn add_member(&self, thread_id: i32, user_id: i32) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO thread_members (thread_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+        )
+        .bind(thread_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_member(&self, thread_id: i32, user_id: i32) -> AppResult<()> {
+        sqlx::query("DELETE FROM thread_members WHERE thread_id = $1 AND user_id = $2")
+            .bind(thread_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_members(&self, thread_id: i32) -> AppResult<Vec<ThreadMember>> {
+        let members = sqlx::query_as::<_, ThreadMember>(
+            "SELECT u.id as user_id, u.username, tm.joined_at \
+             FROM thread_members tm INNER JOIN users u ON u.id = tm.user_id \
+             WHERE tm.thread_id = $1 ORDER BY tm.joined_at, u.id"
+        )
+        .bind(thread_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+}
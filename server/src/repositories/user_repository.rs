@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use sqlx::PgPool;
 use crate::errors::AppResult;
-use crate::models::{User, CreateUserDto};
+use crate::models::{User, CreateUserDto, PresenceSettings, PresenceSettingsRow};
+
+const USER_COLUMNS: &str =
+    "id, username, email, password_hash, created_at, display_name, avatar_attachment_id, bio, pronouns";
 
 /// Trait définissant les opérations sur les utilisateurs
 #[async_trait]
@@ -11,16 +14,14 @@ pub trait UserRepositoryTrait: Send + Sync {
     async fn find_by_username(&self, username: &str) -> AppResult<Option<User>>;
     async fn find_by_email(&self, email: &str) -> AppResult<Option<User>>;
 
-    // Ajout pour update/delete
-    async fn update(
-        &self,
-        id: i32,
-        username: &str,
-        email: &str,
-        password_hash: &str,
-    ) -> AppResult<User>;
+    /// Enregistrer le compte et le profil tels que modifiés dans `user`
+    async fn update(&self, user: &User) -> AppResult<User>;
 
     async fn delete(&self, id: i32) -> AppResult<()>;
+
+    /// Statut de présence choisi et statut personnalisé
+    async fn find_presence_settings(&self, id: i32) -> AppResult<Option<PresenceSettings>>;
+    async fn update_presence_settings(&self, id: i32, settings: &PresenceSettings) -> AppResult<()>;
 }
 
 /// Implémentation PostgreSQL du repository utilisateur
@@ -38,13 +39,13 @@ impl UserRepository {
 #[async_trait]
 impl UserRepositoryTrait for UserRepository {
     async fn create(&self, dto: CreateUserDto, password_hash: &str) -> AppResult<User> {
-        let user = sqlx::query_as!(
-            User,
-            "INSERT INTO users (username, email, password_hash) VALUES ($1, $2, $3) RETURNING id, username, email, password_hash, created_at",
-            dto.username,
-            dto.email,
-            password_hash
-        )
+        let user = sqlx::query_as::<_, User>(&format!(
+            "INSERT INTO users (username, email, password_hash) VALUES ($1, $2, $3) RETURNING {}",
+            USER_COLUMNS
+        ))
+        .bind(dto.username)
+        .bind(dto.email)
+        .bind(password_hash)
         .fetch_one(&self.pool)
         .await?;
 
@@ -52,11 +53,11 @@ impl UserRepositoryTrait for UserRepository {
     }
 
     async fn find_by_id(&self, id: i32) -> AppResult<Option<User>> {
-        let user = sqlx::query_as!(
-            User,
-            "SELECT id, username, email, password_hash, created_at FROM users WHERE id = $1",
-            id
-        )
+        let user = sqlx::query_as::<_, User>(&format!(
+            "SELECT {} FROM users WHERE id = $1",
+            USER_COLUMNS
+        ))
+        .bind(id)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -64,11 +65,11 @@ impl UserRepositoryTrait for UserRepository {
     }
 
     async fn find_by_username(&self, username: &str) -> AppResult<Option<User>> {
-        let user = sqlx::query_as!(
-            User,
-            "SELECT id, username, email, password_hash, created_at FROM users WHERE username = $1",
-            username
-        )
+        let user = sqlx::query_as::<_, User>(&format!(
+            "SELECT {} FROM users WHERE username = $1",
+            USER_COLUMNS
+        ))
+        .bind(username)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -76,11 +77,11 @@ impl UserRepositoryTrait for UserRepository {
     }
 
     async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
-        let user = sqlx::query_as!(
-            User,
-            "SELECT id, username, email, password_hash, created_at FROM users WHERE email = $1",
-            email
-        )
+        let user = sqlx::query_as::<_, User>(&format!(
+            "SELECT {} FROM users WHERE email = $1",
+            USER_COLUMNS
+        ))
+        .bind(email)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -88,21 +89,20 @@ impl UserRepositoryTrait for UserRepository {
     }
 
     // ---- Update ----
-    async fn update(
-        &self,
-        id: i32,
-        username: &str,
-        email: &str,
-        password_hash: &str,
-    ) -> AppResult<User> {
-        let user = sqlx::query_as!(
-            User,
-            "UPDATE users SET username = $1, email = $2, password_hash = $3 WHERE id = $4 RETURNING id, username, email, password_hash, created_at",
-            username,
-            email,
-            password_hash,
-            id
-        )
+    async fn update(&self, user: &User) -> AppResult<User> {
+        let user = sqlx::query_as::<_, User>(&format!(
+            "UPDATE users SET username = $1, email = $2, password_hash = $3, display_name = $4, \
+             avatar_attachment_id = $5, bio = $6, pronouns = $7 WHERE id = $8 RETURNING {}",
+            USER_COLUMNS
+        ))
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.password_hash)
+        .bind(&user.display_name)
+        .bind(user.avatar_attachment_id)
+        .bind(&user.bio)
+        .bind(&user.pronouns)
+        .bind(user.id)
         .fetch_one(&self.pool)
         .await?;
 
@@ -120,4 +120,27 @@ impl UserRepositoryTrait for UserRepository {
 
         Ok(())
     }
+
+    // ---- Présence ----
+    async fn find_presence_settings(&self, id: i32) -> AppResult<Option<PresenceSettings>> {
+        let row = sqlx::query_as::<_, PresenceSettingsRow>(
+            "SELECT presence_status, custom_status FROM users WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(PresenceSettings::from))
+    }
+
+    async fn update_presence_settings(&self, id: i32, settings: &PresenceSettings) -> AppResult<()> {
+        sqlx::query("UPDATE users SET presence_status = $1, custom_status = $2 WHERE id = $3")
+            .bind(settings.status.as_str())
+            .bind(settings.custom_status.as_deref())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,351 @@
+use std::io::Cursor;
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Attachment, AttachmentContent, NewAttachment, Permissions, UploadedFile};
+use crate::repositories::attachment_repository::{AttachmentRepository, AttachmentRepositoryTrait};
+use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
+use crate::repositories::message_repository::{MessageRepository, MessageRepositoryTrait};
+use crate::services::PermissionService;
+use crate::storage::Storage;
+
+/// Nombre maximal de pièces jointes par message (et par envoi)
+pub const MAX_ATTACHMENTS_PER_MESSAGE: usize = 10;
+
+/// Côté le plus long d'une miniature, en pixels
+const THUMBNAIL_SIZE: u32 = 400;
+
+/// Dimensions maximales d'une image décodée (au-delà, fichier sans miniature)
+const MAX_IMAGE_DIMENSION: u32 = 12_000;
+
+/// Durée de conservation d'une pièce jointe jamais rattachée à un message
+const PENDING_TTL_HOURS: i64 = 24;
+
+/// Service gérant l'envoi et la lecture des pièces jointes
+#[derive(Clone)]
+pub struct AttachmentService {
+    attachment_repo: Arc<AttachmentRepository>,
+    channel_repo: Arc<ChannelRepository>,
+    message_repo: Arc<MessageRepository>,
+    permission_service: Arc<PermissionService>,
+    storage: Arc<dyn Storage>,
+    /// Taille maximale d'un fichier, en octets
+    max_size: usize,
+}
+
+impl AttachmentService {
+    pub fn new(
+        attachment_repo: Arc<AttachmentRepository>,
+        channel_repo: Arc<ChannelRepository>,
+        message_repo: Arc<MessageRepository>,
+        permission_service: Arc<PermissionService>,
+        storage: Arc<dyn Storage>,
+        max_size: usize,
+    ) -> Self {
+        Self {
+            attachment_repo,
+            channel_repo,
+            message_repo,
+            permission_service,
+            storage,
+            max_size,
+        }
+    }
+
+    /// Taille maximale d'un fichier, en octets
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Erreur renvoyée pour un fichier trop volumineux
+    pub fn too_large(&self) -> AppError {
+        AppError::AttachmentTooLarge((self.max_size / (1024 * 1024)) as u64)
+    }
+
+    /// Envoyer des fichiers dans un canal, avant de les joindre à un message
+    pub async fn upload(
+        &self,
+        channel_id: i32,
+        files: Vec<UploadedFile>,
+        user_id: i32,
+    ) -> AppResult<Vec<Attachment>> {
+        let channel = self
+            .channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        // Mêmes conditions que pour écrire un message
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::SEND_MESSAGES,
+            )
+            .await?;
+        self.permission_service
+            .check_not_timed_out(channel.server_id, user_id)
+            .await?;
+
+        if files.is_empty() {
+            return Err(AppError::ValidationError("Aucun fichier reçu".to_string()));
+        }
+        if files.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+            return Err(AppError::ValidationError(format!(
+                "{} fichiers maximum par envoi",
+                MAX_ATTACHMENTS_PER_MESSAGE
+            )));
+        }
+        if files.iter().any(|file| file.data.is_empty()) {
+            return Err(AppError::ValidationError("Un fichier est vide".to_string()));
+        }
+        if files.iter().any(|file| file.data.len() > self.max_size) {
+            return Err(self.too_large());
+        }
+
+        let mut attachments = Vec::with_capacity(files.len());
+        for file in files {
+            attachments.push(self.store(channel.id, file, user_id).await?);
+        }
+
+        Ok(attachments)
+    }
+
+    /// Récupérer une pièce jointe et son contenu (ou sa miniature)
+    ///
+    /// Il faut pouvoir lire l'historique du canal ; une pièce jointe en attente
+    /// n'est visible que de celui qui l'a envoyée, sauf s'il en a fait son avatar.
+    pub async fn download(
+        &self,
+        attachment_id: i32,
+        user_id: i32,
+        thumbnail: bool,
+    ) -> AppResult<AttachmentContent> {
+        let attachment = self
+            .attachment_repo
+            .find_by_id(attachment_id)
+            .await?
+            .ok_or(AppError::AttachmentNotFound)?;
+
+        // Avatar : visible de tout utilisateur, comme les profils des membres
+        if !self.attachment_repo.is_avatar(attachment.id).await? {
+            match attachment.message_id {
+                Some(message_id) => {
+                    self.message_repo
+                        .find_by_id(message_id)
+                        .await?
+                        .filter(|message| !message.is_deleted)
+                        .ok_or(AppError::AttachmentNotFound)?;
+                }
+                None if attachment.uploader_id != Some(user_id) => {
+                    return Err(AppError::AttachmentNotFound);
+                }
+                None => {}
+            }
+
+            let channel = self
+                .channel_repo
+                .find_by_id(attachment.channel_id)
+                .await?
+                .ok_or(AppError::AttachmentNotFound)?;
+
+            self.permission_service
+                .check(
+                    user_id,
+                    channel.server_id,
+                    Some(channel.id),
+                    Permissions::VIEW_CHANNELS | Permissions::READ_MESSAGE_HISTORY,
+                )
+                .await?;
+        }
+
+        if !thumbnail {
+            let data = self.storage.get(&attachment.storage_key).await?;
+            return Ok(AttachmentContent {
+                content_type: attachment.content_type.clone(),
+                attachment,
+                data,
+            });
+        }
+
+        let key = attachment.thumbnail_key.as_deref().ok_or(AppError::AttachmentNotFound)?;
+        let data = self.storage.get(key).await?;
+        // Miniature en JPEG ou en PNG selon la transparence de l'image
+        let content_type = image::guess_format(&data)
+            .map(|format| format.to_mime_type())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        Ok(AttachmentContent {
+            attachment,
+            content_type,
+            data,
+        })
+    }
+
+    /// Supprimer les pièces jointes envoyées mais jamais rattachées à un message
+    pub async fn purge_pending(&self) -> AppResult<usize> {
+        let expired = self
+            .attachment_repo
+            .delete_pending_before(Utc::now() - Duration::hours(PENDING_TTL_HOURS))
+            .await?;
+
+        for attachment in &expired {
+            self.delete_files(attachment).await;
+        }
+        if !expired.is_empty() {
+            info!("{} pièce(s) jointe(s) en attente supprimée(s)", expired.len());
+        }
+
+        Ok(expired.len())
+    }
+
+    /// Analyser, stocker puis enregistrer un fichier
+    async fn store(&self, channel_id: i32, file: UploadedFile, user_id: i32) -> AppResult<Attachment> {
+        // Empreinte et décodage des images hors du runtime asynchrone
+        let (file, processed) = tokio::task::spawn_blocking(move || {
+            let processed = process(&file.data);
+            (file, processed)
+        })
+        .await
+        .map_err(|_| AppError::InternalServerError)?;
+
+        let storage_key = format!("{}/{}", channel_id, Uuid::new_v4());
+        let thumbnail_key = processed.thumbnail.as_ref().map(|_| format!("{}.thumb", storage_key));
+
+        self.storage.put(&storage_key, &file.data).await?;
+        if let (Some(key), Some(thumbnail)) = (&thumbnail_key, &processed.thumbnail) {
+            self.storage.put(key, thumbnail).await?;
+        }
+
+        let new_attachment = NewAttachment {
+            channel_id,
+            uploader_id: user_id,
+            filename: sanitize_filename(&file.filename),
+            content_type: processed
+                .image_type
+                .map(str::to_string)
+                .unwrap_or_else(|| sanitize_content_type(file.content_type.as_deref())),
+            size: file.data.len() as i64,
+            width: processed.dimensions.map(|(width, _)| width as i32),
+            height: processed.dimensions.map(|(_, height)| height as i32),
+            checksum: processed.checksum,
+            storage_key,
+            thumbnail_key,
+        };
+
+        match self.attachment_repo.create(&new_attachment).await {
+            Ok(attachment) => Ok(attachment),
+            Err(error) => {
+                // Ne pas laisser de fichier orphelin
+                let _ = self.storage.delete(&new_attachment.storage_key).await;
+                if let Some(key) = &new_attachment.thumbnail_key {
+                    let _ = self.storage.delete(key).await;
+                }
+                Err(error)
+            }
+        }
+    }
+
+    async fn delete_files(&self, attachment: &Attachment) {
+        let keys = std::iter::once(&attachment.storage_key).chain(&attachment.thumbnail_key);
+        for key in keys {
+            if let Err(error) = self.storage.delete(key).await {
+                warn!("Impossible de supprimer le fichier {}: {}", key, error);
+            }
+        }
+    }
+}
+
+/// Résultat de l'analyse d'un fichier
+struct ProcessedFile {
+    checksum: String,
+    /// Type MIME du format détecté, pour les images décodables
+    image_type: Option<&'static str>,
+    dimensions: Option<(u32, u32)>,
+    thumbnail: Option<Vec<u8>>,
+}
+
+fn process(data: &[u8]) -> ProcessedFile {
+    let checksum = format!("{:x}", Sha256::digest(data));
+
+    match decode_image(data) {
+        Some((format, image)) => ProcessedFile {
+            checksum,
+            image_type: Some(format.to_mime_type()),
+            dimensions: Some((image.width(), image.height())),
+            thumbnail: encode_thumbnail(&image),
+        },
+        None => ProcessedFile {
+            checksum,
+            image_type: None,
+            dimensions: None,
+            thumbnail: None,
+        },
+    }
+}
+
+/// Décoder une image (PNG, JPEG, GIF ou WebP) d'après son contenu, pas son nom
+fn decode_image(data: &[u8]) -> Option<(ImageFormat, DynamicImage)> {
+    let format = image::guess_format(data).ok()?;
+    if !matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP) {
+        return None;
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let image = reader.decode().ok()?;
+
+    Some((format, image))
+}
+
+/// Miniature en JPEG, ou en PNG si l'image a de la transparence
+fn encode_thumbnail(image: &DynamicImage) -> Option<Vec<u8>> {
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let mut output = Cursor::new(Vec::new());
+
+    let encoded = if thumbnail.color().has_alpha() {
+        thumbnail.write_to(&mut output, ImageFormat::Png)
+    } else {
+        DynamicImage::ImageRgb8(thumbnail.to_rgb8()).write_to(&mut output, ImageFormat::Jpeg)
+    };
+
+    encoded.ok().map(|_| output.into_inner())
+}
+
+/// Nom de fichier sans chemin ni caractères de contrôle
+fn sanitize_filename(filename: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name.chars().filter(|c| !c.is_control()).take(255).collect();
+    let name = name.trim();
+
+    if name.is_empty() || name == "." || name == ".." {
+        "fichier".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Type MIME annoncé par le client, s'il est bien formé (sans paramètres)
+fn sanitize_content_type(content_type: Option<&str>) -> String {
+    let is_token = |part: &str| {
+        !part.is_empty()
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || "!#$&^_.+-".contains(c))
+    };
+
+    content_type
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| value.len() <= 127)
+        .filter(|value| matches!(value.split_once('/'), Some((kind, sub)) if is_token(kind) && is_token(sub)))
+        .unwrap_or_else(|| "application/octet-stream".to_string())
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use tracing::error;
+use crate::errors::AppResult;
+use crate::models::{AuditLogPage, AuditLogQuery, NewAuditEntry, Permissions};
+use crate::repositories::audit_repository::{AuditLogRepository, AuditLogRepositoryTrait};
+use crate::services::PermissionService;
+
+/// Nombre d'entrées par page par défaut
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Nombre maximal d'entrées par page
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Service gérant le journal d'audit des serveurs
+#[derive(Clone)]
+pub struct AuditService {
+    audit_repo: Arc<AuditLogRepository>,
+    permission_service: Arc<PermissionService>,
+}
+
+impl AuditService {
+    pub fn new(
+        audit_repo: Arc<AuditLogRepository>,
+        permission_service: Arc<PermissionService>,
+    ) -> Self {
+        Self {
+            audit_repo,
+            permission_service,
+        }
+    }
+
+    /// Enregistrer une action d'administration
+    ///
+    /// L'action a déjà eu lieu : un échec d'écriture est journalisé mais ne fait
+    /// pas échouer la requête.
+    pub async fn record(&self, entry: NewAuditEntry) {
+        if let Err(e) = self.audit_repo.create(&entry).await {
+            error!(
+                "Impossible d'enregistrer l'action {} sur le serveur {} : {}",
+                entry.action.as_str(),
+                entry.server_id,
+                e
+            );
+        }
+    }
+
+    /// Consulter le journal d'audit d'un serveur (VIEW_AUDIT_LOG)
+    pub async fn get_audit_log(
+        &self,
+        server_id: i32,
+        query: AuditLogQuery,
+        user_id: i32,
+    ) -> AppResult<AuditLogPage> {
+        self.permission_service
+            .check(user_id, server_id, None, Permissions::VIEW_AUDIT_LOG)
+            .await?;
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+        // Une entrée de plus pour savoir s'il reste une page
+        let mut entries = self
+            .audit_repo
+            .find_by_server(server_id, query.action, query.actor_id, query.before, limit + 1)
+            .await?;
+
+        let next_cursor = if entries.len() as i64 > limit {
+            entries.truncate(limit as usize);
+            entries.last().map(|entry| entry.id)
+        } else {
+            None
+        };
+
+        Ok(AuditLogPage { entries, next_cursor })
+    }
+}
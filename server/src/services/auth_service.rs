@@ -1,20 +1,46 @@
 use std::sync::Arc;
+use chrono::{Duration, Utc};
 use crate::errors::{AppError, AppResult};
-use crate::models::{User, CreateUserDto, LoginDto, AuthResponse, UpdateUserDto};
+use crate::models::{User, CreateUserDto, LoginDto, AuthResponse, UpdateUserDto, SessionInfo};
+use crate::repositories::attachment_repository::{AttachmentRepository, AttachmentRepositoryTrait};
+use crate::repositories::session_repository::{SessionRepository, SessionRepositoryTrait};
 use crate::repositories::user_repository::{UserRepository, UserRepositoryTrait};
-use crate::utils::{hash_password, verify_password, create_token};
+use crate::utils::{
+    access_token_lifetime, create_token, generate_refresh_token, hash_password, hash_refresh_token,
+    refresh_token_lifetime, verify_password, Claims,
+};
+use crate::ws::Hub;
+
+/// Durée de conservation des sessions expirées ou révoquées (détection de réutilisation)
+const INACTIVE_SESSION_RETENTION_DAYS: i64 = 7;
+
+/// Longueurs maximales, en caractères (limites des colonnes)
+const MAX_USERNAME_LENGTH: usize = 50;
+const MAX_EMAIL_LENGTH: usize = 255;
+const MAX_DISPLAY_NAME_LENGTH: usize = 32;
+const MAX_BIO_LENGTH: usize = 190;
+const MAX_PRONOUNS_LENGTH: usize = 40;
 
 #[derive(Clone)]
 pub struct AuthService {
     user_repo: Arc<UserRepository>,
+    session_repo: Arc<SessionRepository>,
+    attachment_repo: Arc<AttachmentRepository>,
+    /// Fermeture des sockets et connexions IRC des sessions révoquées
+    hub: Arc<Hub>,
 }
 
 impl AuthService {
-    pub fn new(user_repo: Arc<UserRepository>) -> Self {
-        Self { user_repo }
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        session_repo: Arc<SessionRepository>,
+        attachment_repo: Arc<AttachmentRepository>,
+        hub: Arc<Hub>,
+    ) -> Self {
+        Self { user_repo, session_repo, attachment_repo, hub }
     }
 
-    pub async fn register(&self, dto: CreateUserDto) -> AppResult<AuthResponse> {
+    pub async fn register(&self, dto: CreateUserDto, user_agent: Option<String>) -> AppResult<AuthResponse> {
         if self.user_repo.find_by_username(&dto.username).await?.is_some() {
             return Err(AppError::UsernameTaken);
         }
@@ -23,21 +49,23 @@ impl AuthService {
             return Err(AppError::EmailTaken);
         }
 
-        if dto.password.len() < 6 {
-            return Err(AppError::ValidationError(
-                "Le mot de passe doit contenir au moins 6 caractères".to_string(),
-            ));
-        }
+        validate_password(&dto.password)?;
 
         let password_hash = hash_password(&dto.password)?;
         let user = self.user_repo.create(dto, &password_hash).await?;
-        let token = create_token(user.id, &user.username)?;
         tracing::info!("✅ Inscription réussie - Nouvel utilisateur: {} (ID: {})", user.username, user.id);
 
-        Ok(AuthResponse { user, token })
+        self.open_session(user, user_agent).await
     }
 
-    pub async fn login(&self, dto: LoginDto) -> AppResult<AuthResponse> {
+    pub async fn login(&self, dto: LoginDto, user_agent: Option<String>) -> AppResult<AuthResponse> {
+        let user = self.verify_credentials(&dto).await?;
+        tracing::info!("✅ Connexion réussie - Utilisateur: {} (ID: {})", user.username, user.id);
+        self.open_session(user, user_agent).await
+    }
+
+    /// Vérifier un identifiant (pseudo ou email) et son mot de passe, sans ouvrir de session
+    pub async fn verify_credentials(&self, dto: &LoginDto) -> AppResult<User> {
         // Essayer de trouver l'utilisateur par username d'abord, puis par email
         let user = match self.user_repo.find_by_username(&dto.username).await? {
             Some(user) => user,
@@ -52,9 +80,108 @@ impl AuthService {
             return Err(AppError::InvalidCredentials);
         }
 
-        let token = create_token(user.id, &user.username)?;
-        tracing::info!("✅ Connexion réussie - Utilisateur: {} (ID: {})", user.username, user.id);
-        Ok(AuthResponse { user, token })
+        Ok(user)
+    }
+
+    /// Échanger un refresh token contre de nouveaux tokens
+    ///
+    /// Le refresh token présenté est remplacé. Un refresh token déjà remplacé qui revient
+    /// signale un vol probable : la session est révoquée.
+    pub async fn refresh(&self, refresh_token: &str) -> AppResult<AuthResponse> {
+        let token_hash = hash_refresh_token(refresh_token);
+
+        let Some(session) = self.session_repo.find_active_by_token_hash(&token_hash).await? else {
+            if let Some(session) = self.session_repo.find_by_previous_hash(&token_hash).await? {
+                self.session_repo.revoke(session.id, session.user_id).await?;
+                self.hub.revoke_sessions(session.user_id, vec![session.id]).await;
+                tracing::warn!(
+                    "⚠️ Refresh token réutilisé - Session {} de l'utilisateur {} révoquée",
+                    session.id,
+                    session.user_id
+                );
+            }
+            return Err(AppError::InvalidToken);
+        };
+
+        let new_refresh_token = generate_refresh_token();
+        let session = self
+            .session_repo
+            .rotate(
+                session.id,
+                &token_hash,
+                &hash_refresh_token(&new_refresh_token),
+                Utc::now() + refresh_token_lifetime(),
+            )
+            .await?
+            .ok_or(AppError::InvalidToken)?;
+
+        let user = self.get_current_user(session.user_id).await?;
+        let token = create_token(user.id, &user.username, session.id)?;
+
+        Ok(AuthResponse {
+            user,
+            token,
+            expires_in: access_token_lifetime().num_seconds(),
+            refresh_token: new_refresh_token,
+        })
+    }
+
+    /// Vérifier que la session d'un access token n'a pas été révoquée
+    pub async fn check_session(&self, claims: &Claims) -> AppResult<()> {
+        if self.session_repo.is_active(claims.sid, claims.sub).await? {
+            Ok(())
+        } else {
+            Err(AppError::InvalidToken)
+        }
+    }
+
+    /// Sessions actives de l'utilisateur, la plus récemment utilisée en premier
+    pub async fn list_sessions(&self, user_id: i32, current_session_id: i32) -> AppResult<Vec<SessionInfo>> {
+        let sessions = self.session_repo.list_active(user_id).await?;
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionInfo::new(session, current_session_id))
+            .collect())
+    }
+
+    /// Révoquer une session de l'utilisateur (déconnexion, appareil perdu)
+    ///
+    /// Les sockets et connexions IRC ouverts avec cette session sont fermés.
+    pub async fn revoke_session(&self, user_id: i32, session_id: i32) -> AppResult<()> {
+        if !self.session_repo.revoke(session_id, user_id).await? {
+            return Err(AppError::SessionNotFound);
+        }
+        self.hub.revoke_sessions(user_id, vec![session_id]).await;
+        tracing::info!("Session {} de l'utilisateur {} révoquée", session_id, user_id);
+        Ok(())
+    }
+
+    /// Supprimer les sessions expirées ou révoquées depuis plus de quelques jours
+    pub async fn purge_sessions(&self) -> AppResult<u64> {
+        let before = Utc::now() - Duration::days(INACTIVE_SESSION_RETENTION_DAYS);
+        self.session_repo.delete_inactive_before(before).await
+    }
+
+    /// Ouvrir une session : access token et refresh token
+    async fn open_session(&self, user: User, user_agent: Option<String>) -> AppResult<AuthResponse> {
+        let refresh_token = generate_refresh_token();
+        let session = self
+            .session_repo
+            .create(
+                user.id,
+                &hash_refresh_token(&refresh_token),
+                user_agent.as_deref(),
+                Utc::now() + refresh_token_lifetime(),
+            )
+            .await?;
+        let token = create_token(user.id, &user.username, session.id)?;
+
+        Ok(AuthResponse {
+            user,
+            token,
+            expires_in: access_token_lifetime().num_seconds(),
+            refresh_token,
+        })
     }
 
     pub async fn get_current_user(&self, user_id: i32) -> AppResult<User> {
@@ -65,36 +192,161 @@ impl AuthService {
     }
 
     // --------- Update ---------
+    /// Modifier le compte et le profil de l'utilisateur
+    ///
+    /// Changer d'email ou de mot de passe demande le mot de passe actuel ; un nouveau mot
+    /// de passe révoque les autres sessions de l'utilisateur.
     pub async fn update_user(
         &self,
         user_id: i32,
+        current_session_id: i32,
         dto: UpdateUserDto,
     ) -> AppResult<User> {
-        let user = self.user_repo.find_by_id(user_id).await?
+        let mut user = self.user_repo.find_by_id(user_id).await?
             .ok_or(AppError::UserNotFound)?;
 
-        let username = dto.username.unwrap_or(user.username);
-        let email = dto.email.unwrap_or(user.email);
+        let email = dto.email.map(|email| email.trim().to_string()).filter(|email| *email != user.email);
+        if email.is_some() || dto.password.is_some() {
+            let current_password = dto.current_password.as_deref().ok_or_else(|| {
+                AppError::ValidationError(
+                    "Le mot de passe actuel est requis pour changer d'email ou de mot de passe".to_string(),
+                )
+            })?;
+            if !verify_password(current_password, &user.password_hash)? {
+                return Err(AppError::IncorrectPassword);
+            }
+        }
 
-        if let Some(_) = self.user_repo.find_by_username(&username).await? {
-            return Err(AppError::UsernameTaken);
+        if let Some(username) = dto.username.map(|username| username.trim().to_string()) {
+            if username != user.username {
+                validate_username(&username)?;
+                // Seul un autre compte peut rendre le pseudo indisponible
+                if self.user_repo.find_by_username(&username).await?.is_some_and(|other| other.id != user_id) {
+                    return Err(AppError::UsernameTaken);
+                }
+                user.username = username;
+            }
         }
-        if let Some(_) = self.user_repo.find_by_email(&email).await? {
-            return Err(AppError::EmailTaken);
+        if let Some(email) = email {
+            validate_email(&email)?;
+            if self.user_repo.find_by_email(&email).await?.is_some_and(|other| other.id != user_id) {
+                return Err(AppError::EmailTaken);
+            }
+            user.email = email;
+        }
+        if let Some(password) = &dto.password {
+            validate_password(password)?;
+            user.password_hash = hash_password(password)?;
         }
 
-        let password_hash = if let Some(pwd) = &dto.password {
-            hash_password(pwd)?
-        } else {
-            user.password_hash.clone()
-        };
+        if let Some(display_name) = dto.display_name {
+            user.display_name = validate_profile_text(&display_name, "Nom affiché", MAX_DISPLAY_NAME_LENGTH, false)?;
+        }
+        if let Some(bio) = dto.bio {
+            user.bio = validate_profile_text(&bio, "Bio", MAX_BIO_LENGTH, true)?;
+        }
+        if let Some(pronouns) = dto.pronouns {
+            user.pronouns = validate_profile_text(&pronouns, "Pronoms", MAX_PRONOUNS_LENGTH, false)?;
+        }
+        if let Some(avatar_attachment_id) = dto.avatar_attachment_id {
+            if let Some(attachment_id) = avatar_attachment_id {
+                self.check_avatar(attachment_id, user_id).await?;
+            }
+            user.avatar_attachment_id = avatar_attachment_id;
+        }
+
+        let user = self.user_repo.update(&user).await?;
+
+        // Nouveau mot de passe : les sessions et connexions IRC ouvertes avec l'ancien sont révoquées
+        if dto.password.is_some() {
+            let revoked = self.session_repo.revoke_others(user_id, current_session_id).await?;
+            tracing::info!("Mot de passe de l'utilisateur {} changé, {} session(s) révoquée(s)", user_id, revoked.len());
+            self.hub.revoke_credentials(user_id, revoked).await;
+        }
 
-        self.user_repo.update(user_id, &username, &email, &password_hash).await
+        Ok(user)
+    }
+
+    /// Vérifier le mot de passe de l'utilisateur avant une action sensible
+    pub async fn confirm_password(&self, user_id: i32, password: &str) -> AppResult<()> {
+        let user = self.get_current_user(user_id).await?;
+        if !verify_password(password, &user.password_hash)? {
+            return Err(AppError::IncorrectPassword);
+        }
+        Ok(())
     }
 
     // --------- Delete ---------
     pub async fn delete_user(&self, user_id: i32) -> AppResult<()> {
         self.user_repo.delete(user_id).await?;
+        tracing::info!("Compte de l'utilisateur {} supprimé", user_id);
         Ok(())
     }
+
+    /// L'avatar doit être une image envoyée par l'utilisateur lui-même
+    async fn check_avatar(&self, attachment_id: i32, user_id: i32) -> AppResult<()> {
+        let is_own_image = self
+            .attachment_repo
+            .find_by_id(attachment_id)
+            .await?
+            .is_some_and(|attachment| attachment.uploader_id == Some(user_id) && attachment.width.is_some());
+        if !is_own_image {
+            return Err(AppError::ValidationError(
+                "L'avatar doit être une image que vous avez envoyée".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn validate_username(username: &str) -> AppResult<()> {
+    if username.is_empty() || username.chars().count() > MAX_USERNAME_LENGTH {
+        return Err(AppError::ValidationError(format!(
+            "Le nom d'utilisateur doit contenir entre 1 et {} caractères",
+            MAX_USERNAME_LENGTH
+        )));
+    }
+    if username.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(AppError::ValidationError(
+            "Le nom d'utilisateur ne peut pas contenir d'espaces".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_email(email: &str) -> AppResult<()> {
+    if !email.contains('@') || email.chars().count() > MAX_EMAIL_LENGTH {
+        return Err(AppError::ValidationError("Adresse email invalide".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_password(password: &str) -> AppResult<()> {
+    if password.len() < 6 {
+        return Err(AppError::ValidationError(
+            "Le mot de passe doit contenir au moins 6 caractères".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Champ de profil nettoyé ; vide pour le retirer
+fn validate_profile_text(value: &str, field: &str, max_length: usize, multiline: bool) -> AppResult<Option<String>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    if value.chars().count() > max_length {
+        return Err(AppError::ValidationError(format!(
+            "{} : {} caractères maximum",
+            field, max_length
+        )));
+    }
+    if value.chars().any(|c| c.is_control() && !(multiline && c == '\n')) {
+        return Err(AppError::ValidationError(format!(
+            "{} : caractères de contrôle interdits",
+            field
+        )));
+    }
+    Ok(Some(value.to_string()))
 }
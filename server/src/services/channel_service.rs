@@ -1,25 +1,38 @@
 use std::sync::Arc;
 use crate::errors::{AppError, AppResult};
-use crate::models::{Channel, CreateChannelDto, UpdateChannelDto, UserRole};
+use crate::models::{
+    AuditAction, Channel, CreateChannelDto, NewAuditEntry, PermissionOverwrite, PermissionOverwriteDto,
+    Permissions, UpdateChannelDto,
+};
 use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
-use crate::services::ServerService;
+use crate::repositories::role_repository::{RoleRepository, RoleRepositoryTrait};
+use crate::services::{AuditService, PermissionService};
 
 /// Service gérant les canaux
 #[derive(Clone)]
 pub struct ChannelService {
     channel_repo: Arc<ChannelRepository>,
-    server_service: Arc<ServerService>,
+    role_repo: Arc<RoleRepository>,
+    permission_service: Arc<PermissionService>,
+    audit_service: Arc<AuditService>,
 }
 
 impl ChannelService {
-    pub fn new(channel_repo: Arc<ChannelRepository>, server_service: Arc<ServerService>) -> Self {
+    pub fn new(
+        channel_repo: Arc<ChannelRepository>,
+        role_repo: Arc<RoleRepository>,
+        permission_service: Arc<PermissionService>,
+        audit_service: Arc<AuditService>,
+    ) -> Self {
         Self {
             channel_repo,
-            server_service,
+            role_repo,
+            permission_service,
+            audit_service,
         }
     }
 
-    /// Créer un canal (Admin ou Owner uniquement)
+    /// Créer un canal (MANAGE_CHANNELS)
     pub async fn create_channel(
         &self,
         server_id: i32,
@@ -27,23 +40,49 @@ impl ChannelService {
         user_id: i32,
     ) -> AppResult<Channel> {
         // Vérifier les permissions
-        let role = self.server_service.get_user_role(server_id, user_id).await?;
-        match role {
-            UserRole::Owner | UserRole::Admin => {}
-            UserRole::Member => return Err(AppError::AdminOnly),
-        }
+        self.permission_service
+            .check(user_id, server_id, None, Permissions::MANAGE_CHANNELS)
+            .await?;
 
-        self.channel_repo
+        let channel = self
+            .channel_repo
             .create(&dto.name, &dto.r#type, server_id)
-            .await
+            .await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::ChannelCreate, Some(channel.id))
+                    .after(&channel),
+            )
+            .await;
+
+        Ok(channel)
     }
 
-    /// Récupérer les canaux d'un serveur
+    /// Récupérer les canaux d'un serveur visibles par l'utilisateur
     pub async fn get_server_channels(&self, server_id: i32, user_id: i32) -> AppResult<Vec<Channel>> {
-        // Vérifier que l'utilisateur est membre du serveur
-        self.server_service.get_user_role(server_id, user_id).await?;
+        let channels = self.channel_repo.find_by_server(server_id).await?;
+
+        // Masquer les canaux sans VIEW_CHANNELS (surcharges incluses)
+        self.permission_service
+            .filter_channels(user_id, server_id, channels, Permissions::VIEW_CHANNELS)
+            .await
+    }
 
-        self.channel_repo.find_by_server(server_id).await
+    /// Identifiants de tous les canaux d'un serveur, sans contrôle d'accès (usage interne)
+    pub async fn get_channel_ids(&self, server_id: i32) -> AppResult<Vec<i32>> {
+        let channels = self.channel_repo.find_by_server(server_id).await?;
+        Ok(channels.iter().map(|channel| channel.id).collect())
+    }
+
+    /// Serveur auquel appartient un canal, sans contrôle d'accès (usage interne)
+    pub async fn get_server_id(&self, channel_id: i32) -> AppResult<i32> {
+        let channel = self
+            .channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+        Ok(channel.server_id)
     }
 
     /// Récupérer un canal par ID
@@ -54,15 +93,15 @@ impl ChannelService {
             .await?
             .ok_or(AppError::ChannelNotFound)?;
 
-        // Vérifier que l'utilisateur est membre du serveur
-        self.server_service
-            .get_user_role(channel.server_id, user_id)
+        // Vérifier que l'utilisateur peut voir le canal
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::VIEW_CHANNELS)
             .await?;
 
         Ok(channel)
     }
 
-    /// Mettre à jour un canal (Admin ou Owner uniquement)
+    /// Mettre à jour un canal (MANAGE_CHANNELS)
     pub async fn update_channel(
         &self,
         channel_id: i32,
@@ -76,20 +115,25 @@ impl ChannelService {
             .ok_or(AppError::ChannelNotFound)?;
 
         // Vérifier les permissions
-        let role = self
-            .server_service
-            .get_user_role(channel.server_id, user_id)
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::MANAGE_CHANNELS)
             .await?;
-        match role {
-            UserRole::Owner | UserRole::Admin => {}
-            UserRole::Member => return Err(AppError::AdminOnly),
-        }
 
-        self.channel_repo.update(channel_id, &dto.name).await
+        let updated = self.channel_repo.update(channel_id, &dto.name).await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(channel.server_id, user_id, AuditAction::ChannelUpdate, Some(channel.id))
+                    .before(&channel)
+                    .after(&updated),
+            )
+            .await;
+
+        Ok(updated)
     }
 
-    /// Supprimer un canal (Admin ou Owner uniquement)
-    pub async fn delete_channel(&self, channel_id: i32, user_id: i32) -> AppResult<()> {
+    /// Supprimer un canal (MANAGE_CHANNELS) ; retourne le canal supprimé
+    pub async fn delete_channel(&self, channel_id: i32, user_id: i32) -> AppResult<Channel> {
         let channel = self
             .channel_repo
             .find_by_id(channel_id)
@@ -97,15 +141,189 @@ impl ChannelService {
             .ok_or(AppError::ChannelNotFound)?;
 
         // Vérifier les permissions
-        let role = self
-            .server_service
-            .get_user_role(channel.server_id, user_id)
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::MANAGE_CHANNELS)
+            .await?;
+
+        self.channel_repo.delete(channel_id).await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(channel.server_id, user_id, AuditAction::ChannelDelete, Some(channel.id))
+                    .before(&channel),
+            )
+            .await;
+
+        Ok(channel)
+    }
+
+    /// Lister les surcharges de permissions d'un canal (MANAGE_CHANNELS)
+    pub async fn get_overwrites(&self, channel_id: i32, user_id: i32) -> AppResult<Vec<PermissionOverwrite>> {
+        let channel = self.get_managed_channel(channel_id, user_id).await?;
+
+        self.channel_repo.find_overwrites(channel.id).await
+    }
+
+    /// Définir la surcharge d'un rôle sur un canal (MANAGE_CHANNELS et MANAGE_ROLES)
+    pub async fn set_role_overwrite(
+        &self,
+        channel_id: i32,
+        role_id: i32,
+        dto: PermissionOverwriteDto,
+        user_id: i32,
+    ) -> AppResult<PermissionOverwrite> {
+        let channel = self.get_managed_channel(channel_id, user_id).await?;
+        let (allow, deny) = self.validate_overwrite(&channel, dto, user_id).await?;
+
+        // Le rôle doit appartenir au serveur du canal
+        self.role_repo
+            .find_by_id(role_id)
+            .await?
+            .filter(|role| role.server_id == channel.server_id)
+            .ok_or(AppError::RoleNotFound)?;
+
+        let before = self.find_overwrite(channel.id, Some(role_id), None).await?;
+        let overwrite = self
+            .channel_repo
+            .upsert_role_overwrite(channel.id, role_id, allow, deny)
+            .await?;
+
+        self.record_overwrite_update(&channel, user_id, before, &overwrite).await;
+
+        Ok(overwrite)
+    }
+
+    /// Définir la surcharge d'un membre sur un canal (MANAGE_CHANNELS et MANAGE_ROLES)
+    pub async fn set_member_overwrite(
+        &self,
+        channel_id: i32,
+        target_user_id: i32,
+        dto: PermissionOverwriteDto,
+        user_id: i32,
+    ) -> AppResult<PermissionOverwrite> {
+        let channel = self.get_managed_channel(channel_id, user_id).await?;
+        let (allow, deny) = self.validate_overwrite(&channel, dto, user_id).await?;
+
+        if !self.permission_service.is_member(channel.server_id, target_user_id).await? {
+            return Err(AppError::UserNotFound);
+        }
+
+        let before = self.find_overwrite(channel.id, None, Some(target_user_id)).await?;
+        let overwrite = self
+            .channel_repo
+            .upsert_member_overwrite(channel.id, target_user_id, allow, deny)
+            .await?;
+
+        self.record_overwrite_update(&channel, user_id, before, &overwrite).await;
+
+        Ok(overwrite)
+    }
+
+    /// Supprimer la surcharge d'un rôle sur un canal
+    pub async fn delete_role_overwrite(&self, channel_id: i32, role_id: i32, user_id: i32) -> AppResult<()> {
+        let channel = self.get_managed_channel(channel_id, user_id).await?;
+        let before = self.find_overwrite(channel.id, Some(role_id), None).await?;
+
+        if !self.channel_repo.delete_role_overwrite(channel.id, role_id).await? {
+            return Err(AppError::OverwriteNotFound);
+        }
+
+        self.record_overwrite_delete(&channel, user_id, before).await;
+        Ok(())
+    }
+
+    /// Supprimer la surcharge d'un membre sur un canal
+    pub async fn delete_member_overwrite(&self, channel_id: i32, target_user_id: i32, user_id: i32) -> AppResult<()> {
+        let channel = self.get_managed_channel(channel_id, user_id).await?;
+        let before = self.find_overwrite(channel.id, None, Some(target_user_id)).await?;
+
+        if !self.channel_repo.delete_member_overwrite(channel.id, target_user_id).await? {
+            return Err(AppError::OverwriteNotFound);
+        }
+
+        self.record_overwrite_delete(&channel, user_id, before).await;
+        Ok(())
+    }
+
+    /// Surcharge existante d'un rôle ou d'un membre sur un canal
+    async fn find_overwrite(
+        &self,
+        channel_id: i32,
+        role_id: Option<i32>,
+        user_id: Option<i32>,
+    ) -> AppResult<Option<PermissionOverwrite>> {
+        let overwrites = self.channel_repo.find_overwrites(channel_id).await?;
+
+        Ok(overwrites
+            .into_iter()
+            .find(|overwrite| overwrite.role_id == role_id && overwrite.user_id == user_id))
+    }
+
+    async fn record_overwrite_update(
+        &self,
+        channel: &Channel,
+        user_id: i32,
+        before: Option<PermissionOverwrite>,
+        after: &PermissionOverwrite,
+    ) {
+        self.audit_service
+            .record(
+                NewAuditEntry::new(channel.server_id, user_id, AuditAction::ChannelOverwriteUpdate, Some(channel.id))
+                    .before(before)
+                    .after(after),
+            )
+            .await;
+    }
+
+    async fn record_overwrite_delete(&self, channel: &Channel, user_id: i32, before: Option<PermissionOverwrite>) {
+        self.audit_service
+            .record(
+                NewAuditEntry::new(channel.server_id, user_id, AuditAction::ChannelOverwriteDelete, Some(channel.id))
+                    .before(before),
+            )
+            .await;
+    }
+
+    /// Récupérer un canal dont l'utilisateur peut gérer les permissions
+    async fn get_managed_channel(&self, channel_id: i32, user_id: i32) -> AppResult<Channel> {
+        let channel = self
+            .channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::MANAGE_CHANNELS | Permissions::MANAGE_ROLES,
+            )
             .await?;
-        match role {
-            UserRole::Owner | UserRole::Admin => {}
-            UserRole::Member => return Err(AppError::AdminOnly),
+
+        Ok(channel)
+    }
+
+    /// Valider une surcharge : pas de conflit allow/deny, et uniquement des permissions détenues
+    async fn validate_overwrite(
+        &self,
+        channel: &Channel,
+        dto: PermissionOverwriteDto,
+        user_id: i32,
+    ) -> AppResult<(Permissions, Permissions)> {
+        let allow = dto.allow & Permissions::all();
+        let deny = dto.deny & Permissions::all();
+
+        if allow & deny != Permissions::NONE {
+            return Err(AppError::ValidationError(
+                "Une permission ne peut pas être à la fois accordée et refusée".to_string(),
+            ));
         }
 
-        self.channel_repo.delete(channel_id).await
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), allow | deny)
+            .await?;
+
+        Ok((allow, deny))
     }
 }
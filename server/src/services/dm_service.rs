@@ -0,0 +1,164 @@
+use std::sync::Arc;
+use crate::errors::{AppError, AppResult};
+use crate::models::{CreateMessageDto, DmConversationDetails, MessageQuery, MessageWithAuthor, OpenDmDto};
+use crate::repositories::dm_repository::{DmRepository, DmRepositoryTrait};
+use crate::repositories::user_repository::{UserRepository, UserRepositoryTrait};
+
+/// Nombre maximum de participants dans un groupe privé (créateur inclus)
+const MAX_GROUP_PARTICIPANTS: usize = 10;
+
+/// Service gérant les messages privés
+#[derive(Clone)]
+pub struct DmService {
+    dm_repo: Arc<DmRepository>,
+    user_repo: Arc<UserRepository>,
+}
+
+impl DmService {
+    pub fn new(dm_repo: Arc<DmRepository>, user_repo: Arc<UserRepository>) -> Self {
+        Self { dm_repo, user_repo }
+    }
+
+    /// Ouvrir une conversation privée (réutilise la conversation 1:1 existante)
+    pub async fn open_conversation(&self, dto: OpenDmDto, user_id: i32) -> AppResult<DmConversationDetails> {
+        let mut recipients: Vec<i32> = dto.user_ids.into_iter().filter(|id| *id != user_id).collect();
+        recipients.sort_unstable();
+        recipients.dedup();
+
+        if recipients.is_empty() {
+            return Err(AppError::ValidationError(
+                "Une conversation privée nécessite au moins un destinataire".to_string(),
+            ));
+        }
+        if recipients.len() + 1 > MAX_GROUP_PARTICIPANTS {
+            return Err(AppError::ValidationError(format!(
+                "Un groupe privé ne peut pas dépasser {} participants",
+                MAX_GROUP_PARTICIPANTS
+            )));
+        }
+
+        // Vérifier que tous les destinataires existent
+        for recipient_id in &recipients {
+            if self.user_repo.find_by_id(*recipient_id).await?.is_none() {
+                return Err(AppError::UserNotFound);
+            }
+        }
+
+        let name = dto.name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty());
+        let is_group = recipients.len() > 1 || name.is_some();
+
+        // Une seule conversation 1:1 par paire d'utilisateurs
+        if !is_group {
+            if let Some(conversation) = self.dm_repo.find_direct(user_id, recipients[0]).await? {
+                return self.with_participants(conversation.id).await;
+            }
+        }
+
+        let mut participant_ids = recipients;
+        participant_ids.push(user_id);
+
+        let conversation = self
+            .dm_repo
+            .create(name.as_deref(), is_group, &participant_ids)
+            .await?;
+
+        self.with_participants(conversation.id).await
+    }
+
+    /// Lister les conversations privées d'un utilisateur
+    pub async fn get_user_conversations(&self, user_id: i32) -> AppResult<Vec<DmConversationDetails>> {
+        let conversations = self.dm_repo.find_by_user(user_id).await?;
+
+        let mut details = Vec::with_capacity(conversations.len());
+        for conversation in conversations {
+            let participants = self.dm_repo.get_participants(conversation.id).await?;
+            details.push(DmConversationDetails {
+                conversation,
+                participants,
+            });
+        }
+
+        Ok(details)
+    }
+
+    /// Récupérer une conversation (participants uniquement)
+    pub async fn get_conversation(&self, conversation_id: i32, user_id: i32) -> AppResult<DmConversationDetails> {
+        self.check_is_participant(conversation_id, user_id).await?;
+        self.with_participants(conversation_id).await
+    }
+
+    /// Envoyer un message dans une conversation
+    pub async fn send_message(
+        &self,
+        conversation_id: i32,
+        dto: CreateMessageDto,
+        author_id: i32,
+    ) -> AppResult<MessageWithAuthor> {
+        self.check_is_participant(conversation_id, author_id).await?;
+
+        // Valider le contenu
+        if dto.content.trim().is_empty() {
+            return Err(AppError::ValidationError(
+                "Le message ne peut pas être vide".to_string(),
+            ));
+        }
+
+        // Réponses et pièces jointes sont propres aux canaux de serveur
+        if dto.reply_to_id.is_some() || !dto.attachment_ids.is_empty() {
+            return Err(AppError::ValidationError(
+                "Les messages privés n'acceptent ni réponse (reply_to_id) ni pièce jointe (attachment_ids)"
+                    .to_string(),
+            ));
+        }
+
+        self.dm_repo
+            .create_message(&dto.content, conversation_id, author_id)
+            .await
+    }
+
+    /// Récupérer l'historique d'une conversation
+    pub async fn get_messages(
+        &self,
+        conversation_id: i32,
+        user_id: i32,
+        query: MessageQuery,
+    ) -> AppResult<Vec<MessageWithAuthor>> {
+        self.check_is_participant(conversation_id, user_id).await?;
+
+        let cursor = query.cursor().ok_or_else(|| {
+            AppError::ValidationError(
+                "Un seul des paramètres before, after et around peut être utilisé".to_string(),
+            )
+        })?;
+        let limit = query.limit.unwrap_or(50).clamp(1, 100); // Max 100 messages par requête
+
+        self.dm_repo
+            .find_messages(conversation_id, cursor, limit)
+            .await
+    }
+
+    /// Vérifier qu'un utilisateur participe à une conversation
+    async fn check_is_participant(&self, conversation_id: i32, user_id: i32) -> AppResult<()> {
+        if self.dm_repo.find_by_id(conversation_id).await?.is_none() {
+            return Err(AppError::ConversationNotFound);
+        }
+        if !self.dm_repo.is_participant(conversation_id, user_id).await? {
+            return Err(AppError::Forbidden);
+        }
+        Ok(())
+    }
+
+    async fn with_participants(&self, conversation_id: i32) -> AppResult<DmConversationDetails> {
+        let conversation = self
+            .dm_repo
+            .find_by_id(conversation_id)
+            .await?
+            .ok_or(AppError::ConversationNotFound)?;
+        let participants = self.dm_repo.get_participants(conversation_id).await?;
+
+        Ok(DmConversationDetails {
+            conversation,
+            participants,
+        })
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use chrono::Utc;
+use crate::errors::{AppError, AppResult};
+use crate::models::{
+    AuditAction, CreateInvitationDto, Invitation, InvitationPreview, NewAuditEntry, Permissions,
+};
+use crate::repositories::invitation_repository::{InvitationRepository, InvitationRepositoryTrait};
+use crate::services::{AuditService, PermissionService};
+use crate::utils::generate_invitation_code;
+
+/// Nombre maximal d'utilisations d'une invitation limitée
+const MAX_INVITATION_USES: i32 = 1000;
+
+/// Service gérant les invitations des serveurs
+#[derive(Clone)]
+pub struct InvitationService {
+    invitation_repo: Arc<InvitationRepository>,
+    permission_service: Arc<PermissionService>,
+    audit_service: Arc<AuditService>,
+}
+
+impl InvitationService {
+    pub fn new(
+        invitation_repo: Arc<InvitationRepository>,
+        permission_service: Arc<PermissionService>,
+        audit_service: Arc<AuditService>,
+    ) -> Self {
+        Self {
+            invitation_repo,
+            permission_service,
+            audit_service,
+        }
+    }
+
+    /// Créer une invitation (CREATE_INVITE)
+    pub async fn create_invitation(
+        &self,
+        server_id: i32,
+        dto: CreateInvitationDto,
+        user_id: i32,
+    ) -> AppResult<Invitation> {
+        self.permission_service
+            .check(user_id, server_id, None, Permissions::CREATE_INVITE)
+            .await?;
+
+        if matches!(dto.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+            return Err(AppError::ValidationError(
+                "La date d'expiration doit être dans le futur".to_string(),
+            ));
+        }
+        if matches!(dto.max_uses, Some(max_uses) if !(1..=MAX_INVITATION_USES).contains(&max_uses)) {
+            return Err(AppError::ValidationError(format!(
+                "Le nombre d'utilisations doit être compris entre 1 et {}",
+                MAX_INVITATION_USES
+            )));
+        }
+
+        let code = generate_invitation_code();
+        let invitation = self
+            .invitation_repo
+            .create(server_id, &code, user_id, dto.max_uses, dto.expires_at, dto.temporary)
+            .await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::InviteCreate, Some(invitation.id))
+                    .after(&invitation),
+            )
+            .await;
+
+        Ok(invitation)
+    }
+
+    /// Lister les invitations d'un serveur (MANAGE_SERVER)
+    pub async fn get_invitations(&self, server_id: i32, user_id: i32) -> AppResult<Vec<Invitation>> {
+        self.permission_service
+            .check(user_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
+
+        self.invitation_repo.find_by_server(server_id).await
+    }
+
+    /// Révoquer une invitation (son créateur, ou MANAGE_SERVER)
+    pub async fn revoke_invitation(&self, server_id: i32, code: &str, user_id: i32) -> AppResult<()> {
+        let invitation = self
+            .invitation_repo
+            .find_by_code(code)
+            .await?
+            .filter(|invitation| invitation.server_id == server_id)
+            .ok_or(AppError::InvalidInvitationCode)?;
+
+        if invitation.creator_id == Some(user_id) {
+            // Le créateur doit toujours être membre du serveur
+            self.permission_service.compute(user_id, server_id, None).await?;
+        } else {
+            self.permission_service
+                .check(user_id, server_id, None, Permissions::MANAGE_SERVER)
+                .await?;
+        }
+
+        self.invitation_repo.delete(invitation.id).await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::InviteDelete, Some(invitation.id))
+                    .before(&invitation),
+            )
+            .await;
+
+        Ok(())
+    }
+
+    /// Aperçu public d'une invitation : nom du serveur et nombre de membres
+    pub async fn preview_invitation(&self, code: &str) -> AppResult<InvitationPreview> {
+        let invitation = self
+            .invitation_repo
+            .find_by_code(code)
+            .await?
+            .ok_or(AppError::InvalidInvitationCode)?;
+
+        if !invitation.is_usable() {
+            return Err(AppError::InvitationExpired);
+        }
+
+        self.invitation_repo
+            .find_preview(code)
+            .await?
+            .ok_or(AppError::InvalidInvitationCode)
+    }
+}
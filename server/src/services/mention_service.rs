@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::error;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Mention, MentionKind, MentionQuery, Message, MessageMentions, Permissions};
+use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
+use crate::repositories::mention_repository::{MentionRepository, MentionRepositoryTrait};
+use crate::services::PermissionService;
+use crate::utils::parse_mentions;
+use crate::ws::Hub;
+
+/// Service gérant les mentions et la boîte de réception associée
+#[derive(Clone)]
+pub struct MentionService {
+    mention_repo: Arc<MentionRepository>,
+    channel_repo: Arc<ChannelRepository>,
+    permission_service: Arc<PermissionService>,
+    /// Utilisateurs connectés, pour @here
+    hub: Arc<Hub>,
+}
+
+impl MentionService {
+    pub fn new(
+        mention_repo: Arc<MentionRepository>,
+        channel_repo: Arc<ChannelRepository>,
+        permission_service: Arc<PermissionService>,
+        hub: Arc<Hub>,
+    ) -> Self {
+        Self {
+            mention_repo,
+            channel_repo,
+            permission_service,
+            hub,
+        }
+    }
+
+    /// Enregistrer les mentions d'un message qui vient d'être posté
+    ///
+    /// Best-effort : le message est déjà publié, une erreur est seulement journalisée.
+    pub async fn record_mentions(&self, message: &Message) -> MessageMentions {
+        match self.resolve_and_store(message).await {
+            Ok(mentions) => mentions,
+            Err(e) => {
+                error!("Impossible d'enregistrer les mentions du message {} : {}", message.id, e);
+                MessageMentions::default()
+            }
+        }
+    }
+
+    /// Mentions non vues de l'utilisateur, tous serveurs confondus
+    pub async fn get_unseen_mentions(&self, user_id: i32, query: MentionQuery) -> AppResult<Vec<Mention>> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 100);
+        self.mention_repo.find_unseen(user_id, query.before, limit).await
+    }
+
+    /// Retirer une mention de la boîte de réception
+    pub async fn dismiss_mention(&self, user_id: i32, message_id: i32) -> AppResult<()> {
+        if !self.mention_repo.mark_seen(user_id, message_id).await? {
+            return Err(AppError::MessageNotFound);
+        }
+        Ok(())
+    }
+
+    /// Seuls les membres qui voient le canal sont notifiés, jamais l'auteur.
+    /// @everyone et @here exigent MENTION_EVERYONE, sinon ils restent du texte.
+    async fn resolve_and_store(&self, message: &Message) -> AppResult<MessageMentions> {
+        let parsed = parse_mentions(&message.content);
+        if parsed.is_empty() {
+            return Ok(MessageMentions::default());
+        }
+
+        let channel = self
+            .channel_repo
+            .find_by_id(message.channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+        let server_id = channel.server_id;
+
+        let author_permissions = self
+            .permission_service
+            .compute(message.author_id, server_id, Some(channel.id))
+            .await?;
+        let can_mention_everyone = author_permissions.contains(Permissions::MENTION_EVERYONE);
+
+        // Garder la raison la plus directe pour chaque utilisateur
+        let mut candidates: HashMap<i32, MentionKind> = HashMap::new();
+        let mut add = |user_ids: Vec<i32>, kind: MentionKind| {
+            for user_id in user_ids {
+                let entry = candidates.entry(user_id).or_insert(kind);
+                *entry = (*entry).min(kind);
+            }
+        };
+
+        if !parsed.user_ids.is_empty() {
+            add(self.mention_repo.find_member_ids(server_id, &parsed.user_ids).await?, MentionKind::User);
+        }
+        if !parsed.role_ids.is_empty() {
+            add(self.mention_repo.find_role_member_ids(server_id, &parsed.role_ids).await?, MentionKind::Role);
+        }
+        if can_mention_everyone && parsed.here {
+            let online: Vec<i32> = self
+                .hub
+                .get_online_presences()
+                .await
+                .iter()
+                .map(|presence| presence.user_id)
+                .collect();
+            add(self.mention_repo.find_member_ids(server_id, &online).await?, MentionKind::Here);
+        }
+        if can_mention_everyone && parsed.everyone {
+            add(self.mention_repo.find_all_member_ids(server_id).await?, MentionKind::Everyone);
+        }
+
+        candidates.remove(&message.author_id);
+
+        let mut recipients = Vec::with_capacity(candidates.len());
+        for (user_id, kind) in candidates {
+            let permissions = self
+                .permission_service
+                .compute(user_id, server_id, Some(channel.id))
+                .await?;
+            if permissions.contains(Permissions::VIEW_CHANNELS) {
+                recipients.push((user_id, kind));
+            }
+        }
+        recipients.sort_unstable();
+
+        if !recipients.is_empty() {
+            self.mention_repo.create_many(message.id, &recipients).await?;
+        }
+
+        Ok(MessageMentions {
+            server_id,
+            recipients,
+        })
+    }
+}
@@ -1,28 +1,59 @@
 use std::sync::Arc;
+use chrono::{DateTime, Utc};
 use crate::errors::{AppError, AppResult};
-use crate::models::{Message, MessageWithAuthor, CreateMessageDto, UserRole};
+use crate::models::{
+    AuditAction, Channel, Message, MessageCursor, MessageQuery, MessageRevision, MessageWithAuthor,
+    CreateMessageDto, NewAuditEntry, PinOutcome, ReactionChange, Thread, UpdateMessageDto, Permissions,
+};
 use crate::repositories::message_repository::{MessageRepository, MessageRepositoryTrait};
 use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
-use crate::services::ServerService;
+use crate::repositories::thread_repository::{ThreadRepository, ThreadRepositoryTrait};
+use crate::repositories::reaction_repository::{ReactionRepository, ReactionRepositoryTrait};
+use crate::repositories::attachment_repository::{AttachmentRepository, AttachmentRepositoryTrait};
+use crate::services::attachment_service::MAX_ATTACHMENTS_PER_MESSAGE;
+use crate::services::{AuditService, PermissionService};
+
+/// Nombre maximal d'emojis différents sur un même message
+const MAX_REACTIONS_PER_MESSAGE: usize = 20;
+
+/// Longueur maximale d'un emoji (séquences composées comprises)
+const MAX_EMOJI_LENGTH: usize = 32;
 
 /// Service gérant les messages
 #[derive(Clone)]
 pub struct MessageService {
     message_repo: Arc<MessageRepository>,
     channel_repo: Arc<ChannelRepository>,
-    server_service: Arc<ServerService>,
+    thread_repo: Arc<ThreadRepository>,
+    reaction_repo: Arc<ReactionRepository>,
+    attachment_repo: Arc<AttachmentRepository>,
+    permission_service: Arc<PermissionService>,
+    audit_service: Arc<AuditService>,
+    /// Nombre maximal de messages épinglés par canal
+    max_pins: i64,
 }
 
 impl MessageService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         message_repo: Arc<MessageRepository>,
         channel_repo: Arc<ChannelRepository>,
-        server_service: Arc<ServerService>,
+        thread_repo: Arc<ThreadRepository>,
+        reaction_repo: Arc<ReactionRepository>,
+        attachment_repo: Arc<AttachmentRepository>,
+        permission_service: Arc<PermissionService>,
+        audit_service: Arc<AuditService>,
+        max_pins: i64,
     ) -> Self {
         Self {
             message_repo,
             channel_repo,
-            server_service,
+            thread_repo,
+            reaction_repo,
+            attachment_repo,
+            permission_service,
+            audit_service,
+            max_pins,
         }
     }
 
@@ -40,20 +71,74 @@ impl MessageService {
             .await?
             .ok_or(AppError::ChannelNotFound)?;
 
-        // Vérifier que l'utilisateur est membre du serveur
-        self.server_service
-            .get_user_role(channel.server_id, author_id)
-            .await?;
+        self.check_can_post(&channel, &dto, author_id).await?;
 
-        // Valider le contenu
-        if dto.content.trim().is_empty() {
-            return Err(AppError::ValidationError(
-                "Le message ne peut pas être vide".to_string(),
-            ));
+        if let Some(reply_to_id) = dto.reply_to_id {
+            self.check_reply_target(reply_to_id, channel.id, None).await?;
         }
 
         self.message_repo
-            .create(&dto.content, channel_id, author_id)
+            .create(&dto.content, channel_id, author_id, dto.reply_to_id, None, &dto.attachment_ids)
+            .await?
+            .ok_or_else(attachment_unavailable)
+    }
+
+    /// Créer un message dans un fil de discussion (le participant rejoint le fil)
+    pub async fn create_thread_message(
+        &self,
+        thread_id: i32,
+        dto: CreateMessageDto,
+        author_id: i32,
+    ) -> AppResult<Message> {
+        let (thread, channel) = self.find_thread(thread_id).await?;
+
+        self.check_can_post(&channel, &dto, author_id).await?;
+
+        if let Some(reply_to_id) = dto.reply_to_id {
+            self.check_reply_target(reply_to_id, channel.id, Some(&thread)).await?;
+        }
+
+        let message = self
+            .message_repo
+            .create(
+                &dto.content,
+                channel.id,
+                author_id,
+                dto.reply_to_id,
+                Some(thread.id),
+                &dto.attachment_ids,
+            )
+            .await?
+            .ok_or_else(attachment_unavailable)?;
+
+        self.thread_repo.add_member(thread.id, author_id).await?;
+
+        Ok(message)
+    }
+
+    /// Récupérer l'historique d'un fil de discussion
+    pub async fn get_thread_messages(
+        &self,
+        thread_id: i32,
+        user_id: i32,
+        query: MessageQuery,
+    ) -> AppResult<Vec<MessageWithAuthor>> {
+        let (thread, channel) = self.find_thread(thread_id).await?;
+
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        let cursor = self.page_cursor(&query, channel.id, Some(thread.id)).await?;
+        let limit = query.limit.unwrap_or(50).clamp(1, 100); // Max 100 messages par requête
+
+        self.message_repo
+            .find_by_thread(thread.id, user_id, cursor, limit)
             .await
     }
 
@@ -62,8 +147,7 @@ impl MessageService {
         &self,
         channel_id: i32,
         user_id: i32,
-        limit: Option<i64>,
-        offset: Option<i64>,
+        query: MessageQuery,
     ) -> AppResult<Vec<MessageWithAuthor>> {
         // Vérifier que le canal existe
         let channel = self
@@ -72,16 +156,21 @@ impl MessageService {
             .await?
             .ok_or(AppError::ChannelNotFound)?;
 
-        // Vérifier que l'utilisateur est membre du serveur
-        self.server_service
-            .get_user_role(channel.server_id, user_id)
+        // Vérifier que l'utilisateur peut lire l'historique du canal
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::READ_MESSAGE_HISTORY,
+            )
             .await?;
 
-        let limit = limit.unwrap_or(50).min(100); // Max 100 messages par requête
-        let offset = offset.unwrap_or(0);
+        let cursor = self.page_cursor(&query, channel.id, None).await?;
+        let limit = query.limit.unwrap_or(50).clamp(1, 100); // Max 100 messages par requête
 
         self.message_repo
-            .find_by_channel(channel_id, limit, offset)
+            .find_by_channel(channel_id, user_id, cursor, limit)
             .await
     }
 
@@ -100,14 +189,91 @@ impl MessageService {
             .await?
             .ok_or(AppError::ChannelNotFound)?;
 
-        // Vérifier que l'utilisateur est membre du serveur
-        self.server_service
-            .get_user_role(channel.server_id, user_id)
+        // Vérifier que l'utilisateur peut voir le canal
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::VIEW_CHANNELS)
             .await?;
 
         Ok(message)
     }
 
+    /// Modifier un message (auteur uniquement)
+    pub async fn update_message(
+        &self,
+        message_id: i32,
+        dto: UpdateMessageDto,
+        user_id: i32,
+    ) -> AppResult<Message> {
+        let message = self
+            .message_repo
+            .find_by_id(message_id)
+            .await?
+            .filter(|message| !message.is_deleted)
+            .ok_or(AppError::MessageNotFound)?;
+
+        // Vérifier que le canal existe
+        let channel = self
+            .channel_repo
+            .find_by_id(message.channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        // Vérifier que l'utilisateur a toujours accès au canal
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::VIEW_CHANNELS)
+            .await?;
+
+        if message.author_id != user_id {
+            return Err(AppError::Forbidden);
+        }
+
+        // Valider le contenu
+        if dto.content.trim().is_empty() {
+            return Err(AppError::ValidationError(
+                "Le message ne peut pas être vide".to_string(),
+            ));
+        }
+
+        // Rien à faire si le contenu est identique
+        if dto.content == message.content {
+            return Ok(message);
+        }
+
+        self.message_repo.update(message_id, &dto.content).await
+    }
+
+    /// Récupérer l'historique des modifications d'un message (MANAGE_MESSAGES)
+    pub async fn get_message_revisions(
+        &self,
+        message_id: i32,
+        user_id: i32,
+    ) -> AppResult<Vec<MessageRevision>> {
+        let message = self
+            .message_repo
+            .find_by_id(message_id)
+            .await?
+            .ok_or(AppError::MessageNotFound)?;
+
+        // Vérifier que le canal existe
+        let channel = self
+            .channel_repo
+            .find_by_id(message.channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        // Vérifier les permissions
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::MANAGE_MESSAGES,
+            )
+            .await?;
+
+        self.message_repo.find_revisions(message_id).await
+    }
+
     /// Supprimer un message
     pub async fn delete_message(&self, message_id: i32, user_id: i32) -> AppResult<()> {
         let message = self
@@ -123,20 +289,373 @@ impl MessageService {
             .await?
             .ok_or(AppError::ChannelNotFound)?;
 
-        // Permissions : l'auteur peut supprimer son message, ou un Admin/Owner
-        let role = self
-            .server_service
-            .get_user_role(channel.server_id, user_id)
+        // Permissions : l'auteur peut supprimer son message, ou un membre avec MANAGE_MESSAGES
+        let permissions = self
+            .permission_service
+            .compute(user_id, channel.server_id, Some(channel.id))
             .await?;
 
         let can_delete = message.author_id == user_id
-            || matches!(role, UserRole::Admin | UserRole::Owner);
+            || permissions.contains(Permissions::MANAGE_MESSAGES);
 
         if !can_delete {
             return Err(AppError::Forbidden);
         }
 
         // Soft delete
-        self.message_repo.soft_delete(message_id).await
+        self.message_repo.soft_delete(message_id).await?;
+
+        // Seules les suppressions par un modérateur sont journalisées
+        if message.author_id != user_id {
+            self.audit_service
+                .record(
+                    NewAuditEntry::new(channel.server_id, user_id, AuditAction::MessageDelete, Some(message.id))
+                        .before(&message),
+                )
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Lister les messages épinglés d'un canal
+    pub async fn get_pins(&self, channel_id: i32, user_id: i32) -> AppResult<Vec<MessageWithAuthor>> {
+        let channel = self
+            .channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        self.message_repo.find_pins(channel.id, user_id).await
+    }
+
+    /// Épingler un message du canal (None s'il l'était déjà)
+    pub async fn pin_message(
+        &self,
+        channel_id: i32,
+        message_id: i32,
+        user_id: i32,
+    ) -> AppResult<Option<DateTime<Utc>>> {
+        let (message, channel) = self.find_pinnable(channel_id, message_id, user_id).await?;
+        if message.pinned_at.is_some() {
+            return Ok(None);
+        }
+
+        let pinned_at = match self.message_repo.pin(message.id, channel.id, user_id, self.max_pins).await? {
+            PinOutcome::Pinned(pinned_at) => pinned_at,
+            PinOutcome::AlreadyPinned => return Ok(None),
+            PinOutcome::LimitReached => {
+                return Err(AppError::ValidationError(format!(
+                    "Un canal ne peut pas avoir plus de {} messages épinglés",
+                    self.max_pins
+                )));
+            }
+        };
+
+        self.audit_service
+            .record(NewAuditEntry::new(channel.server_id, user_id, AuditAction::MessagePin, Some(message.id)))
+            .await;
+
+        Ok(Some(pinned_at))
+    }
+
+    /// Désépingler un message du canal (false s'il ne l'était pas)
+    pub async fn unpin_message(&self, channel_id: i32, message_id: i32, user_id: i32) -> AppResult<bool> {
+        let (message, channel) = self.find_pinnable(channel_id, message_id, user_id).await?;
+
+        let unpinned = self.message_repo.unpin(message.id).await?;
+        if unpinned {
+            self.audit_service
+                .record(NewAuditEntry::new(channel.server_id, user_id, AuditAction::MessageUnpin, Some(message.id)))
+                .await;
+        }
+
+        Ok(unpinned)
     }
+
+    /// Réagir à un message (None si la réaction existait déjà)
+    pub async fn add_reaction(
+        &self,
+        message_id: i32,
+        emoji: &str,
+        user_id: i32,
+    ) -> AppResult<Option<ReactionChange>> {
+        validate_emoji(emoji)?;
+
+        let (message, channel) = self.find_reactable(message_id, user_id).await?;
+
+        // Un membre exclu temporairement ne peut plus réagir
+        self.permission_service
+            .check_not_timed_out(channel.server_id, user_id)
+            .await?;
+
+        let emojis = self.reaction_repo.find_emojis(message.id).await?;
+        if !emojis.iter().any(|existing| existing == emoji) && emojis.len() >= MAX_REACTIONS_PER_MESSAGE {
+            return Err(AppError::ValidationError(format!(
+                "Un message ne peut pas recevoir plus de {} réactions différentes",
+                MAX_REACTIONS_PER_MESSAGE
+            )));
+        }
+
+        if !self.reaction_repo.add(message.id, user_id, emoji).await? {
+            return Ok(None);
+        }
+
+        self.reaction_change(&message, emoji).await.map(Some)
+    }
+
+    /// Retirer sa réaction (None si elle n'existait pas)
+    pub async fn remove_reaction(
+        &self,
+        message_id: i32,
+        emoji: &str,
+        user_id: i32,
+    ) -> AppResult<Option<ReactionChange>> {
+        let (message, _) = self.find_reactable(message_id, user_id).await?;
+
+        if !self.reaction_repo.remove(message.id, user_id, emoji).await? {
+            return Ok(None);
+        }
+
+        self.reaction_change(&message, emoji).await.map(Some)
+    }
+
+    /// Récupérer un message visible auquel l'utilisateur peut réagir
+    async fn find_reactable(&self, message_id: i32, user_id: i32) -> AppResult<(Message, Channel)> {
+        let message = self
+            .message_repo
+            .find_by_id(message_id)
+            .await?
+            .filter(|message| !message.is_deleted)
+            .ok_or(AppError::MessageNotFound)?;
+
+        let channel = self
+            .channel_repo
+            .find_by_id(message.channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        Ok((message, channel))
+    }
+
+    /// Message du canal lui-même (hors fils) que l'utilisateur peut épingler
+    async fn find_pinnable(&self, channel_id: i32, message_id: i32, user_id: i32) -> AppResult<(Message, Channel)> {
+        let channel = self
+            .channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::MANAGE_MESSAGES,
+            )
+            .await?;
+
+        let message = self
+            .message_repo
+            .find_by_id(message_id)
+            .await?
+            .filter(|message| {
+                !message.is_deleted && message.channel_id == channel.id && message.thread_id.is_none()
+            })
+            .ok_or(AppError::MessageNotFound)?;
+
+        Ok((message, channel))
+    }
+
+    async fn reaction_change(&self, message: &Message, emoji: &str) -> AppResult<ReactionChange> {
+        let count = self.reaction_repo.count(message.id, emoji).await?;
+
+        Ok(ReactionChange {
+            channel_id: message.channel_id,
+            message_id: message.id,
+            emoji: emoji.to_string(),
+            count,
+        })
+    }
+
+    /// Vérifier qu'un membre peut écrire dans un canal et valider le contenu
+    async fn check_can_post(
+        &self,
+        channel: &Channel,
+        dto: &CreateMessageDto,
+        author_id: i32,
+    ) -> AppResult<()> {
+        // Vérifier que l'utilisateur peut écrire dans le canal
+        self.permission_service
+            .check(
+                author_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::SEND_MESSAGES,
+            )
+            .await?;
+
+        // Un membre exclu temporairement ne peut plus écrire
+        self.permission_service
+            .check_not_timed_out(channel.server_id, author_id)
+            .await?;
+
+        // Valider le contenu (un message peut ne contenir que des pièces jointes)
+        if dto.content.trim().is_empty() && dto.attachment_ids.is_empty() {
+            return Err(AppError::ValidationError(
+                "Le message ne peut pas être vide".to_string(),
+            ));
+        }
+
+        self.check_attachments(dto, channel.id, author_id).await
+    }
+
+    /// Vérifier que les pièces jointes sont en attente, envoyées par l'auteur dans ce canal
+    async fn check_attachments(
+        &self,
+        dto: &CreateMessageDto,
+        channel_id: i32,
+        author_id: i32,
+    ) -> AppResult<()> {
+        if dto.attachment_ids.is_empty() {
+            return Ok(());
+        }
+        if dto.attachment_ids.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+            return Err(AppError::ValidationError(format!(
+                "{} pièces jointes maximum par message",
+                MAX_ATTACHMENTS_PER_MESSAGE
+            )));
+        }
+
+        let mut ids = dto.attachment_ids.clone();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let attachments = self.attachment_repo.find_by_ids(&ids).await?;
+        let usable = attachments.len() == ids.len()
+            && attachments.iter().all(|attachment| {
+                attachment.message_id.is_none()
+                    && attachment.uploader_id == Some(author_id)
+                    && attachment.channel_id == channel_id
+            });
+
+        if !usable {
+            return Err(attachment_unavailable());
+        }
+
+        Ok(())
+    }
+
+    /// Vérifier qu'une réponse vise un message du même canal (ou du même fil)
+    ///
+    /// Dans un fil, on peut aussi répondre au message d'origine.
+    async fn check_reply_target(
+        &self,
+        reply_to_id: i32,
+        channel_id: i32,
+        thread: Option<&Thread>,
+    ) -> AppResult<()> {
+        let target = self
+            .message_repo
+            .find_by_id(reply_to_id)
+            .await?
+            .filter(|message| !message.is_deleted && message.channel_id == channel_id);
+
+        let in_context = match (&target, thread) {
+            (Some(target), Some(thread)) => {
+                target.thread_id == Some(thread.id) || target.id == thread.root_message_id
+            }
+            (Some(target), None) => target.thread_id.is_none(),
+            (None, _) => false,
+        };
+
+        if !in_context {
+            return Err(AppError::ValidationError(
+                "Le message auquel vous répondez n'existe pas dans cette conversation".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Valider le curseur d'une page d'historique
+    ///
+    /// Le message visé par `around` doit appartenir à la conversation (canal ou fil).
+    async fn page_cursor(
+        &self,
+        query: &MessageQuery,
+        channel_id: i32,
+        thread_id: Option<i32>,
+    ) -> AppResult<MessageCursor> {
+        let cursor = query.cursor().ok_or_else(|| {
+            AppError::ValidationError(
+                "Un seul des paramètres before, after et around peut être utilisé".to_string(),
+            )
+        })?;
+
+        if let MessageCursor::Around(message_id) = cursor {
+            self.message_repo
+                .find_by_id(message_id)
+                .await?
+                .filter(|message| {
+                    !message.is_deleted
+                        && message.channel_id == channel_id
+                        && message.thread_id == thread_id
+                })
+                .ok_or(AppError::MessageNotFound)?;
+        }
+
+        Ok(cursor)
+    }
+
+    /// Récupérer un fil et son canal parent
+    async fn find_thread(&self, thread_id: i32) -> AppResult<(Thread, Channel)> {
+        let thread = self
+            .thread_repo
+            .find_by_id(thread_id)
+            .await?
+            .ok_or(AppError::ThreadNotFound)?;
+
+        let channel = self
+            .channel_repo
+            .find_by_id(thread.channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        Ok((thread, channel))
+    }
+}
+
+/// Un emoji est une courte séquence sans espace
+fn validate_emoji(emoji: &str) -> AppResult<()> {
+    let length = emoji.chars().count();
+    if length == 0
+        || length > MAX_EMOJI_LENGTH
+        || emoji.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(AppError::ValidationError("Emoji invalide".to_string()));
+    }
+    Ok(())
+}
+
+fn attachment_unavailable() -> AppError {
+    AppError::ValidationError("Pièce jointe introuvable ou déjà utilisée".to_string())
 }
@@ -2,8 +2,30 @@ pub mod auth_service;
 pub mod server_service;
 pub mod channel_service;
 pub mod message_service;
+pub mod dm_service;
+pub mod permission_service;
+pub mod role_service;
+pub mod audit_service;
+pub mod invitation_service;
+pub mod thread_service;
+pub mod mention_service;
+pub mod read_state_service;
+pub mod search_service;
+pub mod attachment_service;
+pub mod presence_service;
 
 pub use auth_service::AuthService;
 pub use server_service::ServerService;
 pub use channel_service::ChannelService;
 pub use message_service::MessageService;
+pub use dm_service::DmService;
+pub use permission_service::PermissionService;
+pub use role_service::RoleService;
+pub use audit_service::AuditService;
+pub use invitation_service::InvitationService;
+pub use thread_service::ThreadService;
+pub use mention_service::MentionService;
+pub use read_state_service::ReadStateService;
+pub use search_service::SearchService;
+pub use attachment_service::AttachmentService;
+pub use presence_service::PresenceService;
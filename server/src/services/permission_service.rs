@@ -0,0 +1,325 @@
+use std::sync::Arc;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Channel, PermissionOverwrite, Permissions, Server};
+use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
+use crate::repositories::role_repository::{RoleRepository, RoleRepositoryTrait};
+use crate::repositories::server_repository::{ServerRepository, ServerRepositoryTrait};
+
+/// Service central de résolution des permissions
+///
+/// Toutes les vérifications d'accès des autres services passent par `check`.
+#[derive(Clone)]
+pub struct PermissionService {
+    server_repo: Arc<ServerRepository>,
+    role_repo: Arc<RoleRepository>,
+    channel_repo: Arc<ChannelRepository>,
+}
+
+/// Permissions d'un membre au niveau du serveur, avant les surcharges de canal
+struct MemberPermissions {
+    user_id: i32,
+    base: Permissions,
+    /// Propriétaire ou ADMINISTRATOR : les surcharges ne s'appliquent pas
+    bypass_overwrites: bool,
+    default_role_id: Option<i32>,
+    role_ids: Vec<i32>,
+}
+
+impl MemberPermissions {
+    /// Appliquer les surcharges d'un canal : @everyone, puis les rôles, puis le membre
+    fn for_channel(&self, channel_id: i32, overwrites: &[PermissionOverwrite]) -> Permissions {
+        if self.bypass_overwrites {
+            return self.base;
+        }
+
+        let overwrites: Vec<&PermissionOverwrite> = overwrites
+            .iter()
+            .filter(|overwrite| overwrite.channel_id == channel_id)
+            .collect();
+
+        let mut permissions = self.base;
+
+        if let Some(default_role_id) = self.default_role_id {
+            if let Some(overwrite) = overwrites.iter().find(|o| o.role_id == Some(default_role_id)) {
+                permissions = (permissions & !overwrite.deny) | overwrite.allow;
+            }
+        }
+
+        let mut role_allow = Permissions::NONE;
+        let mut role_deny = Permissions::NONE;
+        for overwrite in overwrites
+            .iter()
+            .filter(|o| matches!(o.role_id, Some(role_id) if self.role_ids.contains(&role_id)))
+        {
+            role_allow |= overwrite.allow;
+            role_deny |= overwrite.deny;
+        }
+        permissions = (permissions & !role_deny) | role_allow;
+
+        if let Some(overwrite) = overwrites.iter().find(|o| o.user_id == Some(self.user_id)) {
+            permissions = (permissions & !overwrite.deny) | overwrite.allow;
+        }
+
+        permissions
+    }
+}
+
+impl PermissionService {
+    pub fn new(
+        server_repo: Arc<ServerRepository>,
+        role_repo: Arc<RoleRepository>,
+        channel_repo: Arc<ChannelRepository>,
+    ) -> Self {
+        Self {
+            server_repo,
+            role_repo,
+            channel_repo,
+        }
+    }
+
+    /// Calculer les permissions effectives d'un utilisateur
+    ///
+    /// Le propriétaire possède toutes les permissions. Les autres membres cumulent
+    /// le rôle @everyone et leurs rôles attribués, puis les surcharges du canal
+    /// s'il est précisé. Un non-membre n'a accès à rien.
+    pub async fn compute(
+        &self,
+        user_id: i32,
+        server_id: i32,
+        channel_id: Option<i32>,
+    ) -> AppResult<Permissions> {
+        let member = self.member_permissions(user_id, server_id).await?;
+
+        match channel_id {
+            Some(channel_id) if !member.bypass_overwrites => {
+                let overwrites = self.channel_repo.find_overwrites(channel_id).await?;
+                Ok(member.for_channel(channel_id, &overwrites))
+            }
+            _ => Ok(member.base),
+        }
+    }
+
+    /// Vérifier qu'un utilisateur possède une permission (sur le serveur ou un canal)
+    pub async fn check(
+        &self,
+        user_id: i32,
+        server_id: i32,
+        channel_id: Option<i32>,
+        permission: Permissions,
+    ) -> AppResult<()> {
+        let granted = self.compute(user_id, server_id, channel_id).await?;
+
+        if !granted.contains(permission) {
+            let missing = permission & !granted;
+            return Err(AppError::MissingPermission(missing.names().join(", ")));
+        }
+
+        Ok(())
+    }
+
+    /// Ne garder que les canaux d'un serveur sur lesquels l'utilisateur a une permission
+    pub async fn filter_channels(
+        &self,
+        user_id: i32,
+        server_id: i32,
+        channels: Vec<Channel>,
+        permission: Permissions,
+    ) -> AppResult<Vec<Channel>> {
+        let member = self.member_permissions(user_id, server_id).await?;
+
+        let overwrites = if member.bypass_overwrites {
+            Vec::new()
+        } else {
+            self.channel_repo.find_overwrites_by_server(server_id).await?
+        };
+
+        Ok(channels
+            .into_iter()
+            .filter(|channel| member.for_channel(channel.id, &overwrites).contains(permission))
+            .collect())
+    }
+
+    /// Vérifier qu'un membre n'est pas exclu temporairement
+    pub async fn check_not_timed_out(&self, server_id: i32, user_id: i32) -> AppResult<()> {
+        if let Some(until) = self.server_repo.get_timeout(server_id, user_id).await? {
+            return Err(AppError::MemberTimedOut(until.to_rfc3339()));
+        }
+        Ok(())
+    }
+
+    /// Vérifier qu'un utilisateur est membre du serveur
+    pub async fn is_member(&self, server_id: i32, user_id: i32) -> AppResult<bool> {
+        self.server_repo.is_member(server_id, user_id).await
+    }
+
+    /// Vérifier qu'un utilisateur est propriétaire du serveur
+    pub async fn is_owner(&self, server_id: i32, user_id: i32) -> AppResult<bool> {
+        let server = self.get_server(server_id).await?;
+        Ok(server.owner_id == user_id)
+    }
+
+    /// Position du rôle le plus élevé d'un membre (le propriétaire domine tout le monde)
+    pub async fn highest_position(&self, server_id: i32, user_id: i32) -> AppResult<i32> {
+        if self.is_owner(server_id, user_id).await? {
+            return Ok(i32::MAX);
+        }
+
+        let roles = self.role_repo.get_member_roles(server_id, user_id).await?;
+        Ok(roles.iter().map(|role| role.position).max().unwrap_or(0))
+    }
+
+    /// Vérifier qu'un membre est hiérarchiquement au-dessus d'un autre
+    pub async fn check_outranks(&self, server_id: i32, actor_id: i32, target_id: i32) -> AppResult<()> {
+        let actor_position = self.highest_position(server_id, actor_id).await?;
+        let target_position = self.highest_position(server_id, target_id).await?;
+
+        if actor_position <= target_position {
+            return Err(AppError::RoleHierarchy);
+        }
+
+        Ok(())
+    }
+
+    /// Permissions d'un membre au niveau du serveur
+    async fn member_permissions(&self, user_id: i32, server_id: i32) -> AppResult<MemberPermissions> {
+        let server = self.get_server(server_id).await?;
+
+        if server.owner_id == user_id {
+            return Ok(MemberPermissions {
+                user_id,
+                base: Permissions::all(),
+                bypass_overwrites: true,
+                default_role_id: None,
+                role_ids: Vec::new(),
+            });
+        }
+
+        if !self.server_repo.is_member(server_id, user_id).await? {
+            return Err(AppError::Forbidden);
+        }
+
+        let mut roles = self.role_repo.get_member_roles(server_id, user_id).await?;
+        let role_ids = roles.iter().map(|role| role.id).collect();
+        let default_role = self.role_repo.find_default(server_id).await?;
+        let default_role_id = default_role.as_ref().map(|role| role.id);
+        roles.extend(default_role);
+
+        let base = Permissions::from_roles(&roles);
+
+        Ok(MemberPermissions {
+            user_id,
+            base,
+            bypass_overwrites: base.contains(Permissions::ADMINISTRATOR),
+            default_role_id,
+            role_ids,
+        })
+    }
+
+    async fn get_server(&self, server_id: i32) -> AppResult<Server> {
+        self.server_repo
+            .find_by_id(server_id)
+            .await?
+            .ok_or(AppError::ServerNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: i32 = 10;
+    const EVERYONE: i32 = 1;
+    const MODERATOR: i32 = 2;
+    const MUTED: i32 = 3;
+    const USER: i32 = 42;
+
+    fn member(role_ids: Vec<i32>) -> MemberPermissions {
+        MemberPermissions {
+            user_id: USER,
+            base: Permissions::VIEW_CHANNELS | Permissions::SEND_MESSAGES,
+            bypass_overwrites: false,
+            default_role_id: Some(EVERYONE),
+            role_ids,
+        }
+    }
+
+    fn role(role_id: i32, allow: Permissions, deny: Permissions) -> PermissionOverwrite {
+        PermissionOverwrite {
+            id: role_id,
+            channel_id: CHANNEL,
+            role_id: Some(role_id),
+            user_id: None,
+            allow,
+            deny,
+        }
+    }
+
+    fn user(user_id: i32, allow: Permissions, deny: Permissions) -> PermissionOverwrite {
+        PermissionOverwrite {
+            id: 100 + user_id,
+            channel_id: CHANNEL,
+            role_id: None,
+            user_id: Some(user_id),
+            allow,
+            deny,
+        }
+    }
+
+    #[test]
+    fn role_overwrites_apply_after_everyone() {
+        // Canal privé : @everyone ne voit pas le canal, les modérateurs oui
+        let overwrites = [
+            role(EVERYONE, Permissions::NONE, Permissions::VIEW_CHANNELS),
+            role(MODERATOR, Permissions::VIEW_CHANNELS, Permissions::NONE),
+        ];
+
+        assert!(!member(vec![]).for_channel(CHANNEL, &overwrites).contains(Permissions::VIEW_CHANNELS));
+        assert!(member(vec![MODERATOR]).for_channel(CHANNEL, &overwrites).contains(Permissions::VIEW_CHANNELS));
+    }
+
+    #[test]
+    fn role_allow_wins_over_role_deny() {
+        let overwrites = [
+            role(MODERATOR, Permissions::SEND_MESSAGES, Permissions::NONE),
+            role(MUTED, Permissions::NONE, Permissions::SEND_MESSAGES),
+        ];
+
+        let permissions = member(vec![MODERATOR, MUTED]).for_channel(CHANNEL, &overwrites);
+        assert!(permissions.contains(Permissions::SEND_MESSAGES));
+        assert!(!member(vec![MUTED]).for_channel(CHANNEL, &overwrites).contains(Permissions::SEND_MESSAGES));
+    }
+
+    #[test]
+    fn member_overwrite_applies_last() {
+        let overwrites = [
+            role(EVERYONE, Permissions::NONE, Permissions::SEND_MESSAGES),
+            role(MODERATOR, Permissions::SEND_MESSAGES, Permissions::NONE),
+            user(USER, Permissions::NONE, Permissions::SEND_MESSAGES),
+            user(USER + 1, Permissions::MANAGE_MESSAGES, Permissions::NONE),
+        ];
+
+        let permissions = member(vec![MODERATOR]).for_channel(CHANNEL, &overwrites);
+        assert!(!permissions.contains(Permissions::SEND_MESSAGES));
+        assert!(!permissions.contains(Permissions::MANAGE_MESSAGES));
+    }
+
+    #[test]
+    fn overwrites_of_other_channels_are_ignored() {
+        let mut overwrite = role(EVERYONE, Permissions::NONE, Permissions::VIEW_CHANNELS);
+        overwrite.channel_id = CHANNEL + 1;
+
+        assert_eq!(member(vec![]).for_channel(CHANNEL, &[overwrite]), member(vec![]).base);
+    }
+
+    #[test]
+    fn owner_and_administrators_bypass_overwrites() {
+        let mut admin = member(vec![]);
+        admin.bypass_overwrites = true;
+        let overwrites = [
+            role(EVERYONE, Permissions::NONE, Permissions::VIEW_CHANNELS),
+            user(USER, Permissions::NONE, Permissions::SEND_MESSAGES),
+        ];
+
+        assert_eq!(admin.for_channel(CHANNEL, &overwrites), admin.base);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::error;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Presence, PresenceSettings, PresenceStatus, UpdatePresenceDto};
+use crate::repositories::server_repository::{ServerRepository, ServerRepositoryTrait};
+use crate::repositories::user_repository::{UserRepository, UserRepositoryTrait};
+use crate::ws::{Hub, HubEffects};
+
+/// Longueur maximale d'un statut personnalisé, en caractères
+const MAX_CUSTOM_STATUS_LENGTH: usize = 128;
+
+/// Service gérant la présence des utilisateurs (statut choisi et activité des sockets)
+#[derive(Clone)]
+pub struct PresenceService {
+    user_repo: Arc<UserRepository>,
+    server_repo: Arc<ServerRepository>,
+    /// Sockets connectés et leur activité
+    hub: Arc<Hub>,
+    /// Délai sans activité avant le passage automatique en inactif
+    idle_after: Duration,
+}
+
+impl PresenceService {
+    pub fn new(
+        user_repo: Arc<UserRepository>,
+        server_repo: Arc<ServerRepository>,
+        hub: Arc<Hub>,
+        idle_after: Duration,
+    ) -> Self {
+        Self {
+            user_repo,
+            server_repo,
+            hub,
+            idle_after,
+        }
+    }
+
+    /// Enregistrer un nouveau socket authentifié, avec le statut choisi par l'utilisateur
+    /// (statut par défaut s'il ne peut pas être lu)
+    pub async fn connect(&self, socket_id: &str, user_id: i32, username: &str) -> HubEffects {
+        let settings = match self.user_repo.find_presence_settings(user_id).await {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(e) => {
+                error!("Impossible de lire le statut de {} : {}", user_id, e);
+                PresenceSettings::default()
+            }
+        };
+
+        self.hub
+            .connect_socket(socket_id.to_string(), user_id, username.to_string(), settings)
+            .await
+    }
+
+    /// Noter une activité de l'utilisateur sur un socket
+    pub async fn touch(&self, user_id: i32, socket_id: &str) -> HubEffects {
+        self.hub.touch_presence_socket(user_id, socket_id).await
+    }
+
+    /// Changer le statut choisi et/ou le statut personnalisé
+    pub async fn update(&self, user_id: i32, dto: UpdatePresenceDto) -> AppResult<HubEffects> {
+        if dto.status == Some(PresenceStatus::Offline) {
+            return Err(AppError::ValidationError(
+                "Statut invalide : choisissez invisible pour paraître hors ligne".to_string(),
+            ));
+        }
+
+        let mut settings = self
+            .user_repo
+            .find_presence_settings(user_id)
+            .await?
+            .ok_or(AppError::UserNotFound)?;
+
+        if let Some(status) = dto.status {
+            settings.status = status;
+        }
+        if let Some(custom_status) = dto.custom_status {
+            settings.custom_status = validate_custom_status(&custom_status)?;
+        }
+
+        self.user_repo.update_presence_settings(user_id, &settings).await?;
+
+        Ok(self.hub.set_presence_settings(user_id, settings).await)
+    }
+
+    /// Passer en inactif les sockets sans activité récente
+    pub async fn mark_idle(&self) -> HubEffects {
+        self.hub.mark_idle_sockets(self.idle_after).await
+    }
+
+    /// Serveurs de l'utilisateur, à prévenir de ses changements de présence
+    pub async fn get_server_ids(&self, user_id: i32) -> AppResult<Vec<i32>> {
+        let servers = self.server_repo.find_by_user(user_id).await?;
+        Ok(servers.iter().map(|server| server.id).collect())
+    }
+
+    /// Présence d'un utilisateur, vue par lui-même
+    pub async fn get_presence(&self, user_id: i32) -> Presence {
+        self.hub.get_presence(user_id).await
+    }
+
+    /// Membres d'un serveur actuellement en ligne (les invisibles sont exclus)
+    pub async fn get_server_presences(&self, server_id: i32) -> AppResult<Vec<Presence>> {
+        let members: HashSet<i32> = self
+            .server_repo
+            .get_members(server_id)
+            .await?
+            .iter()
+            .map(|member| member.user_id)
+            .collect();
+
+        let mut presences: Vec<Presence> = self
+            .hub
+            .get_online_presences()
+            .await
+            .into_iter()
+            .filter(|presence| members.contains(&presence.user_id))
+            .collect();
+        presences.sort_unstable_by_key(|presence| presence.user_id);
+
+        Ok(presences)
+    }
+}
+
+/// Statut personnalisé nettoyé ; vide pour le retirer
+fn validate_custom_status(custom_status: &str) -> AppResult<Option<String>> {
+    let custom_status = custom_status.trim();
+    if custom_status.is_empty() {
+        return Ok(None);
+    }
+    if custom_status.chars().count() > MAX_CUSTOM_STATUS_LENGTH {
+        return Err(AppError::ValidationError(format!(
+            "Le statut personnalisé ne peut pas dépasser {} caractères",
+            MAX_CUSTOM_STATUS_LENGTH
+        )));
+    }
+    if custom_status.chars().any(char::is_control) {
+        return Err(AppError::ValidationError(
+            "Le statut personnalisé ne peut pas contenir de caractères de contrôle".to_string(),
+        ));
+    }
+
+    Ok(Some(custom_status.to_string()))
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::errors::{AppError, AppResult};
+use crate::models::{
+    AckChannelDto, Channel, ChannelReadState, ChannelWithReadState, Permissions, Server,
+    ServerWithReadState,
+};
+use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
+use crate::repositories::message_repository::{MessageRepository, MessageRepositoryTrait};
+use crate::repositories::read_state_repository::{ReadStateRepository, ReadStateRepositoryTrait};
+use crate::services::PermissionService;
+
+/// Service gérant les états de lecture et les compteurs de non-lus
+#[derive(Clone)]
+pub struct ReadStateService {
+    read_state_repo: Arc<ReadStateRepository>,
+    message_repo: Arc<MessageRepository>,
+    channel_repo: Arc<ChannelRepository>,
+    permission_service: Arc<PermissionService>,
+}
+
+impl ReadStateService {
+    pub fn new(
+        read_state_repo: Arc<ReadStateRepository>,
+        message_repo: Arc<MessageRepository>,
+        channel_repo: Arc<ChannelRepository>,
+        permission_service: Arc<PermissionService>,
+    ) -> Self {
+        Self {
+            read_state_repo,
+            message_repo,
+            channel_repo,
+            permission_service,
+        }
+    }
+
+    /// Marquer un canal comme lu jusqu'à un message (le dernier du canal par défaut)
+    pub async fn ack_channel(
+        &self,
+        channel_id: i32,
+        dto: AckChannelDto,
+        user_id: i32,
+    ) -> AppResult<ChannelReadState> {
+        let channel = self
+            .channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)?;
+
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::VIEW_CHANNELS)
+            .await?;
+
+        let message_id = match dto.message_id {
+            Some(message_id) => {
+                let message = self
+                    .message_repo
+                    .find_by_id(message_id)
+                    .await?
+                    .filter(|message| message.channel_id == channel.id && message.thread_id.is_none())
+                    .ok_or(AppError::MessageNotFound)?;
+                Some(message.id)
+            }
+            None => self.read_state_repo.find_last_message_id(channel.id).await?,
+        };
+
+        // Un canal vide n'a rien à marquer
+        if let Some(message_id) = message_id {
+            self.read_state_repo.ack(user_id, channel.id, message_id).await?;
+        }
+
+        self.read_state_repo
+            .find_states(user_id, &[channel.id])
+            .await?
+            .pop()
+            .ok_or(AppError::ChannelNotFound)
+    }
+
+    /// Ajouter l'état de lecture aux canaux (déjà filtrés sur VIEW_CHANNELS)
+    pub async fn with_read_states(
+        &self,
+        user_id: i32,
+        channels: Vec<Channel>,
+    ) -> AppResult<Vec<ChannelWithReadState>> {
+        let mut states = self.find_states(user_id, &channels).await?;
+
+        Ok(channels
+            .into_iter()
+            .map(|channel| {
+                let state = states.remove(&channel.id);
+                ChannelWithReadState {
+                    last_read_message_id: state.as_ref().and_then(|s| s.last_read_message_id),
+                    unread_count: state.as_ref().map_or(0, |s| s.unread_count),
+                    mention_count: state.as_ref().map_or(0, |s| s.mention_count),
+                    channel,
+                }
+            })
+            .collect())
+    }
+
+    /// Ajouter aux serveurs le total des non-lus de leurs canaux visibles
+    pub async fn with_server_read_states(
+        &self,
+        user_id: i32,
+        servers: Vec<Server>,
+    ) -> AppResult<Vec<ServerWithReadState>> {
+        let mut result = Vec::with_capacity(servers.len());
+
+        for server in servers {
+            let channels = self.channel_repo.find_by_server(server.id).await?;
+            let channels = self
+                .permission_service
+                .filter_channels(user_id, server.id, channels, Permissions::VIEW_CHANNELS)
+                .await?;
+            let states = self.find_states(user_id, &channels).await?;
+
+            result.push(ServerWithReadState {
+                unread_count: states.values().map(|s| s.unread_count).sum(),
+                mention_count: states.values().map(|s| s.mention_count).sum(),
+                server,
+            });
+        }
+
+        Ok(result)
+    }
+
+    async fn find_states(
+        &self,
+        user_id: i32,
+        channels: &[Channel],
+    ) -> AppResult<HashMap<i32, ChannelReadState>> {
+        if channels.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let channel_ids: Vec<i32> = channels.iter().map(|channel| channel.id).collect();
+        let states = self.read_state_repo.find_states(user_id, &channel_ids).await?;
+
+        Ok(states.into_iter().map(|state| (state.channel_id, state)).collect())
+    }
+}
@@ -0,0 +1,270 @@
+use std::sync::Arc;
+use crate::errors::{AppError, AppResult};
+use crate::models::{AuditAction, CreateRoleDto, NewAuditEntry, Permissions, Role, UpdateRoleDto};
+use crate::repositories::role_repository::{RoleRepository, RoleRepositoryTrait};
+use crate::repositories::server_repository::{ServerRepository, ServerRepositoryTrait};
+use crate::services::{AuditService, PermissionService};
+
+/// Service gérant les rôles personnalisés
+#[derive(Clone)]
+pub struct RoleService {
+    role_repo: Arc<RoleRepository>,
+    server_repo: Arc<ServerRepository>,
+    permission_service: Arc<PermissionService>,
+    audit_service: Arc<AuditService>,
+}
+
+impl RoleService {
+    pub fn new(
+        role_repo: Arc<RoleRepository>,
+        server_repo: Arc<ServerRepository>,
+        permission_service: Arc<PermissionService>,
+        audit_service: Arc<AuditService>,
+    ) -> Self {
+        Self {
+            role_repo,
+            server_repo,
+            permission_service,
+            audit_service,
+        }
+    }
+
+    /// Lister les rôles d'un serveur (du plus élevé au plus bas)
+    pub async fn get_server_roles(&self, server_id: i32, user_id: i32) -> AppResult<Vec<Role>> {
+        // Vérifier que l'utilisateur est membre du serveur
+        self.permission_service.compute(user_id, server_id, None).await?;
+
+        self.role_repo.find_by_server(server_id).await
+    }
+
+    /// Créer un rôle (MANAGE_ROLES)
+    pub async fn create_role(&self, server_id: i32, dto: CreateRoleDto, user_id: i32) -> AppResult<Role> {
+        let granted = self.check_can_manage_roles(server_id, user_id).await?;
+
+        let name = validate_role_name(&dto.name)?;
+        let permissions = dto.permissions & Permissions::all();
+        self.check_can_grant(granted, permissions)?;
+        self.check_position(server_id, dto.position, user_id).await?;
+
+        let role = self
+            .role_repo
+            .create(server_id, &name, permissions, dto.position, false)
+            .await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::RoleCreate, Some(role.id))
+                    .after(&role),
+            )
+            .await;
+
+        Ok(role)
+    }
+
+    /// Mettre à jour un rôle (MANAGE_ROLES, rôles inférieurs uniquement)
+    pub async fn update_role(
+        &self,
+        server_id: i32,
+        role_id: i32,
+        dto: UpdateRoleDto,
+        user_id: i32,
+    ) -> AppResult<Role> {
+        let granted = self.check_can_manage_roles(server_id, user_id).await?;
+        let role = self.get_role(server_id, role_id).await?;
+
+        if !role.is_default {
+            self.check_position(server_id, role.position, user_id).await?;
+        }
+
+        // Le nom et la position du rôle @everyone sont fixes
+        let before = role.clone();
+        let name = match dto.name {
+            Some(_) if role.is_default => {
+                return Err(AppError::ValidationError(
+                    "Le rôle @everyone ne peut pas être renommé".to_string(),
+                ));
+            }
+            Some(name) => validate_role_name(&name)?,
+            None => role.name,
+        };
+
+        let position = match dto.position {
+            Some(_) if role.is_default => {
+                return Err(AppError::ValidationError(
+                    "Le rôle @everyone ne peut pas être déplacé".to_string(),
+                ));
+            }
+            Some(position) => {
+                self.check_position(server_id, position, user_id).await?;
+                position
+            }
+            None => role.position,
+        };
+
+        let permissions = match dto.permissions {
+            Some(permissions) => {
+                let permissions = permissions & Permissions::all();
+                self.check_can_grant(granted, permissions)?;
+                permissions
+            }
+            None => role.permissions,
+        };
+
+        let updated = self
+            .role_repo
+            .update(role_id, &name, permissions, position)
+            .await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::RoleUpdate, Some(role_id))
+                    .before(&before)
+                    .after(&updated),
+            )
+            .await;
+
+        Ok(updated)
+    }
+
+    /// Supprimer un rôle (MANAGE_ROLES, rôles inférieurs uniquement)
+    pub async fn delete_role(&self, server_id: i32, role_id: i32, user_id: i32) -> AppResult<()> {
+        self.check_can_manage_roles(server_id, user_id).await?;
+        let role = self.get_role(server_id, role_id).await?;
+
+        if role.is_default {
+            return Err(AppError::ValidationError(
+                "Le rôle @everyone ne peut pas être supprimé".to_string(),
+            ));
+        }
+
+        self.check_position(server_id, role.position, user_id).await?;
+
+        self.role_repo.delete(role_id).await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::RoleDelete, Some(role_id))
+                    .before(&role),
+            )
+            .await;
+
+        Ok(())
+    }
+
+    /// Définir les rôles d'un membre (MANAGE_ROLES)
+    pub async fn set_member_roles(
+        &self,
+        server_id: i32,
+        target_user_id: i32,
+        role_ids: Vec<i32>,
+        requester_id: i32,
+    ) -> AppResult<Vec<Role>> {
+        self.check_can_manage_roles(server_id, requester_id).await?;
+
+        if !self.server_repo.is_member(server_id, target_user_id).await? {
+            return Err(AppError::UserNotFound);
+        }
+
+        // On ne modifie pas les rôles d'un membre de rang égal ou supérieur
+        if target_user_id != requester_id {
+            self.permission_service
+                .check_outranks(server_id, requester_id, target_user_id)
+                .await?;
+        }
+
+        let mut role_ids = role_ids;
+        role_ids.sort_unstable();
+        role_ids.dedup();
+
+        // Chaque rôle ajouté ou retiré doit être inférieur au rôle le plus élevé du demandeur
+        let current_roles = self.role_repo.get_member_roles(server_id, target_user_id).await?;
+        for role_id in &role_ids {
+            let role = self.get_role(server_id, *role_id).await?;
+            if role.is_default {
+                return Err(AppError::ValidationError(
+                    "Le rôle @everyone est attribué implicitement".to_string(),
+                ));
+            }
+            if !current_roles.iter().any(|current| current.id == role.id) {
+                self.check_position(server_id, role.position, requester_id).await?;
+            }
+        }
+        for role in current_roles.iter().filter(|role| !role_ids.contains(&role.id)) {
+            self.check_position(server_id, role.position, requester_id).await?;
+        }
+
+        self.role_repo
+            .set_member_roles(server_id, target_user_id, &role_ids)
+            .await?;
+
+        let roles = self.role_repo.get_member_roles(server_id, target_user_id).await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, requester_id, AuditAction::MemberRolesUpdate, Some(target_user_id))
+                    .before(current_roles.iter().map(|role| role.id).collect::<Vec<_>>())
+                    .after(&role_ids),
+            )
+            .await;
+
+        Ok(roles)
+    }
+
+    /// Vérifier la permission MANAGE_ROLES et retourner les permissions du demandeur
+    async fn check_can_manage_roles(&self, server_id: i32, user_id: i32) -> AppResult<Permissions> {
+        let granted = self.permission_service.compute(user_id, server_id, None).await?;
+        self.check_can_grant(granted, Permissions::MANAGE_ROLES)?;
+        Ok(granted)
+    }
+
+    /// Un membre ne peut pas accorder des permissions qu'il ne possède pas
+    fn check_can_grant(&self, granted: Permissions, permissions: Permissions) -> AppResult<()> {
+        if !granted.contains(permissions) {
+            let missing = permissions & !granted;
+            return Err(AppError::MissingPermission(missing.names().join(", ")));
+        }
+        Ok(())
+    }
+
+    /// Vérifier qu'une position est strictement inférieure au rôle le plus élevé du demandeur
+    async fn check_position(&self, server_id: i32, position: i32, user_id: i32) -> AppResult<()> {
+        if position < 1 {
+            return Err(AppError::ValidationError(
+                "La position d'un rôle doit être supérieure à celle de @everyone".to_string(),
+            ));
+        }
+
+        let highest = self.permission_service.highest_position(server_id, user_id).await?;
+        if position >= highest {
+            return Err(AppError::RoleHierarchy);
+        }
+
+        Ok(())
+    }
+
+    async fn get_role(&self, server_id: i32, role_id: i32) -> AppResult<Role> {
+        self.role_repo
+            .find_by_id(role_id)
+            .await?
+            .filter(|role| role.server_id == server_id)
+            .ok_or(AppError::RoleNotFound)
+    }
+}
+
+/// Valider le nom d'un rôle
+fn validate_role_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+
+    if name.is_empty() || name.len() > 100 {
+        return Err(AppError::ValidationError(
+            "Le nom du rôle doit contenir entre 1 et 100 caractères".to_string(),
+        ));
+    }
+    if name.eq_ignore_ascii_case("@everyone") {
+        return Err(AppError::ValidationError(
+            "Le nom @everyone est réservé".to_string(),
+        ));
+    }
+
+    Ok(name.to_string())
+}
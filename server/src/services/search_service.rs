@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use crate::errors::{AppError, AppResult};
+use crate::models::{MessageSearch, Permissions, SearchHit, SearchQuery, SearchResults};
+use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
+use crate::repositories::message_repository::{MessageRepository, MessageRepositoryTrait};
+use crate::repositories::user_repository::{UserRepository, UserRepositoryTrait};
+use crate::services::PermissionService;
+use crate::utils::{parse_search_query, ParsedSearch};
+
+/// Nombre de messages de contexte avant et après chaque résultat
+const CONTEXT_SIZE: i64 = 2;
+
+/// Service gérant la recherche dans les messages d'un serveur
+#[derive(Clone)]
+pub struct SearchService {
+    message_repo: Arc<MessageRepository>,
+    channel_repo: Arc<ChannelRepository>,
+    user_repo: Arc<UserRepository>,
+    permission_service: Arc<PermissionService>,
+}
+
+impl SearchService {
+    pub fn new(
+        message_repo: Arc<MessageRepository>,
+        channel_repo: Arc<ChannelRepository>,
+        user_repo: Arc<UserRepository>,
+        permission_service: Arc<PermissionService>,
+    ) -> Self {
+        Self {
+            message_repo,
+            channel_repo,
+            user_repo,
+            permission_service,
+        }
+    }
+
+    /// Rechercher dans les canaux (et leurs fils) dont l'utilisateur peut lire l'historique
+    pub async fn search_messages(
+        &self,
+        server_id: i32,
+        query: SearchQuery,
+        user_id: i32,
+    ) -> AppResult<SearchResults> {
+        let parsed = parse_search_query(&query.q)?;
+        if parsed.is_empty() {
+            return Err(AppError::ValidationError("La recherche est vide".to_string()));
+        }
+
+        // Vérifie aussi que l'utilisateur est membre du serveur
+        let channels = self.channel_repo.find_by_server(server_id).await?;
+        let mut channels = self
+            .permission_service
+            .filter_channels(
+                user_id,
+                server_id,
+                channels,
+                Permissions::VIEW_CHANNELS | Permissions::READ_MESSAGE_HISTORY,
+            )
+            .await?;
+
+        if !parsed.channels.is_empty() {
+            channels.retain(|channel| {
+                parsed.channels.iter().any(|name| {
+                    name.eq_ignore_ascii_case(&channel.name) || *name == channel.id.to_string()
+                })
+            });
+        }
+
+        let author_ids = self.resolve_authors(&parsed.from).await?;
+        let (since, until) = date_range(&parsed);
+
+        // Canal ou auteur introuvable, période vide : aucun résultat possible
+        let impossible = matches!((since, until), (Some(since), Some(until)) if since >= until);
+        if channels.is_empty() || author_ids.as_ref().is_some_and(Vec::is_empty) || impossible {
+            return Ok(SearchResults {
+                total: 0,
+                hits: Vec::new(),
+                next_cursor: None,
+            });
+        }
+
+        let search = MessageSearch {
+            channel_ids: channels.iter().map(|channel| channel.id).collect(),
+            text: parsed.text,
+            author_ids,
+            since,
+            until,
+            has: parsed.has,
+        };
+
+        let limit = query.limit.unwrap_or(25).clamp(1, 50);
+        let mut messages = self
+            .message_repo
+            .search(&search, user_id, query.before, limit + 1)
+            .await?;
+
+        let next_cursor = if messages.len() as i64 > limit {
+            messages.truncate(limit as usize);
+            messages.last().map(|message| message.id)
+        } else {
+            None
+        };
+
+        let total = self.message_repo.count_search(&search).await?;
+
+        let ids: Vec<i32> = messages.iter().map(|message| message.id).collect();
+        let context = if ids.is_empty() {
+            Vec::new()
+        } else {
+            self.message_repo.find_context(&ids, user_id, CONTEXT_SIZE).await?
+        };
+
+        let hits = messages
+            .into_iter()
+            .map(|message| {
+                // Le contexte est trié par id croissant
+                let conversation: Vec<_> = context
+                    .iter()
+                    .filter(|other| {
+                        other.channel_id == message.channel_id && other.thread_id == message.thread_id
+                    })
+                    .cloned()
+                    .collect();
+                let before_end = conversation.partition_point(|other| other.id < message.id);
+                let after_start = conversation.partition_point(|other| other.id <= message.id);
+                let size = CONTEXT_SIZE as usize;
+
+                let context_before = conversation[before_end.saturating_sub(size)..before_end].to_vec();
+                let context_after = conversation[after_start..].iter().take(size).cloned().collect();
+
+                SearchHit {
+                    message,
+                    context_before,
+                    context_after,
+                }
+            })
+            .collect();
+
+        Ok(SearchResults {
+            total,
+            hits,
+            next_cursor,
+        })
+    }
+
+    /// Identifiants des auteurs de `from:` (`None` sans filtre, les inconnus sont ignorés)
+    async fn resolve_authors(&self, names: &[String]) -> AppResult<Option<Vec<i32>>> {
+        if names.is_empty() {
+            return Ok(None);
+        }
+
+        let mut author_ids = Vec::with_capacity(names.len());
+        for name in names {
+            let mention = name
+                .strip_prefix("<@")
+                .and_then(|rest| rest.strip_suffix('>'))
+                .and_then(|id| id.parse::<i32>().ok());
+
+            let author_id = match mention {
+                Some(id) => Some(id),
+                None => self.user_repo.find_by_username(name).await?.map(|user| user.id),
+            };
+            author_ids.extend(author_id);
+        }
+
+        Ok(Some(author_ids))
+    }
+}
+
+/// Bornes de la période recherchée, en UTC
+fn date_range(parsed: &ParsedSearch) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let next_day = |date: NaiveDate| date.checked_add_days(Days::new(1));
+
+    let since = [parsed.after.and_then(next_day), parsed.during].into_iter().flatten().max();
+    let until = [parsed.before, parsed.during.and_then(next_day)].into_iter().flatten().min();
+
+    let start_of_day = |date: NaiveDate| date.and_hms_opt(0, 0, 0).map(|time| time.and_utc());
+    (since.and_then(start_of_day), until.and_then(start_of_day))
+}
@@ -1,18 +1,48 @@
 use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
+use tracing::info;
 use crate::errors::{AppError, AppResult};
-use crate::models::{Server, ServerMemberDetails, UserRole, CreateServerDto};
+use crate::models::{
+    AuditAction, Server, ServerBan, ServerMemberDetails, CreateServerDto, BanMemberDto, NewAuditEntry,
+    InvitationUse, OwnedServersAction, Permissions,
+};
 use crate::repositories::server_repository::{ServerRepository, ServerRepositoryTrait};
+use crate::repositories::role_repository::{RoleRepository, RoleRepositoryTrait};
+use crate::repositories::invitation_repository::{InvitationRepository, InvitationRepositoryTrait};
+use crate::services::{AuditService, PermissionService};
 use crate::utils::generate_invitation_code;
 
+/// Durée maximale d'une exclusion temporaire
+const MAX_TIMEOUT_DAYS: i64 = 28;
+
+/// Longueur maximale du motif d'une sanction
+const MAX_REASON_LENGTH: usize = 512;
+
 /// Service gérant les serveurs
 #[derive(Clone)]
 pub struct ServerService {
     server_repo: Arc<ServerRepository>,
+    role_repo: Arc<RoleRepository>,
+    invitation_repo: Arc<InvitationRepository>,
+    permission_service: Arc<PermissionService>,
+    audit_service: Arc<AuditService>,
 }
 
 impl ServerService {
-    pub fn new(server_repo: Arc<ServerRepository>) -> Self {
-        Self { server_repo }
+    pub fn new(
+        server_repo: Arc<ServerRepository>,
+        role_repo: Arc<RoleRepository>,
+        invitation_repo: Arc<InvitationRepository>,
+        permission_service: Arc<PermissionService>,
+        audit_service: Arc<AuditService>,
+    ) -> Self {
+        Self {
+            server_repo,
+            role_repo,
+            invitation_repo,
+            permission_service,
+            audit_service,
+        }
     }
 
     /// Créer un nouveau serveur
@@ -26,9 +56,17 @@ impl ServerService {
             .create(&dto.name, owner_id, &invitation_code)
             .await?;
 
-        // Ajouter le créateur comme Owner
-        self.server_repo
-            .add_member(server.id, owner_id, UserRole::Owner)
+        // Ajouter le créateur comme membre (la propriété découle de owner_id)
+        self.server_repo.add_member(server.id, owner_id).await?;
+
+        // Créer le rôle @everyone par défaut
+        self.role_repo
+            .create(server.id, "@everyone", Permissions::DEFAULT, 0, true)
+            .await?;
+
+        // Le code du serveur est sa première invitation, permanente et révocable
+        self.invitation_repo
+            .create(server.id, &invitation_code, owner_id, None, None, false)
             .await?;
 
         Ok(server)
@@ -55,10 +93,23 @@ impl ServerService {
 
     /// Mettre à jour un serveur
     pub async fn update_server(&self, server_id: i32, name: String, user_id: i32) -> AppResult<Server> {
-        // Vérifier les permissions (Admin ou Owner)
-        self.check_is_admin_or_owner(server_id, user_id).await?;
+        // Vérifier les permissions
+        self.permission_service
+            .check(user_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
 
-        self.server_repo.update(server_id, &name).await
+        let before = self.server_repo.find_by_id(server_id).await?;
+        let server = self.server_repo.update(server_id, &name).await?;
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, user_id, AuditAction::ServerUpdate, Some(server_id))
+                    .before(before)
+                    .after(&server),
+            )
+            .await;
+
+        Ok(server)
     }
 
     /// Supprimer un serveur
@@ -71,23 +122,34 @@ impl ServerService {
 
     /// Rejoindre un serveur via code d'invitation
     pub async fn join_server(&self, invitation_code: String, user_id: i32) -> AppResult<Server> {
+        let invitation = self
+            .invitation_repo
+            .find_by_code(invitation_code.trim())
+            .await?
+            .ok_or(AppError::InvalidInvitationCode)?;
+
         let server = self
             .server_repo
-            .find_by_invitation_code(&invitation_code)
+            .find_by_id(invitation.server_id)
             .await?
-            .ok_or(AppError::InvalidInvitationCode)?;
+            .ok_or(AppError::ServerNotFound)?;
 
         // Vérifier si l'utilisateur est déjà membre
         if self.server_repo.is_member(server.id, user_id).await? {
             return Err(AppError::AlreadyMember);
         }
 
-        // Ajouter comme membre
-        self.server_repo
-            .add_member(server.id, user_id, UserRole::Member)
-            .await?;
+        // Refuser les utilisateurs bannis (bannissement non expiré)
+        if self.server_repo.find_active_ban(server.id, user_id).await?.is_some() {
+            return Err(AppError::Banned);
+        }
 
-        Ok(server)
+        // Consommer l'invitation et ajouter le membre atomiquement
+        match self.invitation_repo.consume(invitation.id, user_id).await? {
+            InvitationUse::Joined => Ok(server),
+            InvitationUse::AlreadyMember => Err(AppError::AlreadyMember),
+            InvitationUse::Unavailable => Err(AppError::InvitationExpired),
+        }
     }
 
     /// Quitter un serveur
@@ -96,17 +158,93 @@ impl ServerService {
         self.check_is_member(server_id, user_id).await?;
 
         // Vérifier que ce n'est pas le propriétaire
-        let role = self
+        if self.permission_service.is_owner(server_id, user_id).await? {
+            return Err(AppError::OwnerCannotLeave);
+        }
+
+        self.server_repo.remove_member(server_id, user_id).await
+    }
+
+    /// Transmettre la propriété d'un serveur à un membre permanent (propriétaire uniquement)
+    ///
+    /// Le mot de passe du propriétaire est vérifié en amont par l'appelant.
+    pub async fn transfer_ownership(
+        &self,
+        server_id: i32,
+        new_owner_id: i32,
+        requester_id: i32,
+    ) -> AppResult<Server> {
+        self.check_is_owner(server_id, requester_id).await?;
+
+        if new_owner_id == requester_id {
+            return Err(AppError::ValidationError(
+                "Vous êtes déjà propriétaire de ce serveur".to_string(),
+            ));
+        }
+
+        let member = self
             .server_repo
-            .get_member_role(server_id, user_id)
+            .find_member(server_id, new_owner_id)
             .await?
-            .ok_or(AppError::ServerNotFound)?;
+            .ok_or(AppError::UserNotFound)?;
 
-        if role == UserRole::Owner {
-            return Err(AppError::OwnerCannotLeave);
+        // Un membre temporaire est retiré à sa déconnexion : le serveur resterait sans propriétaire
+        if member.is_temporary {
+            return Err(AppError::ValidationError(
+                "La propriété ne peut pas être transmise à un membre temporaire".to_string(),
+            ));
         }
 
-        self.server_repo.remove_member(server_id, user_id).await
+        self.change_owner(server_id, requester_id, new_owner_id).await
+    }
+
+    /// Serveurs dont l'utilisateur est propriétaire
+    pub async fn get_owned_servers(&self, user_id: i32) -> AppResult<Vec<Server>> {
+        self.server_repo.find_owned(user_id).await
+    }
+
+    /// Se défaire d'un serveur avant la suppression du compte de son propriétaire
+    ///
+    /// Le serveur est transmis au membre permanent le plus haut placé, ou supprimé s'il
+    /// n'en a pas (ou si `action` le demande). Retourne le serveur transmis.
+    pub async fn release_server(
+        &self,
+        server_id: i32,
+        owner_id: i32,
+        action: OwnedServersAction,
+    ) -> AppResult<Option<Server>> {
+        let successor = match action {
+            OwnedServersAction::Transfer => self.server_repo.find_successor(server_id, owner_id).await?,
+            OwnedServersAction::Delete => None,
+        };
+
+        let Some(new_owner_id) = successor else {
+            self.server_repo.delete(server_id).await?;
+            info!("Serveur {} supprimé avec le compte de son propriétaire {}", server_id, owner_id);
+            return Ok(None);
+        };
+
+        self.change_owner(server_id, owner_id, new_owner_id).await.map(Some)
+    }
+
+    /// Changer de propriétaire (rôles échangés) et l'inscrire au journal d'audit
+    async fn change_owner(&self, server_id: i32, owner_id: i32, new_owner_id: i32) -> AppResult<Server> {
+        let server = self
+            .server_repo
+            .transfer_ownership(server_id, owner_id, new_owner_id)
+            .await?
+            .ok_or(AppError::OwnerOnly)?;
+        info!("Serveur {} transmis de {} à {}", server_id, owner_id, new_owner_id);
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, owner_id, AuditAction::OwnershipTransfer, Some(new_owner_id))
+                    .before(serde_json::json!({ "owner_id": owner_id }))
+                    .after(serde_json::json!({ "owner_id": new_owner_id })),
+            )
+            .await;
+
+        Ok(server)
     }
 
     /// Récupérer les membres d'un serveur
@@ -117,74 +255,262 @@ impl ServerService {
         self.server_repo.get_members(server_id).await
     }
 
-    /// Mettre à jour le rôle d'un membre
-    pub async fn update_member_role(
+    /// Expulser un membre (KICK_MEMBERS) ; il pourra revenir avec une invitation
+    pub async fn kick_member(
         &self,
         server_id: i32,
         target_user_id: i32,
-        new_role: UserRole,
+        reason: Option<String>,
         requester_id: i32,
-    ) -> AppResult<()> {
-        // Seul le propriétaire peut changer les rôles
-        self.check_is_owner(server_id, requester_id).await?;
+    ) -> AppResult<ServerMemberDetails> {
+        let member = self
+            .check_can_moderate(server_id, target_user_id, requester_id, Permissions::KICK_MEMBERS)
+            .await?;
+        let reason = validate_reason(reason)?;
+
+        self.server_repo.remove_member(server_id, target_user_id).await?;
+
+        info!(
+            "Membre {} expulsé du serveur {} par {} (motif : {})",
+            target_user_id,
+            server_id,
+            requester_id,
+            reason.as_deref().unwrap_or("aucun")
+        );
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, requester_id, AuditAction::MemberKick, Some(target_user_id))
+                    .before(&member)
+                    .reason(reason),
+            )
+            .await;
+
+        Ok(member)
+    }
+
+    /// Bannir un utilisateur (BAN_MEMBERS), membre ou non du serveur
+    pub async fn ban_member(
+        &self,
+        server_id: i32,
+        target_user_id: i32,
+        dto: BanMemberDto,
+        requester_id: i32,
+    ) -> AppResult<ServerBan> {
+        self.permission_service
+            .check(requester_id, server_id, None, Permissions::BAN_MEMBERS)
+            .await?;
+
+        if target_user_id == requester_id {
+            return Err(AppError::ValidationError(
+                "Vous ne pouvez pas vous sanctionner vous-même".to_string(),
+            ));
+        }
 
-        // Ne pas permettre de changer le rôle du propriétaire
-        let target_role = self
+        self.permission_service
+            .check_outranks(server_id, requester_id, target_user_id)
+            .await?;
+
+        if matches!(dto.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+            return Err(AppError::ValidationError(
+                "La date d'expiration doit être dans le futur".to_string(),
+            ));
+        }
+        let reason = validate_reason(dto.reason)?;
+
+        let ban = self
             .server_repo
-            .get_member_role(server_id, target_user_id)
+            .ban_member(server_id, target_user_id, reason.as_deref(), requester_id, dto.expires_at)
             .await?
             .ok_or(AppError::UserNotFound)?;
 
-        if target_role == UserRole::Owner {
-            return Err(AppError::Forbidden);
+        info!(
+            "Utilisateur {} banni du serveur {} par {} (motif : {})",
+            target_user_id,
+            server_id,
+            requester_id,
+            ban.reason.as_deref().unwrap_or("aucun")
+        );
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, requester_id, AuditAction::MemberBan, Some(target_user_id))
+                    .after(&ban)
+                    .reason(ban.reason.clone()),
+            )
+            .await;
+
+        Ok(ban)
+    }
+
+    /// Lever un bannissement (BAN_MEMBERS)
+    pub async fn unban_member(&self, server_id: i32, target_user_id: i32, requester_id: i32) -> AppResult<()> {
+        self.permission_service
+            .check(requester_id, server_id, None, Permissions::BAN_MEMBERS)
+            .await?;
+
+        let before = self.server_repo.find_active_ban(server_id, target_user_id).await?;
+
+        if !self.server_repo.delete_ban(server_id, target_user_id).await? {
+            return Err(AppError::BanNotFound);
         }
 
-        self.server_repo
-            .update_member_role(server_id, target_user_id, new_role)
-            .await
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, requester_id, AuditAction::MemberUnban, Some(target_user_id))
+                    .before(before),
+            )
+            .await;
+
+        Ok(())
     }
 
-    /// Vérifier si un utilisateur est membre d'un serveur
-    async fn check_is_member(&self, server_id: i32, user_id: i32) -> AppResult<()> {
-        if !self.server_repo.is_member(server_id, user_id).await? {
-            return Err(AppError::Forbidden);
+    /// Lister les bannissements en cours (BAN_MEMBERS)
+    pub async fn get_bans(&self, server_id: i32, requester_id: i32) -> AppResult<Vec<ServerBan>> {
+        self.permission_service
+            .check(requester_id, server_id, None, Permissions::BAN_MEMBERS)
+            .await?;
+
+        self.server_repo.find_bans(server_id).await
+    }
+
+    /// Exclure temporairement un membre (KICK_MEMBERS) ; `None` lève l'exclusion
+    pub async fn timeout_member(
+        &self,
+        server_id: i32,
+        target_user_id: i32,
+        until: Option<DateTime<Utc>>,
+        reason: Option<String>,
+        requester_id: i32,
+    ) -> AppResult<ServerMemberDetails> {
+        let member = self
+            .check_can_moderate(server_id, target_user_id, requester_id, Permissions::KICK_MEMBERS)
+            .await?;
+        let reason = validate_reason(reason)?;
+
+        if let Some(until) = until {
+            let now = Utc::now();
+            if until <= now {
+                return Err(AppError::ValidationError(
+                    "La fin de l'exclusion doit être dans le futur".to_string(),
+                ));
+            }
+            if until > now + Duration::days(MAX_TIMEOUT_DAYS) {
+                return Err(AppError::ValidationError(format!(
+                    "Une exclusion temporaire ne peut pas dépasser {} jours",
+                    MAX_TIMEOUT_DAYS
+                )));
+            }
         }
-        Ok(())
+
+        self.server_repo
+            .set_timeout(server_id, target_user_id, until)
+            .await?;
+
+        match until {
+            Some(until) => info!(
+                "Membre {} exclu du serveur {} jusqu'au {} par {} (motif : {})",
+                target_user_id,
+                server_id,
+                until.to_rfc3339(),
+                requester_id,
+                reason.as_deref().unwrap_or("aucun")
+            ),
+            None => info!(
+                "Exclusion du membre {} levée sur le serveur {} par {}",
+                target_user_id, server_id, requester_id
+            ),
+        }
+
+        self.audit_service
+            .record(
+                NewAuditEntry::new(server_id, requester_id, AuditAction::MemberTimeout, Some(target_user_id))
+                    .before(serde_json::json!({ "timeout_until": member.timeout_until }))
+                    .after(serde_json::json!({ "timeout_until": until }))
+                    .reason(reason),
+            )
+            .await;
+
+        self.server_repo
+            .find_member(server_id, target_user_id)
+            .await?
+            .ok_or(AppError::UserNotFound)
     }
 
-    /// Vérifier si un utilisateur est admin ou owner
-    async fn check_is_admin_or_owner(&self, server_id: i32, user_id: i32) -> AppResult<()> {
-        let role = self
+    /// Vérifier qu'un modérateur peut sanctionner un membre et retourner ce membre
+    async fn check_can_moderate(
+        &self,
+        server_id: i32,
+        target_user_id: i32,
+        requester_id: i32,
+        permission: Permissions,
+    ) -> AppResult<ServerMemberDetails> {
+        self.permission_service
+            .check(requester_id, server_id, None, permission)
+            .await?;
+
+        if target_user_id == requester_id {
+            return Err(AppError::ValidationError(
+                "Vous ne pouvez pas vous sanctionner vous-même".to_string(),
+            ));
+        }
+
+        let member = self
             .server_repo
-            .get_member_role(server_id, user_id)
+            .find_member(server_id, target_user_id)
             .await?
-            .ok_or(AppError::Forbidden)?;
+            .ok_or(AppError::UserNotFound)?;
+
+        // Le propriétaire et les rangs supérieurs sont intouchables
+        self.permission_service
+            .check_outranks(server_id, requester_id, target_user_id)
+            .await?;
+
+        Ok(member)
+    }
+
+    /// Retirer un utilisateur déconnecté des serveurs rejoints par invitation temporaire
+    ///
+    /// Retourne les serveurs quittés.
+    pub async fn remove_temporary_memberships(&self, user_id: i32) -> AppResult<Vec<i32>> {
+        let server_ids = self.server_repo.remove_temporary_memberships(user_id).await?;
 
-        match role {
-            UserRole::Owner | UserRole::Admin => Ok(()),
-            UserRole::Member => Err(AppError::AdminOnly),
+        for server_id in &server_ids {
+            info!("Membre temporaire {} retiré du serveur {}", user_id, server_id);
         }
+
+        Ok(server_ids)
+    }
+
+    /// Vérifier si un utilisateur est membre d'un serveur (utilisé par d'autres services)
+    pub async fn check_is_member(&self, server_id: i32, user_id: i32) -> AppResult<()> {
+        if !self.server_repo.is_member(server_id, user_id).await? {
+            return Err(AppError::Forbidden);
+        }
+        Ok(())
     }
 
     /// Vérifier si un utilisateur est owner
     async fn check_is_owner(&self, server_id: i32, user_id: i32) -> AppResult<()> {
-        let role = self
-            .server_repo
-            .get_member_role(server_id, user_id)
-            .await?
-            .ok_or(AppError::Forbidden)?;
-
-        match role {
-            UserRole::Owner => Ok(()),
-            _ => Err(AppError::OwnerOnly),
+        if !self.permission_service.is_owner(server_id, user_id).await? {
+            return Err(AppError::OwnerOnly);
         }
+        Ok(())
     }
+}
 
-    /// Récupérer le rôle d'un utilisateur dans un serveur (utilisé par d'autres services)
-    pub async fn get_user_role(&self, server_id: i32, user_id: i32) -> AppResult<UserRole> {
-        self.server_repo
-            .get_member_role(server_id, user_id)
-            .await?
-            .ok_or(AppError::Forbidden)
+/// Nettoyer le motif d'une sanction (vide = aucun motif)
+fn validate_reason(reason: Option<String>) -> AppResult<Option<String>> {
+    let reason = reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+
+    if matches!(&reason, Some(reason) if reason.chars().count() > MAX_REASON_LENGTH) {
+        return Err(AppError::ValidationError(format!(
+            "Le motif ne peut pas dépasser {} caractères",
+            MAX_REASON_LENGTH
+        )));
     }
+
+    Ok(reason)
 }
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+use crate::errors::{AppError, AppResult};
+use crate::models::{Channel, CreateThreadDto, Permissions, Thread, ThreadMember};
+use crate::repositories::channel_repository::{ChannelRepository, ChannelRepositoryTrait};
+use crate::repositories::message_repository::{MessageRepository, MessageRepositoryTrait};
+use crate::repositories::thread_repository::{ThreadRepository, ThreadRepositoryTrait};
+use crate::services::PermissionService;
+
+/// Longueur maximale du nom d'un fil
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Nom d'un fil ouvert sur un message sans texte (pièces jointes uniquement)
+const DEFAULT_THREAD_NAME: &str = "Nouveau fil";
+
+/// Service gérant les fils de discussion
+#[derive(Clone)]
+pub struct ThreadService {
+    thread_repo: Arc<ThreadRepository>,
+    message_repo: Arc<MessageRepository>,
+    channel_repo: Arc<ChannelRepository>,
+    permission_service: Arc<PermissionService>,
+}
+
+impl ThreadService {
+    pub fn new(
+        thread_repo: Arc<ThreadRepository>,
+        message_repo: Arc<MessageRepository>,
+        channel_repo: Arc<ChannelRepository>,
+        permission_service: Arc<PermissionService>,
+    ) -> Self {
+        Self {
+            thread_repo,
+            message_repo,
+            channel_repo,
+            permission_service,
+        }
+    }
+
+    /// Ouvrir un fil à partir d'un message du canal
+    pub async fn create_thread(
+        &self,
+        message_id: i32,
+        dto: CreateThreadDto,
+        user_id: i32,
+    ) -> AppResult<Thread> {
+        let message = self
+            .message_repo
+            .find_by_id(message_id)
+            .await?
+            .filter(|message| !message.is_deleted)
+            .ok_or(AppError::MessageNotFound)?;
+
+        let channel = self.find_channel(message.channel_id).await?;
+
+        self.permission_service
+            .check(
+                user_id,
+                channel.server_id,
+                Some(channel.id),
+                Permissions::VIEW_CHANNELS | Permissions::SEND_MESSAGES,
+            )
+            .await?;
+
+        self.permission_service
+            .check_not_timed_out(channel.server_id, user_id)
+            .await?;
+
+        // Pas de fil dans un fil
+        if message.thread_id.is_some() {
+            return Err(AppError::ValidationError(
+                "Impossible d'ouvrir un fil à partir d'un message de fil".to_string(),
+            ));
+        }
+
+        if self.thread_repo.find_by_root_message(message.id).await?.is_some() {
+            return Err(AppError::ThreadAlreadyExists);
+        }
+
+        // Par défaut, le fil reprend le début du message d'origine
+        let name = match dto.name {
+            Some(name) => name.trim().to_string(),
+            None => default_thread_name(&message.content),
+        };
+        if name.is_empty() || name.chars().count() > MAX_THREAD_NAME_LENGTH {
+            return Err(AppError::ValidationError(format!(
+                "Le nom du fil doit contenir entre 1 et {} caractères",
+                MAX_THREAD_NAME_LENGTH
+            )));
+        }
+
+        self.thread_repo
+            .create(channel.id, message.id, message.author_id, &name, user_id)
+            .await
+    }
+
+    /// Lister les fils d'un canal
+    pub async fn get_channel_threads(&self, channel_id: i32, user_id: i32) -> AppResult<Vec<Thread>> {
+        let channel = self.find_channel(channel_id).await?;
+
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::VIEW_CHANNELS)
+            .await?;
+
+        self.thread_repo.find_by_channel(channel.id).await
+    }
+
+    /// Récupérer un fil
+    pub async fn get_thread(&self, thread_id: i32, user_id: i32) -> AppResult<Thread> {
+        self.find_visible(thread_id, user_id).await
+    }
+
+    /// Lister les participants d'un fil
+    pub async fn get_members(&self, thread_id: i32, user_id: i32) -> AppResult<Vec<ThreadMember>> {
+        let thread = self.find_visible(thread_id, user_id).await?;
+        self.thread_repo.get_members(thread.id).await
+    }
+
+    /// Suivre un fil
+    pub async fn join_thread(&self, thread_id: i32, user_id: i32) -> AppResult<()> {
+        let thread = self.find_visible(thread_id, user_id).await?;
+        self.thread_repo.add_member(thread.id, user_id).await
+    }
+
+    /// Ne plus suivre un fil
+    pub async fn leave_thread(&self, thread_id: i32, user_id: i32) -> AppResult<()> {
+        let thread = self
+            .thread_repo
+            .find_by_id(thread_id)
+            .await?
+            .ok_or(AppError::ThreadNotFound)?;
+
+        self.thread_repo.remove_member(thread.id, user_id).await
+    }
+
+    /// Récupérer un fil dont l'utilisateur peut voir le canal
+    async fn find_visible(&self, thread_id: i32, user_id: i32) -> AppResult<Thread> {
+        let thread = self
+            .thread_repo
+            .find_by_id(thread_id)
+            .await?
+            .ok_or(AppError::ThreadNotFound)?;
+
+        let channel = self.find_channel(thread.channel_id).await?;
+
+        self.permission_service
+            .check(user_id, channel.server_id, Some(channel.id), Permissions::VIEW_CHANNELS)
+            .await?;
+
+        Ok(thread)
+    }
+
+    async fn find_channel(&self, channel_id: i32) -> AppResult<Channel> {
+        self.channel_repo
+            .find_by_id(channel_id)
+            .await?
+            .ok_or(AppError::ChannelNotFound)
+    }
+}
+
+/// Nom par défaut : première ligne du message, tronquée
+fn default_thread_name(content: &str) -> String {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or(DEFAULT_THREAD_NAME)
+        .chars()
+        .take(MAX_THREAD_NAME_LENGTH)
+        .collect()
+}
@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use crate::services::{AuthService, ServerService, ChannelService, MessageService};
+use crate::services::{AuthService, ServerService, ChannelService, MessageService, DmService, PermissionService, RoleService, AuditService, InvitationService, ThreadService, MentionService, ReadStateService, SearchService, AttachmentService};
 
 /// Structure pour contenir tous les services dans un seul State
 #[derive(Clone)]
@@ -8,20 +8,51 @@ pub struct AppState {
     pub server_service: Arc<ServerService>,
     pub channel_service: Arc<ChannelService>,
     pub message_service: Arc<MessageService>,
+    pub dm_service: Arc<DmService>,
+    pub permission_service: Arc<PermissionService>,
+    pub role_service: Arc<RoleService>,
+    pub audit_service: Arc<AuditService>,
+    pub invitation_service: Arc<InvitationService>,
+    pub thread_service: Arc<ThreadService>,
+    pub mention_service: Arc<MentionService>,
+    pub read_state_service: Arc<ReadStateService>,
+    pub search_service: Arc<SearchService>,
+    pub attachment_service: Arc<AttachmentService>,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         auth_service: Arc<AuthService>,
         server_service: Arc<ServerService>,
         channel_service: Arc<ChannelService>,
         message_service: Arc<MessageService>,
+        dm_service: Arc<DmService>,
+        permission_service: Arc<PermissionService>,
+        role_service: Arc<RoleService>,
+        audit_service: Arc<AuditService>,
+        invitation_service: Arc<InvitationService>,
+        thread_service: Arc<ThreadService>,
+        mention_service: Arc<MentionService>,
+        read_state_service: Arc<ReadStateService>,
+        search_service: Arc<SearchService>,
+        attachment_service: Arc<AttachmentService>,
     ) -> Self {
         Self {
             auth_service,
             server_service,
             channel_service,
             message_service,
+            dm_service,
+            permission_service,
+            role_service,
+            audit_service,
+            invitation_service,
+            thread_service,
+            mention_service,
+            read_state_service,
+            search_service,
+            attachment_service,
         }
     }
 }
@@ -0,0 +1,59 @@
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use async_trait::async_trait;
+use tokio::fs;
+use crate::errors::{AppError, AppResult};
+use crate::storage::Storage;
+
+/// Stockage sur le disque local, sous un répertoire racine
+#[derive(Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Chemin d'un fichier, en refusant toute clé qui sortirait de la racine
+    fn path(&self, key: &str) -> AppResult<PathBuf> {
+        let relative = Path::new(key);
+        if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(AppError::StorageError(format!("Clé de stockage invalide: {}", key)));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> AppResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(storage_error)?;
+        }
+
+        fs::write(&path, data).await.map_err(storage_error)
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Vec<u8>> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(data),
+            Err(error) if error.kind() == ErrorKind::NotFound => Err(AppError::AttachmentNotFound),
+            Err(error) => Err(storage_error(error)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(storage_error(error)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn storage_error(error: std::io::Error) -> AppError {
+    AppError::StorageError(error.to_string())
+}
@@ -0,0 +1,22 @@
+// Stockage des fichiers envoyés (pièces jointes et miniatures)
+// L'implémentation est choisie au démarrage : disque local par défaut
+
+pub mod local;
+
+pub use local::LocalStorage;
+
+use async_trait::async_trait;
+use crate::errors::AppResult;
+
+/// Trait définissant un espace de stockage de fichiers
+///
+/// Les clés sont générées par le serveur (`<canal>/<uuid>`) : une implémentation
+/// peut les utiliser telles quelles comme chemins ou noms d'objets.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> AppResult<()>;
+    /// Lire un fichier (`AttachmentNotFound` s'il n'existe pas)
+    async fn get(&self, key: &str) -> AppResult<Vec<u8>>;
+    /// Supprimer un fichier (sans erreur s'il n'existe pas)
+    async fn delete(&self, key: &str) -> AppResult<()>;
+}
@@ -1,6 +1,7 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use crate::errors::{AppError, AppResult};
 
 /// Structure des claims JWT
@@ -8,21 +9,35 @@ use crate::errors::{AppError, AppResult};
 pub struct Claims {
     pub sub: i32, // user_id
     pub username: String,
+    pub sid: i32, // session de connexion (révocable)
+    pub jti: Uuid,
     pub exp: i64, // expiration timestamp
 }
 
-/// Crée un token JWT pour un utilisateur
-pub fn create_token(user_id: i32, username: &str) -> AppResult<String> {
+/// Durée de validité d'un access token (ACCESS_TOKEN_MINUTES, 15 minutes par défaut)
+pub fn access_token_lifetime() -> Duration {
+    let minutes = std::env::var("ACCESS_TOKEN_MINUTES")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(15);
+    Duration::minutes(minutes)
+}
+
+/// Crée un access token JWT pour une session d'un utilisateur
+pub fn create_token(user_id: i32, username: &str, session_id: i32) -> AppResult<String> {
     let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_change_me".to_string());
     
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
+        .checked_add_signed(access_token_lifetime())
         .expect("valid timestamp")
         .timestamp();
 
     let claims = Claims {
         sub: user_id,
         username: username.to_string(),
+        sid: session_id,
+        jti: Uuid::new_v4(),
         exp: expiration,
     };
 
@@ -35,6 +50,9 @@ pub fn create_token(user_id: i32, username: &str) -> AppResult<String> {
 }
 
 /// Vérifie et décode un token JWT
+///
+/// Ne vérifie que la signature et l'expiration : la session peut avoir été révoquée
+/// depuis (voir `AuthService::check_session`).
 pub fn verify_token(token: &str) -> AppResult<Claims> {
     let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_change_me".to_string());
     
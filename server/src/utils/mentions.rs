@@ -0,0 +1,100 @@
+/// Nombre maximal de mentions d'utilisateurs ou de rôles prises en compte par message
+const MAX_MENTIONS: usize = 50;
+
+/// Mentions trouvées dans le contenu d'un message
+///
+/// Syntaxe : `<@id>` pour un utilisateur, `<@&id>` pour un rôle, `@everyone` et `@here`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedMentions {
+    pub user_ids: Vec<i32>,
+    pub role_ids: Vec<i32>,
+    pub everyone: bool,
+    pub here: bool,
+}
+
+impl ParsedMentions {
+    pub fn is_empty(&self) -> bool {
+        self.user_ids.is_empty() && self.role_ids.is_empty() && !self.everyone && !self.here
+    }
+}
+
+/// Extraire les mentions d'un message (sans doublons)
+pub fn parse_mentions(content: &str) -> ParsedMentions {
+    let mut mentions = ParsedMentions::default();
+
+    for (start, _) in content.match_indices('@') {
+        let rest = &content[start + 1..];
+
+        if start > 0 && content.as_bytes()[start - 1] == b'<' {
+            let (is_role, rest) = match rest.strip_prefix('&') {
+                Some(rest) => (true, rest),
+                None => (false, rest),
+            };
+            let Some(end) = rest.find('>') else { continue };
+            let Ok(id) = rest[..end].parse::<i32>() else { continue };
+
+            let ids = if is_role { &mut mentions.role_ids } else { &mut mentions.user_ids };
+            if !ids.contains(&id) && ids.len() < MAX_MENTIONS {
+                ids.push(id);
+            }
+            continue;
+        }
+
+        // @everyone / @here doivent être des mots entiers
+        let preceded_by_word = content[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if preceded_by_word {
+            continue;
+        }
+        for (keyword, flag) in [("everyone", &mut mentions.everyone), ("here", &mut mentions.here)] {
+            if let Some(after) = rest.strip_prefix(keyword) {
+                if !after.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+                    *flag = true;
+                }
+            }
+        }
+    }
+
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_and_role_mentions_are_distinguished() {
+        let mentions = parse_mentions("salut <@12> et <@&3>, voir aussi <@&12>");
+
+        assert_eq!(mentions.user_ids, vec![12]);
+        assert_eq!(mentions.role_ids, vec![3, 12]);
+        assert!(!mentions.everyone && !mentions.here);
+    }
+
+    #[test]
+    fn duplicates_and_malformed_mentions_are_ignored() {
+        let mentions = parse_mentions("<@5> <@5> <@abc> <@7 <@&> @5 <@&x9>");
+
+        assert_eq!(mentions.user_ids, vec![5]);
+        assert!(mentions.role_ids.is_empty());
+    }
+
+    #[test]
+    fn everyone_and_here_must_be_whole_words() {
+        assert!(parse_mentions("@everyone regardez").everyone);
+        assert!(parse_mentions("(@here)").here);
+        assert!(!parse_mentions("mail@everyone.fr").everyone);
+        assert!(!parse_mentions("@everyones").everyone);
+        assert!(!parse_mentions("@here_bas").here);
+        assert!(parse_mentions("rien à signaler").is_empty());
+    }
+
+    #[test]
+    fn mention_count_is_capped() {
+        let content: String = (0..MAX_MENTIONS as i32 + 10).map(|id| format!("<@{id}> ")).collect();
+
+        assert_eq!(parse_mentions(&content).user_ids.len(), MAX_MENTIONS);
+    }
+}
@@ -1,7 +1,13 @@
 pub mod jwt;
 pub mod password;
 pub mod invitation_code;
+pub mod mentions;
+pub mod search_query;
+pub mod refresh_token;
 
-pub use jwt::{create_token, verify_token, Claims};
+pub use jwt::{access_token_lifetime, create_token, verify_token, Claims};
 pub use password::{hash_password, verify_password};
 pub use invitation_code::generate_invitation_code;
+pub use mentions::parse_mentions;
+pub use search_query::{parse_search_query, ParsedSearch};
+pub use refresh_token::{generate_refresh_token, hash_refresh_token, refresh_token_lifetime};
@@ -0,0 +1,27 @@
+use chrono::Duration;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Durée de validité d'un refresh token (REFRESH_TOKEN_DAYS, 30 jours par défaut)
+///
+/// Repart de zéro à chaque renouvellement : une session utilisée n'expire pas.
+pub fn refresh_token_lifetime() -> Duration {
+    let days = std::env::var("REFRESH_TOKEN_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(30);
+    Duration::days(days)
+}
+
+/// Génère un refresh token aléatoire (256 bits, en hexadécimal)
+pub fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Hash stocké en base : un refresh token volé dans la base reste inutilisable
+pub fn hash_refresh_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,165 @@
+use chrono::NaiveDate;
+use crate::errors::{AppError, AppResult};
+use crate::models::HasFilter;
+
+/// Requête de recherche découpée en texte libre et en filtres
+///
+/// Syntaxe : `from:<nom>` ou `from:<@id>`, `in:<canal>`, `has:link|mention|reaction|reply|file|image`,
+/// `before:`, `after:` et `during:` suivis d'une date `AAAA-MM-JJ`. Le reste est du
+/// texte libre ; les guillemets regroupent une phrase exacte.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedSearch {
+    pub text: Option<String>,
+    pub from: Vec<String>,
+    pub channels: Vec<String>,
+    pub has: Vec<HasFilter>,
+    /// Avant ce jour (exclu)
+    pub before: Option<NaiveDate>,
+    /// Après ce jour (exclu)
+    pub after: Option<NaiveDate>,
+    /// Pendant ce jour
+    pub during: Option<NaiveDate>,
+}
+
+impl ParsedSearch {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_none()
+            && self.from.is_empty()
+            && self.channels.is_empty()
+            && self.has.is_empty()
+            && self.before.is_none()
+            && self.after.is_none()
+            && self.during.is_none()
+    }
+}
+
+/// Analyser une requête de recherche
+pub fn parse_search_query(query: &str) -> AppResult<ParsedSearch> {
+    let mut parsed = ParsedSearch::default();
+    let mut text = Vec::new();
+
+    for token in tokenize(query) {
+        let Some((key, value)) = token.split_once(':').filter(|(_, value)| !value.is_empty()) else {
+            text.push(token);
+            continue;
+        };
+        let value = value.trim_matches('"');
+
+        match key {
+            "from" => push_unique(&mut parsed.from, value.to_string()),
+            "in" => push_unique(&mut parsed.channels, value.trim_start_matches('#').to_string()),
+            "has" => {
+                let filter = HasFilter::parse(value).ok_or_else(|| {
+                    AppError::ValidationError(format!(
+                        "Filtre has:{} inconnu (link, mention, reaction, reply, file ou image)",
+                        value
+                    ))
+                })?;
+                push_unique(&mut parsed.has, filter);
+            }
+            "before" => parsed.before = Some(parse_date(value)?),
+            "after" => parsed.after = Some(parse_date(value)?),
+            "during" => parsed.during = Some(parse_date(value)?),
+            // Pas un filtre : « 10:30 », « http://… »
+            _ => text.push(token),
+        }
+    }
+
+    if !text.is_empty() {
+        parsed.text = Some(text.join(" "));
+    }
+
+    Ok(parsed)
+}
+
+/// Découper sur les espaces, sans couper les passages entre guillemets
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+fn parse_date(value: &str) -> AppResult<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        AppError::ValidationError(format!("Date invalide : {} (format AAAA-MM-JJ)", value))
+    })
+}
+
+fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn filters_are_extracted_from_free_text() {
+        let parsed = parse_search_query("from:alice in:#general has:link has:link déploiement prod").unwrap();
+
+        assert_eq!(parsed.text.as_deref(), Some("déploiement prod"));
+        assert_eq!(parsed.from, vec!["alice"]);
+        assert_eq!(parsed.channels, vec!["general"]);
+        assert_eq!(parsed.has, vec![HasFilter::Link]);
+    }
+
+    #[test]
+    fn quoted_phrases_stay_together() {
+        let parsed = parse_search_query(r#"bug "phrase exacte ici" from:"jean dupont""#).unwrap();
+
+        assert_eq!(parsed.text.as_deref(), Some(r#"bug "phrase exacte ici""#));
+        assert_eq!(parsed.from, vec!["jean dupont"]);
+    }
+
+    #[test]
+    fn dates_are_parsed() {
+        let parsed = parse_search_query("after:2026-01-31 before:2026-02-15 during:2026-02-01").unwrap();
+
+        assert_eq!(parsed.after, Some(date("2026-01-31")));
+        assert_eq!(parsed.before, Some(date("2026-02-15")));
+        assert_eq!(parsed.during, Some(date("2026-02-01")));
+        assert!(parsed.text.is_none());
+    }
+
+    #[test]
+    fn invalid_dates_and_unknown_has_filters_are_rejected() {
+        assert!(matches!(parse_search_query("before:31/01/2026"), Err(AppError::ValidationError(_))));
+        assert!(matches!(parse_search_query("after:2026-02-30"), Err(AppError::ValidationError(_))));
+        assert!(matches!(parse_search_query("has:video"), Err(AppError::ValidationError(_))));
+    }
+
+    #[test]
+    fn unknown_keys_and_empty_values_are_free_text() {
+        let parsed = parse_search_query("réunion à 10:30 http://exemple.fr from:").unwrap();
+
+        assert_eq!(parsed.text.as_deref(), Some("réunion à 10:30 http://exemple.fr from:"));
+        assert!(parsed.from.is_empty());
+        assert!(parse_search_query("   ").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgListener, PgPool};
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+use uuid::Uuid;
+use crate::errors::{AppError, AppResult};
+use crate::ws::hub::{HubUpdate, NodeSnapshot};
+use crate::ws::SocketEvent;
+
+/// Canal Postgres `LISTEN/NOTIFY` partagé par toutes les instances
+const PG_CHANNEL: &str = "chat_events";
+
+/// Taille maximale d'une notification envoyée telle quelle (NOTIFY est limité à 8000 octets)
+const MAX_NOTIFY_PAYLOAD: usize = 7900;
+
+/// Préfixe d'une notification qui ne porte que l'identifiant d'un message stocké en base
+const PAYLOAD_REF_PREFIX: char = '@';
+
+/// Durée de conservation des messages trop gros pour NOTIFY
+const PAYLOAD_RETENTION_MINUTES: i32 = 5;
+
+/// Message publié sur le bus par une instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Instance d'origine
+    pub origin: Uuid,
+    pub message: BusMessage,
+}
+
+/// Contenu d'un message du bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BusMessage {
+    /// Événement à envoyer aux sockets de ces rooms (sauf éventuellement un socket)
+    Emit {
+        rooms: Vec<String>,
+        except_socket: Option<String>,
+        event: SocketEvent,
+    },
+
+    /// Retirer des rooms `rooms` les sockets présents dans `within`
+    Leave {
+        within: Vec<String>,
+        rooms: Vec<String>,
+    },
+
+    /// Fermer les sockets présents dans ces rooms
+    Disconnect { rooms: Vec<String> },
+
+    /// Fermer les sockets et connexions IRC authentifiés avec ces sessions de l'utilisateur ;
+    /// `password_logins` : aussi les connexions IRC ouvertes avec son mot de passe
+    RevokeSessions { user_id: i32, session_ids: Vec<i32>, password_logins: bool },
+
+    /// Réaligner les rooms des canaux d'un serveur sur les canaux visibles
+    SyncChannelRooms { server_id: i32 },
+
+    /// Changement d'état du hub (présence, utilisateurs connectés)
+    Hub { update: HubUpdate },
+
+    /// Une instance demande l'état des autres (démarrage, messages perdus)
+    SnapshotRequest,
+
+    /// État des sockets de l'instance d'origine
+    Snapshot { snapshot: NodeSnapshot },
+
+    /// Signe de vie de l'instance d'origine
+    Heartbeat,
+}
+
+/// Ce que l'instance reçoit du bus
+#[derive(Debug)]
+pub enum BusEvent {
+    Message(Box<Envelope>),
+    /// Des messages ont pu être perdus : l'état des autres instances est à redemander
+    Resync,
+}
+
+/// Réception des messages du bus, y compris ceux publiés par l'instance elle-même
+pub type BusReceiver = mpsc::UnboundedReceiver<BusEvent>;
+
+/// Bus d'événements temps réel entre les instances du serveur
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    /// Publier un message à toutes les instances, celle-ci comprise
+    async fn publish(&self, envelope: &Envelope) -> AppResult<()>;
+}
+
+/// Bus en mémoire, pour un déploiement à une seule instance
+pub struct LocalBus {
+    sender: mpsc::UnboundedSender<BusEvent>,
+}
+
+impl LocalBus {
+    pub fn new() -> (Self, BusReceiver) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl EventBus for LocalBus {
+    async fn publish(&self, envelope: &Envelope) -> AppResult<()> {
+        self.sender
+            .send(BusEvent::Message(Box::new(envelope.clone())))
+            .map_err(|_| AppError::InternalServerError)
+    }
+}
+
+/// Bus Postgres `LISTEN/NOTIFY`, pour plusieurs instances sur la même base
+///
+/// Les messages trop gros pour NOTIFY sont stockés dans `event_bus_payloads` et
+/// notifiés par leur identifiant.
+pub struct PgBus {
+    pool: PgPool,
+}
+
+impl PgBus {
+    /// Écouter le canal et lancer la purge des messages stockés
+    pub async fn connect(pool: PgPool) -> AppResult<(Self, BusReceiver)> {
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(PG_CHANNEL).await?;
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(listen(listener, pool.clone(), sender));
+        tokio::spawn(purge_payloads(pool.clone()));
+
+        Ok((Self { pool }, receiver))
+    }
+}
+
+#[async_trait]
+impl EventBus for PgBus {
+    async fn publish(&self, envelope: &Envelope) -> AppResult<()> {
+        let payload = serde_json::to_string(envelope).map_err(|_| AppError::InternalServerError)?;
+
+        let payload = if payload.len() > MAX_NOTIFY_PAYLOAD {
+            let id: i64 = sqlx::query_scalar(
+                "INSERT INTO event_bus_payloads (payload) VALUES ($1) RETURNING id",
+            )
+            .bind(&payload)
+            .fetch_one(&self.pool)
+            .await?;
+            format!("{}{}", PAYLOAD_REF_PREFIX, id)
+        } else {
+            payload
+        };
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(PG_CHANNEL)
+            .bind(payload)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Transmettre les notifications reçues jusqu'à l'arrêt du récepteur
+async fn listen(mut listener: PgListener, pool: PgPool, sender: mpsc::UnboundedSender<BusEvent>) {
+    loop {
+        let event = match listener.try_recv().await {
+            Ok(Some(notification)) => match decode(&pool, notification.payload()).await {
+                Ok(envelope) => BusEvent::Message(Box::new(envelope)),
+                Err(e) => {
+                    warn!("Message du bus d'événements illisible : {}", e);
+                    continue;
+                }
+            },
+            Ok(None) => {
+                // Reconnexion (et nouvel abonnement) avant de redemander l'état des autres
+                warn!("Connexion LISTEN perdue, reconnexion au bus d'événements");
+                if let Err(e) = sqlx::query("SELECT 1").execute(&mut listener).await {
+                    error!("Reconnexion au bus d'événements impossible : {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                BusEvent::Resync
+            }
+            Err(e) => {
+                error!("Erreur de réception sur le bus d'événements : {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+
+        if sender.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+/// Lire une notification, en allant chercher en base un message stocké
+async fn decode(pool: &PgPool, payload: &str) -> AppResult<Envelope> {
+    let payload = match payload.strip_prefix(PAYLOAD_REF_PREFIX) {
+        Some(id) => {
+            let id: i64 = id.parse().map_err(|_| AppError::InternalServerError)?;
+            sqlx::query_scalar("SELECT payload FROM event_bus_payloads WHERE id = $1")
+                .bind(id)
+                .fetch_one(pool)
+                .await?
+        }
+        None => payload.to_string(),
+    };
+
+    serde_json::from_str(&payload).map_err(|_| AppError::InternalServerError)
+}
+
+/// Supprimer chaque minute les messages stockés déjà lus par toutes les instances
+async fn purge_payloads(pool: PgPool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        if let Err(e) = delete_expired_payloads(&pool).await {
+            error!("Échec de la purge des messages du bus d'événements : {}", e);
+        }
+    }
+}
+
+/// Supprimer les messages stockés depuis plus de `PAYLOAD_RETENTION_MINUTES`
+async fn delete_expired_payloads(pool: &PgPool) -> AppResult<u64> {
+    let result = sqlx::query(
+        "DELETE FROM event_bus_payloads WHERE created_at < NOW() - make_interval(mins => $1)",
+    )
+    .bind(PAYLOAD_RETENTION_MINUTES)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Base de test (migrée) désignée par `DATABASE_URL`
+    async fn test_pool() -> PgPool {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL vers une base Postgres migrée");
+        PgPool::connect(&url).await.expect("connexion à DATABASE_URL")
+    }
+
+    /// Prochain message publié par `origin` (la base peut être partagée avec d'autres instances)
+    async fn next_from(receiver: &mut BusReceiver, origin: Uuid) -> BusMessage {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+                .await
+                .expect("message reçu avant le délai")
+                .expect("bus ouvert");
+            if let BusEvent::Message(envelope) = event {
+                if envelope.origin == origin {
+                    return envelope.message;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a local Postgres (DATABASE_URL)"]
+    async fn pg_bus_delivers_small_and_large_messages_to_every_instance() {
+        let pool = test_pool().await;
+        let (bus_a, mut receiver_a) = PgBus::connect(pool.clone()).await.unwrap();
+        let (_bus_b, mut receiver_b) = PgBus::connect(pool.clone()).await.unwrap();
+        let origin = Uuid::new_v4();
+
+        let small = Envelope {
+            origin,
+            message: BusMessage::SyncChannelRooms { server_id: 42 },
+        };
+        // Trop gros pour NOTIFY : stocké en base et notifié par référence
+        let name = "x".repeat(MAX_NOTIFY_PAYLOAD + 100);
+        let large = Envelope {
+            origin,
+            message: BusMessage::Emit {
+                rooms: vec!["server:42".to_string()],
+                except_socket: None,
+                event: SocketEvent::ChannelCreated {
+                    server_id: 42,
+                    channel_id: 7,
+                    name: name.clone(),
+                },
+            },
+        };
+        bus_a.publish(&small).await.unwrap();
+        bus_a.publish(&large).await.unwrap();
+
+        // L'instance d'origine reçoit aussi ses propres messages, dans l'ordre
+        for receiver in [&mut receiver_a, &mut receiver_b] {
+            assert!(matches!(
+                next_from(receiver, origin).await,
+                BusMessage::SyncChannelRooms { server_id: 42 }
+            ));
+            match next_from(receiver, origin).await {
+                BusMessage::Emit {
+                    rooms,
+                    event: SocketEvent::ChannelCreated { name: received, .. },
+                    ..
+                } => {
+                    assert_eq!(rooms, vec!["server:42".to_string()]);
+                    assert_eq!(received, name);
+                }
+                message => panic!("message inattendu : {:?}", message),
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a local Postgres (DATABASE_URL)"]
+    async fn purge_removes_only_expired_payloads() {
+        let pool = test_pool().await;
+        let insert = |age_minutes: i32| {
+            sqlx::query_scalar::<_, i64>(
+                "INSERT INTO event_bus_payloads (payload, created_at) \
+                 VALUES ('{}', NOW() - make_interval(mins => $1)) RETURNING id",
+            )
+            .bind(age_minutes)
+            .fetch_one(&pool)
+        };
+        let expired = insert(PAYLOAD_RETENTION_MINUTES + 1).await.unwrap();
+        let recent = insert(0).await.unwrap();
+
+        assert!(delete_expired_payloads(&pool).await.unwrap() >= 1);
+
+        let remaining: Vec<i64> = sqlx::query_scalar("SELECT id FROM event_bus_payloads WHERE id = ANY($1)")
+            .bind(vec![expired, recent])
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, vec![recent]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a local Postgres (DATABASE_URL)"]
+    async fn missing_payload_reference_is_an_error() {
+        let pool = test_pool().await;
+        assert!(decode(&pool, &format!("{}{}", PAYLOAD_REF_PREFIX, i64::MAX)).await.is_err());
+        assert!(decode(&pool, "{pas du json").await.is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::time::Duration;
+use socketioxide::SocketIo;
+use crate::irc::IrcGateway;
+use crate::services::{ChannelService, PresenceService};
+use crate::ws::bus::{BusEvent, BusMessage, BusReceiver, Envelope};
+use crate::ws::{self, Hub};
+
+/// Intervalle entre deux signes de vie de l'instance
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Délai sans signe de vie après lequel une autre instance est considérée arrêtée
+const NODE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Applique aux sockets de cette instance les messages reçus du bus d'événements
+#[derive(Clone)]
+pub struct Dispatcher {
+    io: SocketIo,
+    hub: Arc<Hub>,
+    channel_service: Arc<ChannelService>,
+    presence_service: Arc<PresenceService>,
+    irc: Arc<IrcGateway>,
+}
+
+impl Dispatcher {
+    pub fn new(
+        io: SocketIo,
+        hub: Arc<Hub>,
+        channel_service: Arc<ChannelService>,
+        presence_service: Arc<PresenceService>,
+        irc: Arc<IrcGateway>,
+    ) -> Self {
+        Self {
+            io,
+            hub,
+            channel_service,
+            presence_service,
+            irc,
+        }
+    }
+
+    /// Lancer la réception du bus et les signes de vie de l'instance
+    pub fn spawn(self, receiver: BusReceiver) {
+        tokio::spawn(self.clone().heartbeat());
+        tokio::spawn(self.run(receiver));
+    }
+
+    /// Traiter les messages du bus jusqu'à sa fermeture
+    async fn run(self, mut receiver: BusReceiver) {
+        // Récupérer l'état des instances déjà lancées
+        self.hub.publish(BusMessage::SnapshotRequest).await;
+
+        while let Some(event) = receiver.recv().await {
+            match event {
+                BusEvent::Message(envelope) => self.dispatch(*envelope).await,
+                BusEvent::Resync => self.hub.publish(BusMessage::SnapshotRequest).await,
+            }
+        }
+    }
+
+    async fn dispatch(&self, envelope: Envelope) {
+        let origin = envelope.origin;
+        let remote = origin != self.hub.node_id();
+        self.hub.node_seen(origin).await;
+
+        match envelope.message {
+            BusMessage::Emit { rooms, except_socket, event } => {
+                self.hub
+                    .sessions()
+                    .deliver(&self.io, rooms, Vec::new(), except_socket.as_deref(), &event);
+                // L'instance d'origine relaie elle-même vers ses clients IRC
+                if remote {
+                    self.irc.relay(&event).await;
+                }
+            }
+            BusMessage::Leave { within, rooms } => {
+                self.hub.sessions().leave_detached(&within, &rooms);
+                self.irc.leave_rooms(&within, &rooms).await;
+                if let Some(namespace) = self.io.of("/") {
+                    namespace.within(within).leave(rooms).ok();
+                }
+            }
+            BusMessage::Disconnect { rooms } => {
+                self.irc.disconnect_rooms(&rooms).await;
+                if let Some(namespace) = self.io.of("/") {
+                    namespace.within(rooms).disconnect().ok();
+                }
+            }
+            BusMessage::RevokeSessions { user_id, session_ids, password_logins } => {
+                ws::disconnect_revoked_sockets(&self.io, user_id, &session_ids);
+                self.irc.close_sessions(user_id, &session_ids, password_logins).await;
+            }
+            BusMessage::SyncChannelRooms { server_id } => {
+                ws::sync_local_channel_rooms(&self.io, &self.hub, &self.channel_service, server_id).await;
+                self.irc.sync_channel_rooms(server_id).await;
+            }
+            BusMessage::Hub { update } if remote => {
+                let effects = self.hub.apply_remote(origin, update).await;
+                ws::notify_hub_effects(&self.io, &self.hub, &self.presence_service, effects).await;
+            }
+            BusMessage::SnapshotRequest if remote => {
+                let snapshot = self.hub.snapshot().await;
+                self.hub.publish(BusMessage::Snapshot { snapshot }).await;
+            }
+            BusMessage::Snapshot { snapshot } if remote => {
+                let effects = self.hub.apply_snapshot(origin, snapshot).await;
+                ws::notify_hub_effects(&self.io, &self.hub, &self.presence_service, effects).await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Publier régulièrement un signe de vie, et oublier les instances arrêtées
+    async fn heartbeat(self) {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.hub.publish(BusMessage::Heartbeat).await;
+
+            let effects = self.hub.expire_nodes(NODE_TIMEOUT).await;
+            ws::notify_hub_effects(&self.io, &self.hub, &self.presence_service, effects).await;
+        }
+    }
+}
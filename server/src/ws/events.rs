@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::models::{Attachment, MentionKind, PresenceStatus};
 
 /// Événements Socket.IO que le serveur peut envoyer
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,15 +12,113 @@ pub enum SocketEvent {
         content: String,
         author_id: i32,
         author_username: String,
+        reply_to_id: Option<i32>,
+        attachments: Vec<Attachment>,
+        /// Nonce fourni par le client à l'envoi (`send_message`)
+        nonce: Option<String>,
         created_at: String,
     },
     
+    /// Un fil de discussion a été ouvert sur un message
+    ThreadCreated {
+        channel_id: i32,
+        thread_id: i32,
+        root_message_id: i32,
+        name: String,
+        creator_id: i32,
+    },
+    
+    /// Un nouveau message a été posté dans un fil
+    ThreadMessage {
+        channel_id: i32,
+        thread_id: i32,
+        message_id: i32,
+        content: String,
+        author_id: i32,
+        author_username: String,
+        reply_to_id: Option<i32>,
+        attachments: Vec<Attachment>,
+        created_at: String,
+    },
+    
+    /// Un message a été modifié
+    MessageUpdated {
+        channel_id: i32,
+        message_id: i32,
+        content: String,
+        updated_at: String,
+    },
+    
     /// Un message a été supprimé
     MessageDeleted {
         channel_id: i32,
         message_id: i32,
     },
     
+    /// Un membre a réagi à un message
+    ReactionAdded {
+        channel_id: i32,
+        message_id: i32,
+        emoji: String,
+        user_id: i32,
+        count: i64,
+    },
+    
+    /// Un membre a retiré sa réaction
+    ReactionRemoved {
+        channel_id: i32,
+        message_id: i32,
+        emoji: String,
+        user_id: i32,
+        count: i64,
+    },
+    
+    /// Un message a été épinglé dans son canal
+    MessagePinned {
+        channel_id: i32,
+        message_id: i32,
+        pinned_by: i32,
+        pinned_at: String,
+    },
+    
+    /// Un message a été désépinglé
+    MessageUnpinned {
+        channel_id: i32,
+        message_id: i32,
+        unpinned_by: i32,
+    },
+    
+    /// L'utilisateur a été mentionné (envoyé dans sa room personnelle)
+    MentionNew {
+        server_id: i32,
+        channel_id: i32,
+        thread_id: Option<i32>,
+        message_id: i32,
+        content: String,
+        author_id: i32,
+        author_username: String,
+        kind: MentionKind,
+        created_at: String,
+    },
+    
+    /// L'utilisateur a marqué un canal comme lu (synchronisé entre ses sockets)
+    ChannelAck {
+        channel_id: i32,
+        last_read_message_id: Option<i32>,
+        unread_count: i64,
+        mention_count: i64,
+    },
+    
+    /// Un nouveau message privé a été envoyé
+    DirectMessage {
+        conversation_id: i32,
+        message_id: i32,
+        content: String,
+        author_id: i32,
+        author_username: String,
+        created_at: String,
+    },
+    
     /// Un utilisateur est en train de taper
     UserTyping {
         channel_id: i32,
@@ -41,6 +140,20 @@ pub enum SocketEvent {
         username: String,
     },
     
+    /// La présence d'un utilisateur a changé (statut ou statut personnalisé)
+    PresenceUpdate {
+        user_id: i32,
+        status: PresenceStatus,
+        custom_status: Option<String>,
+    },
+    
+    /// Le nom ou le propriétaire d'un serveur a changé
+    ServerUpdated {
+        server_id: i32,
+        name: String,
+        owner_id: i32,
+    },
+    
     /// Un nouveau membre a rejoint le serveur
     MemberJoined {
         server_id: i32,
@@ -55,6 +168,23 @@ pub enum SocketEvent {
         username: String,
     },
     
+    /// Un membre a été banni du serveur
+    MemberBanned {
+        server_id: i32,
+        user_id: i32,
+        username: String,
+        reason: Option<String>,
+        expires_at: Option<String>,
+    },
+    
+    /// L'exclusion temporaire d'un membre a été posée ou levée
+    MemberTimedOut {
+        server_id: i32,
+        user_id: i32,
+        username: String,
+        until: Option<String>,
+    },
+    
     /// Un canal a été créé
     ChannelCreated {
         server_id: i32,
@@ -74,12 +204,26 @@ impl SocketEvent {
     pub fn event_name(&self) -> &'static str {
         match self {
             SocketEvent::NewMessage { .. } => "message:new",
+            SocketEvent::ThreadCreated { .. } => "thread:created",
+            SocketEvent::ThreadMessage { .. } => "thread:message",
+            SocketEvent::MessageUpdated { .. } => "message:updated",
             SocketEvent::MessageDeleted { .. } => "message:deleted",
+            SocketEvent::ReactionAdded { .. } => "reaction:added",
+            SocketEvent::ReactionRemoved { .. } => "reaction:removed",
+            SocketEvent::MessagePinned { .. } => "message:pinned",
+            SocketEvent::MessageUnpinned { .. } => "message:unpinned",
+            SocketEvent::MentionNew { .. } => "mention:new",
+            SocketEvent::ChannelAck { .. } => "channel:ack",
+            SocketEvent::DirectMessage { .. } => "dm:new",
             SocketEvent::UserTyping { .. } => "user:typing",
             SocketEvent::UserConnected { .. } => "user:connected",
             SocketEvent::UserDisconnected { .. } => "user:disconnected",
+            SocketEvent::PresenceUpdate { .. } => "presence:update",
+            SocketEvent::ServerUpdated { .. } => "server:updated",
             SocketEvent::MemberJoined { .. } => "member:joined",
             SocketEvent::MemberLeft { .. } => "member:left",
+            SocketEvent::MemberBanned { .. } => "member:banned",
+            SocketEvent::MemberTimedOut { .. } => "member:timeout",
             SocketEvent::ChannelCreated { .. } => "channel:created",
             SocketEvent::ChannelDeleted { .. } => "channel:deleted",
         }
@@ -1,24 +1,76 @@
 use serde::{Deserialize, Serialize};
-use socketioxide::extract::{Data, SocketRef};
+use axum_extra::headers::authorization::Bearer;
+use axum_extra::headers::{Authorization, HeaderMapExt};
+use chrono::Utc;
+use socketioxide::extract::{AckSender, Data, SocketRef};
+use socketioxide::socket::Sid;
+use socketioxide::SocketIo;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, info};
-use crate::services::{MessageService, ServerService};
+use uuid::Uuid;
+use axum::http::StatusCode;
+use crate::errors::{AppError, AppResult};
+use crate::irc::IrcGateway;
+use crate::models::{
+    AckChannelDto, CreateMessageDto, Message, MessageMentions, Presence, PresenceChange,
+    UpdatePresenceDto,
+};
+use crate::services::{
+    AuthService, ChannelService, MentionService, MessageService, PresenceService, ReadStateService,
+    ServerService,
+};
 use crate::utils::verify_token;
-use crate::ws::{Hub, SocketEvent};
+use crate::ws::session::ResumeOutcome;
+use crate::ws::{Hub, HubEffects, SocketEvent, UserInfo};
 
-/// Payload pour l'authentification Socket.IO
+/// Payload `auth` de la connexion Socket.IO
 #[derive(Debug, Deserialize)]
-pub struct AuthenticatePayload {
-    pub token: String,
+pub struct HandshakeAuth {
+    /// Token JWT ; à défaut, en-tête `Authorization: Bearer <token>`
+    pub token: Option<String>,
+    /// Session à reprendre après une reconnexion
+    pub session_id: Option<Uuid>,
+    /// Numéro (`seq`) du dernier événement reçu, pour une reprise
+    #[serde(default)]
+    pub last_seq: u64,
 }
 
-/// Réponse d'authentification
+/// Événement `authenticated`, envoyé à la connexion d'un socket
 #[derive(Debug, Serialize)]
 pub struct AuthenticateResponse {
-    pub success: bool,
-    pub user_id: Option<i32>,
-    pub username: Option<String>,
-    pub error: Option<String>,
+    pub user_id: i32,
+    pub username: String,
+    /// Présence de l'utilisateur (statut choisi et statut personnalisé)
+    pub presence: Presence,
+    /// Session à reprendre après une coupure
+    pub session_id: Uuid,
+}
+
+/// Payload pour reprendre une session sur un socket déjà connecté
+#[derive(Debug, Deserialize)]
+pub struct ResumePayload {
+    pub session_id: Uuid,
+    /// Numéro (`seq`) du dernier événement reçu
+    pub last_seq: u64,
+}
+
+/// Événement `resumed`, envoyé au socket qui reprend une session
+#[derive(Debug, Serialize)]
+pub struct ResumeResponse {
+    /// Session reprise, ou nouvelle session si elle a expiré
+    pub session_id: Uuid,
+    pub user_id: i32,
+    pub username: String,
+    pub presence: Presence,
+    /// Nombre d'événements manqués renvoyés avant cette réponse
+    pub replayed: usize,
+    /// Des événements n'ont pas pu être renvoyés : l'état est à recharger (API REST)
+    pub resync_required: bool,
+    /// `buffer_exceeded` ou `session_expired` quand `resync_required`
+    pub reason: Option<&'static str>,
 }
 
 /// Payload pour rejoindre un serveur
@@ -33,102 +85,335 @@ pub struct LeaveServerPayload {
     pub server_id: i32,
 }
 
+/// Payload pour s'abonner à un canal
+#[derive(Debug, Deserialize)]
+pub struct JoinChannelPayload {
+    pub channel_id: i32,
+}
+
+/// Payload pour se désabonner d'un canal
+#[derive(Debug, Deserialize)]
+pub struct LeaveChannelPayload {
+    pub channel_id: i32,
+}
+
 /// Payload pour l'événement "typing"
 #[derive(Debug, Deserialize)]
 pub struct TypingPayload {
     pub channel_id: i32,
 }
 
-/// Handler pour l'authentification Socket.IO
-pub async fn on_authenticate(
+/// Payload pour marquer un canal comme lu
+#[derive(Debug, Deserialize)]
+pub struct AckPayload {
+    pub channel_id: i32,
+    /// Dernier message du canal si absent
+    pub message_id: Option<i32>,
+}
+
+/// Longueur maximale d'un nonce client
+const MAX_NONCE_LENGTH: usize = 64;
+
+/// Payload pour envoyer un message dans un canal
+#[derive(Debug, Deserialize)]
+pub struct SendMessagePayload {
+    pub channel_id: i32,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub reply_to_id: Option<i32>,
+    #[serde(default)]
+    pub attachment_ids: Vec<i32>,
+    /// Identifiant choisi par le client, renvoyé tel quel ; un renvoi avec le même
+    /// nonce ne crée pas de doublon
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
+/// Réponse (ack) à `send_message`
+#[derive(Debug, Serialize)]
+pub struct SendMessageAck {
+    pub success: bool,
+    pub message: Option<Message>,
+    pub error: Option<String>,
+    pub code: Option<&'static str>,
+    pub nonce: Option<String>,
+}
+
+/// Middleware de connexion : vérifie le token JWT avant l'entrée dans le namespace
+///
+/// Le token vient du payload `auth` ou de l'en-tête `Authorization`. Une connexion sans
+/// token valide, ou dont la session a été révoquée, est refusée (`connect_error`) ; sinon
+/// l'utilisateur devient une extension du socket, fermé automatiquement à l'expiration
+/// du token.
+pub async fn authenticate_handshake(
     socket: SocketRef,
-    Data(data): Data<AuthenticatePayload>,
+    io: SocketIo,
+    Data(auth): Data<HandshakeAuth>,
+    auth_service: Arc<AuthService>,
+) -> Result<(), AppError> {
+    let token = auth
+        .token
+        .or_else(|| {
+            socket
+                .req_parts()
+                .headers
+                .typed_get::<Authorization<Bearer>>()
+                .map(|Authorization(bearer)| bearer.token().to_string())
+        })
+        .ok_or(AppError::Unauthorized)?;
+    // Sans la tolérance de `verify_token` : un socket fermé à l'expiration ne peut pas
+    // se reconnecter avec le même token
+    let claims = verify_token(&token)
+        .ok()
+        .filter(|claims| claims.exp > Utc::now().timestamp())
+        .ok_or_else(|| {
+            error!("❌ Connexion Socket.IO refusée : token invalide ou expiré");
+            AppError::InvalidToken
+        })?;
+    if let Err(e) = auth_service.check_session(&claims).await {
+        error!("❌ Connexion Socket.IO refusée : session {} révoquée", claims.sid);
+        return Err(e);
+    }
+
+    socket.extensions.insert(UserInfo {
+        user_id: claims.sub,
+        username: claims.username,
+        auth_session_id: Some(claims.sid),
+    });
+    tokio::spawn(disconnect_on_expiry(io, socket.id, claims.exp));
+
+    Ok(())
+}
+
+/// Fermer un socket à l'expiration de son token
+async fn disconnect_on_expiry(io: SocketIo, sid: Sid, expires_at: i64) {
+    let remaining = (expires_at - Utc::now().timestamp()).max(0) as u64;
+    tokio::time::sleep(Duration::from_secs(remaining)).await;
+
+    if let Some(socket) = io.get_socket(sid) {
+        info!("Token expiré, fermeture du socket {}", sid);
+        emit_error(&socket, &AppError::InvalidToken);
+        let _ = socket.disconnect();
+    }
+}
+
+/// Fermer les sockets de cette instance authentifiés avec une session révoquée
+///
+/// Exécuté par chaque instance à la réception de `Hub::revoke_sessions`.
+pub fn disconnect_revoked_sockets(io: &SocketIo, user_id: i32, session_ids: &[i32]) {
+    let Some(namespace) = io.of("/") else {
+        return;
+    };
+    let sockets = namespace
+        .within(format!("user:{}", user_id))
+        .sockets()
+        .unwrap_or_default();
+
+    for socket in sockets {
+        let revoked = socket_user(&socket)
+            .auth_session_id
+            .is_some_and(|session_id| session_ids.contains(&session_id));
+        if revoked {
+            info!("Session révoquée, fermeture du socket {}", socket.id);
+            emit_error(&socket, &AppError::InvalidToken);
+            let _ = socket.disconnect();
+        }
+    }
+}
+
+/// Utilisateur du socket, authentifié à la connexion
+fn socket_user(socket: &SocketRef) -> UserInfo {
+    socket
+        .extensions
+        .get::<UserInfo>()
+        .expect("socket authentifié à la connexion")
+}
+
+/// Handler de connexion d'un socket authentifié
+///
+/// Ouvre une session (`authenticated`), ou reprend celle indiquée dans le payload `auth` :
+/// le socket retrouve alors les rooms de la session et reçoit les événements manqués
+/// avant `resumed`.
+pub async fn on_connect(
+    socket: SocketRef,
+    auth: HandshakeAuth,
+    io: SocketIo,
     hub: Arc<Hub>,
+    presence_service: Arc<PresenceService>,
 ) {
-    info!("Tentative d'authentification Socket.IO : {}", socket.id);
+    let user_info = socket_user(&socket);
+    let socket_id = socket.id.to_string();
     
-    match verify_token(&data.token) {
-        Ok(claims) => {
-            // Stocker les informations utilisateur dans le hub
-            hub.authenticate_socket(
-                socket.id.to_string(),
-                claims.sub,
-                claims.username.clone()
-            ).await;
-            
-            info!("✓ Utilisateur {} authentifié via Socket.IO", claims.username);
-            
-            let response = AuthenticateResponse {
-                success: true,
-                user_id: Some(claims.sub),
-                username: Some(claims.username),
-                error: None,
-            };
-            
-            let _ = socket.emit("authenticated", response);
+    // Session ouverte (ou reprise) avant toute room : chaque événement reçu est numéroté
+    let resumed = auth.session_id.map(|session_id| {
+        hub.sessions()
+            .resume(&io, &socket, session_id, user_info.user_id, auth.last_seq)
+    });
+    let session_id = match &resumed {
+        Some((session_id, _)) => *session_id,
+        None => hub.sessions().open(&socket_id, user_info.user_id),
+    };
+    
+    // Room personnelle pour les messages privés
+    let _ = socket.join(format!("user:{}", user_info.user_id));
+    
+    // Enregistrer le socket dans le hub ; premier socket de l'utilisateur : il passe en ligne
+    let effects = presence_service
+        .connect(&socket_id, user_info.user_id, &user_info.username)
+        .await;
+    notify_hub_effects(&io, &hub, &presence_service, effects).await;
+    
+    // Socket fermé entre-temps : son handler de déconnexion a pu passer avant cet enregistrement
+    if !socket.connected() {
+        hub.sessions().detach(&socket);
+        let effects = hub.disconnect_socket(&socket_id, user_info.user_id).await;
+        notify_hub_effects(&io, &hub, &presence_service, effects).await;
+        return;
+    }
+    
+    let Some((_, outcome)) = resumed else {
+        info!("✓ Utilisateur {} authentifié via Socket.IO", user_info.username);
+        
+        let presence = presence_service.get_presence(user_info.user_id).await;
+        let response = AuthenticateResponse {
+            user_id: user_info.user_id,
+            username: user_info.username,
+            presence,
+            session_id,
+        };
+        let _ = socket.emit("authenticated", response);
+        return;
+    };
+    
+    complete_resume(&socket, &io, &hub, &presence_service, user_info, session_id, outcome).await;
+}
+
+/// Handler pour reprendre une session (`resume`) sur un socket déjà connecté
+///
+/// La session ouverte à la connexion est remplacée par celle indiquée : le socket retrouve
+/// ses rooms et reçoit les événements manqués avant `resumed`.
+pub async fn on_resume(
+    socket: SocketRef,
+    Data(data): Data<ResumePayload>,
+    io: SocketIo,
+    hub: Arc<Hub>,
+    presence_service: Arc<PresenceService>,
+) {
+    let user_info = socket_user(&socket);
+    
+    let (session_id, outcome) = hub
+        .sessions()
+        .resume(&io, &socket, data.session_id, user_info.user_id, data.last_seq);
+    
+    complete_resume(&socket, &io, &hub, &presence_service, user_info, session_id, outcome).await;
+}
+
+/// Terminer une reprise de session : serveurs de nouveau rejoints, ancien socket fermé, `resumed`
+async fn complete_resume(
+    socket: &SocketRef,
+    io: &SocketIo,
+    hub: &Hub,
+    presence_service: &PresenceService,
+    user_info: UserInfo,
+    session_id: Uuid,
+    outcome: ResumeOutcome,
+) {
+    let socket_id = socket.id.to_string();
+    
+    let (rooms, replayed, reason, previous_socket) = match outcome {
+        ResumeOutcome::Replayed { rooms, replayed, previous_socket } => {
+            (rooms, replayed, None, previous_socket)
         }
-        Err(_) => {
-            error!("❌ Échec d'authentification Socket.IO");
-            
-            let response = AuthenticateResponse {
-                success: false,
-                user_id: None,
-                username: None,
-                error: Some("Invalid or expired token".to_string()),
-            };
-            
-            let _ = socket.emit("authenticated", response);
-            let _ = socket.disconnect();
+        ResumeOutcome::BufferExceeded { rooms, previous_socket } => {
+            (rooms, 0, Some("buffer_exceeded"), previous_socket)
         }
+        ResumeOutcome::Expired => (Vec::new(), 0, Some("session_expired"), None),
+    };
+    
+    // Serveurs rejoints par la session : le socket compte de nouveau parmi leurs connectés
+    let server_ids = rooms
+        .iter()
+        .filter_map(|room| room.strip_prefix("server:")?.parse::<i32>().ok());
+    for server_id in server_ids {
+        let effects = hub.connect_user(server_id, user_info.user_id, &socket_id).await;
+        notify_hub_effects(io, hub, presence_service, effects).await;
+    }
+    
+    // L'ancien socket de la session, encore ouvert, est remplacé par celui-ci
+    let previous_socket = previous_socket
+        .and_then(|socket_id| socket_id.parse().ok())
+        .and_then(|sid| io.get_socket(sid));
+    if let Some(previous_socket) = previous_socket {
+        let _ = previous_socket.disconnect();
     }
+    
+    info!(
+        "✓ Session de {} reprise ({} événements renvoyés)",
+        user_info.username, replayed
+    );
+    
+    let response = ResumeResponse {
+        session_id,
+        user_id: user_info.user_id,
+        presence: presence_service.get_presence(user_info.user_id).await,
+        username: user_info.username,
+        replayed,
+        resync_required: reason.is_some(),
+        reason,
+    };
+    let _ = socket.emit("resumed", response);
 }
 
 /// Handler pour rejoindre un serveur
 pub async fn on_join_server(
     socket: SocketRef,
     Data(data): Data<JoinServerPayload>,
+    io: SocketIo,
     hub: Arc<Hub>,
     server_service: Arc<ServerService>,
+    channel_service: Arc<ChannelService>,
+    presence_service: Arc<PresenceService>,
 ) {
-    // Récupérer l'ID utilisateur depuis le hub
-    let user_info = match hub.get_user_info(&socket.id.to_string()).await {
-        Some(info) => info,
-        None => {
-            error!("Utilisateur non authentifié tente de rejoindre un serveur");
-            let _ = socket.emit("error", serde_json::json!({
-                "error": "Not authenticated",
-                "code": "UNAUTHORIZED"
-            }));
-            return;
-        }
-    };
+    let user_info = socket_user(&socket);
     
     // Vérifier que l'utilisateur est membre du serveur
-    match server_service.get_user_role(data.server_id, user_info.user_id).await {
-        Ok(_role) => {
+    match server_service.check_is_member(data.server_id, user_info.user_id).await {
+        Ok(()) => {
             // Rejoindre la room du serveur
             let room_name = format!("server:{}", data.server_id);
-            let _ = socket.join(room_name.clone());
+            let _ = socket.join(room_name);
             
-            // Ajouter au hub
-            hub.connect_user(data.server_id, user_info.user_id).await;
-            
-            info!("✓ Utilisateur {} a rejoint le serveur {}", user_info.username, data.server_id);
+            // Rejoindre les rooms des canaux visibles (surcharges de permissions incluses)
+            let channels = channel_service
+                .get_server_channels(data.server_id, user_info.user_id)
+                .await
+                .unwrap_or_default();
+            let channel_rooms: Vec<String> = channels
+                .iter()
+                .map(|channel| format!("channel:{}", channel.id))
+                .collect();
+            let _ = socket.join(channel_rooms);
             
-            // Notifier les autres membres
-            let event = SocketEvent::UserConnected {
-                server_id: data.server_id,
-                user_id: user_info.user_id,
-                username: user_info.username.clone(),
-            };
+            // Ajouter au hub, et notifier les autres membres
+            // (une seule fois par utilisateur, jamais s'il est invisible)
+            let effects = hub
+                .connect_user(data.server_id, user_info.user_id, &socket.id.to_string())
+                .await;
+            notify_hub_effects(&io, &hub, &presence_service, effects).await;
             
-            socket.to(room_name.clone()).emit(event.event_name(), serde_json::to_value(event).unwrap()).ok();
+            info!("✓ Utilisateur {} a rejoint le serveur {}", user_info.username, data.server_id);
             
-            // Envoyer la liste des utilisateurs connectés au nouveau membre
+            // Envoyer au nouveau venu les utilisateurs connectés et la présence des membres
             let connected_users = hub.get_connected_users(data.server_id).await;
+            let presences = presence_service
+                .get_server_presences(data.server_id)
+                .await
+                .unwrap_or_default();
             let _ = socket.emit("server:joined", serde_json::json!({
                 "server_id": data.server_id,
-                "connected_users": connected_users
+                "connected_users": connected_users,
+                "presences": presences
             }));
         }
         Err(_) => {
@@ -145,42 +430,76 @@ pub async fn on_join_server(
 pub async fn on_leave_server(
     socket: SocketRef,
     Data(data): Data<LeaveServerPayload>,
+    io: SocketIo,
     hub: Arc<Hub>,
+    channel_service: Arc<ChannelService>,
+    presence_service: Arc<PresenceService>,
 ) {
-    let user_info = match hub.get_user_info(&socket.id.to_string()).await {
-        Some(info) => info,
-        None => return,
-    };
+    let user_info = socket_user(&socket);
     
     // Quitter la room
-    let room_name = format!("server:{}", data.server_id);
-    let _ = socket.leave(room_name.clone());
-    
-    // Retirer du hub
-    hub.disconnect_user(data.server_id, user_info.user_id).await;
+    let _ = socket.leave(format!("server:{}", data.server_id));
     
-    // Notifier les autres membres
-    let event = SocketEvent::UserDisconnected {
-        server_id: data.server_id,
-        user_id: user_info.user_id,
-        username: user_info.username.clone(),
-    };
+    // Quitter les rooms des canaux du serveur
+    if let Ok(channels) = channel_service
+        .get_server_channels(data.server_id, user_info.user_id)
+        .await
+    {
+        let channel_rooms: Vec<String> = channels
+            .iter()
+            .map(|channel| format!("channel:{}", channel.id))
+            .collect();
+        let _ = socket.leave(channel_rooms);
+    }
     
-    socket.to(room_name).emit(event.event_name(), serde_json::to_value(event).unwrap()).ok();
+    // Retirer du hub ; les autres membres ne sont prévenus qu'au dernier socket
+    let effects = hub
+        .disconnect_user_socket(data.server_id, user_info.user_id, &socket.id.to_string())
+        .await;
+    notify_hub_effects(&io, &hub, &presence_service, effects).await;
     
     info!("✓ Utilisateur {} a quitté le serveur {}", user_info.user_id, data.server_id);
 }
 
+/// Handler pour s'abonner aux événements d'un canal
+///
+/// Utile pour un canal créé après `join_server` ou ouvert sans rejoindre le serveur.
+pub async fn on_join_channel(
+    socket: SocketRef,
+    Data(data): Data<JoinChannelPayload>,
+    channel_service: Arc<ChannelService>,
+) {
+    let user_info = socket_user(&socket);
+    
+    // Même contrôle d'accès que GET /channels/:id
+    match channel_service.get_channel(data.channel_id, user_info.user_id).await {
+        Ok(channel) => {
+            let _ = socket.join(format!("channel:{}", channel.id));
+            let _ = socket.emit("channel:joined", serde_json::json!({
+                "server_id": channel.server_id,
+                "channel_id": channel.id
+            }));
+        }
+        Err(e) => emit_error(&socket, &e),
+    }
+}
+
+/// Handler pour se désabonner des événements d'un canal
+pub async fn on_leave_channel(socket: SocketRef, Data(data): Data<LeaveChannelPayload>) {
+    let _ = socket.leave(format!("channel:{}", data.channel_id));
+}
+
 /// Handler pour l'événement "typing_start"
 pub async fn on_typing_start(
     socket: SocketRef,
     Data(data): Data<TypingPayload>,
+    io: SocketIo,
     hub: Arc<Hub>,
+    presence_service: Arc<PresenceService>,
 ) {
-    let user_info = match hub.get_user_info(&socket.id.to_string()).await {
-        Some(info) => info,
-        None => return,
-    };
+    let user_info = socket_user(&socket);
+    
+    touch_presence(&socket, &io, &hub, &presence_service, user_info.user_id).await;
     
     let event = SocketEvent::UserTyping {
         channel_id: data.channel_id,
@@ -189,24 +508,515 @@ pub async fn on_typing_start(
     };
     
     // Envoyer à tous sauf l'émetteur
-    socket.to(format!("channel:{}", data.channel_id))
-        .emit(event.event_name(), serde_json::to_value(event).unwrap())
-        .ok();
+    hub.emit_except_socket(format!("channel:{}", data.channel_id), &socket.id.to_string(), event)
+        .await;
 }
 
-/// Handler pour la déconnexion
-pub async fn on_disconnect(socket: SocketRef, hub: Arc<Hub>) {
-    let user_info = match hub.get_user_info(&socket.id.to_string()).await {
-        Some(info) => info,
-        None => return,
+/// Handler pour marquer un canal comme lu
+pub async fn on_ack(
+    socket: SocketRef,
+    Data(data): Data<AckPayload>,
+    io: SocketIo,
+    hub: Arc<Hub>,
+    read_state_service: Arc<ReadStateService>,
+    presence_service: Arc<PresenceService>,
+) {
+    let user_info = socket_user(&socket);
+    
+    touch_presence(&socket, &io, &hub, &presence_service, user_info.user_id).await;
+    
+    let dto = AckChannelDto {
+        message_id: data.message_id,
     };
+    match read_state_service
+        .ack_channel(data.channel_id, dto, user_info.user_id)
+        .await
+    {
+        Ok(state) => {
+            let event = SocketEvent::ChannelAck {
+                channel_id: state.channel_id,
+                last_read_message_id: state.last_read_message_id,
+                unread_count: state.unread_count,
+                mention_count: state.mention_count,
+            };
+            
+            // Tous les sockets de l'utilisateur, y compris celui-ci
+            hub.emit(format!("user:{}", user_info.user_id), event).await;
+        }
+        Err(e) => emit_error(&socket, &e),
+    }
+}
+
+/// Handler pour envoyer un message dans un canal
+///
+/// Le résultat est renvoyé par l'ack : le message enregistré, ou l'erreur avec son code.
+#[allow(clippy::too_many_arguments)]
+pub async fn on_send_message(
+    socket: SocketRef,
+    Data(data): Data<SendMessagePayload>,
+    ack: AckSender,
+    io: SocketIo,
+    hub: Arc<Hub>,
+    message_service: Arc<MessageService>,
+    mention_service: Arc<MentionService>,
+    presence_service: Arc<PresenceService>,
+    irc: Arc<IrcGateway>,
+) {
+    let nonce = data.nonce.clone();
+    let user_info = socket_user(&socket);
+    touch_presence(&socket, &io, &hub, &presence_service, user_info.user_id).await;
+    let result = send_message(data, &user_info, &hub, &message_service, &mention_service, &irc).await;
+
+    let response = match result {
+        Ok(message) => SendMessageAck {
+            success: true,
+            message: Some(message),
+            error: None,
+            code: None,
+            nonce,
+        },
+        Err(e) => SendMessageAck {
+            success: false,
+            message: None,
+            error: Some(e.client_message()),
+            code: Some(error_code(&e)),
+            nonce,
+        },
+    };
+
+    let _ = ack.send(response);
+}
+
+/// Créer le message (une seule fois par nonce) puis le diffuser
+async fn send_message(
+    data: SendMessagePayload,
+    user_info: &UserInfo,
+    hub: &Hub,
+    message_service: &MessageService,
+    mention_service: &MentionService,
+    irc: &IrcGateway,
+) -> AppResult<Message> {
+    let channel_id = data.channel_id;
+    let dto = CreateMessageDto {
+        content: data.content,
+        reply_to_id: data.reply_to_id,
+        attachment_ids: data.attachment_ids,
+    };
+
+    let Some(nonce) = data.nonce else {
+        let message = message_service
+            .create_message(channel_id, dto, user_info.user_id)
+            .await?;
+        let mentions = mention_service.record_mentions(&message).await;
+        broadcast_new_message(hub, irc, &message, &user_info.username, &mentions, None).await;
+        return Ok(message);
+    };
+
+    if nonce.is_empty() || nonce.chars().count() > MAX_NONCE_LENGTH {
+        return Err(AppError::ValidationError(format!(
+            "Le nonce doit contenir entre 1 et {} caractères",
+            MAX_NONCE_LENGTH
+        )));
+    }
+
+    // Un renvoi obtient le message déjà créé (ou attend sa création), sans rediffusion
+    let slot = hub.nonce_slot(user_info.user_id, &nonce).await;
+    let mut created = false;
+    let created_flag = &mut created;
+    let message = slot
+        .get_or_try_init(move || async move {
+            let message = message_service
+                .create_message(channel_id, dto, user_info.user_id)
+                .await?;
+            *created_flag = true;
+            Ok::<_, AppError>(message)
+        })
+        .await?
+        .clone();
+
+    if created {
+        let mentions = mention_service.record_mentions(&message).await;
+        broadcast_new_message(hub, irc, &message, &user_info.username, &mentions, Some(nonce)).await;
+    }
+
+    Ok(message)
+}
+
+/// Handler pour changer de statut ou de statut personnalisé
+pub async fn on_update_presence(
+    socket: SocketRef,
+    Data(data): Data<UpdatePresenceDto>,
+    io: SocketIo,
+    hub: Arc<Hub>,
+    presence_service: Arc<PresenceService>,
+) {
+    let user_info = socket_user(&socket);
+    
+    touch_presence(&socket, &io, &hub, &presence_service, user_info.user_id).await;
+    
+    match presence_service.update(user_info.user_id, data).await {
+        Ok(effects) if !effects.presences.is_empty() => {
+            notify_hub_effects(&io, &hub, &presence_service, effects).await;
+        }
+        Ok(_) => {
+            // Rien n'a changé : confirmer quand même au socket
+            let presence = presence_service.get_presence(user_info.user_id).await;
+            hub.sessions().send(&socket, &presence_event(&presence));
+        }
+        Err(e) => emit_error(&socket, &e),
+    }
+}
+
+/// Handler pour signaler une activité de l'utilisateur (fin de l'inactivité automatique)
+pub async fn on_activity(
+    socket: SocketRef,
+    io: SocketIo,
+    hub: Arc<Hub>,
+    presence_service: Arc<PresenceService>,
+) {
+    let user_info = socket_user(&socket);
+    touch_presence(&socket, &io, &hub, &presence_service, user_info.user_id).await;
+}
+
+/// Handler pour la déconnexion
+pub async fn on_disconnect(
+    socket: SocketRef,
+    io: SocketIo,
+    hub: Arc<Hub>,
+    server_service: Arc<ServerService>,
+    presence_service: Arc<PresenceService>,
+) {
+    let user_info = socket_user(&socket);
     
     info!("Utilisateur {} déconnecté", user_info.username);
     
-    // Nettoyer les données du socket
-    hub.disconnect_socket(&socket.id.to_string()).await;
+    // Nettoyer les données du socket : les serveurs où l'utilisateur n'a plus de socket
+    // sont prévenus, et il passe hors ligne au dernier socket fermé
+    let effects = hub.disconnect_socket(&socket.id.to_string(), user_info.user_id).await;
+    notify_hub_effects(&io, &hub, &presence_service, effects).await;
     
-    // Note: Dans une vraie application, il faudrait tracker tous les serveurs
-    // où l'utilisateur était connecté pour notifier correctement
+    // Dernier socket fermé (toutes instances confondues) : les adhésions temporaires prennent fin
+    if !hub.has_sockets(user_info.user_id).await {
+        match server_service.remove_temporary_memberships(user_info.user_id).await {
+            Ok(server_ids) => {
+                for server_id in server_ids {
+                    hub.disconnect_user(server_id, user_info.user_id).await;
+                    
+                    let event = SocketEvent::MemberLeft {
+                        server_id,
+                        user_id: user_info.user_id,
+                        username: user_info.username.clone(),
+                    };
+                    hub.emit(format!("server:{}", server_id), event).await;
+                }
+            }
+            Err(e) => error!(
+                "Impossible de retirer les adhésions temporaires de {} : {}",
+                user_info.user_id, e
+            ),
+        }
+    }
+}
+
+/// Retirer de force tous les sockets d'un utilisateur des rooms d'un serveur
+/// (expulsion ou bannissement)
+pub async fn evict_from_server(hub: &Hub, server_id: i32, user_id: i32, channel_ids: &[i32]) {
+    let mut rooms: Vec<String> = channel_ids
+        .iter()
+        .map(|channel_id| format!("channel:{}", channel_id))
+        .collect();
+    rooms.push(format!("server:{}", server_id));
+
+    hub.leave_rooms(vec![format!("user:{}", user_id)], rooms).await;
+
+    hub.disconnect_user(server_id, user_id).await;
+
+    info!("✓ Sockets de l'utilisateur {} retirés du serveur {}", user_id, server_id);
 }
 
+/// Réaligner les rooms `channel:{id}` des sockets de cette instance sur les canaux visibles
+///
+/// Exécuté par chaque instance à la réception de `Hub::sync_channel_rooms`, à appeler
+/// après tout changement qui modifie la visibilité des canaux (création de canal,
+/// surcharges, rôles). Les sockets perdent les canaux devenus invisibles ; ceux qui ont
+/// rejoint le serveur reçoivent en plus les canaux devenus visibles.
+pub async fn sync_local_channel_rooms(
+    io: &SocketIo,
+    hub: &Hub,
+    channel_service: &ChannelService,
+    server_id: i32,
+) {
+    let Some(namespace) = io.of("/") else {
+        return;
+    };
+    let channel_ids = match channel_service.get_channel_ids(server_id).await {
+        Ok(channel_ids) => channel_ids,
+        Err(e) => {
+            error!("Impossible de lister les canaux du serveur {} : {}", server_id, e);
+            return;
+        }
+    };
+    let server_room = format!("server:{}", server_id);
+    let mut rooms: Vec<String> = channel_ids
+        .iter()
+        .map(|channel_id| format!("channel:{}", channel_id))
+        .collect();
+    rooms.push(server_room.clone());
+
+    let sockets = namespace.within(rooms.clone()).sockets().unwrap_or_default();
+
+    // Canaux visibles, calculés une seule fois par utilisateur
+    let mut visible_by_user: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for socket in sockets {
+        let user_info = socket_user(&socket);
+        let visible =
+            visible_channels(&mut visible_by_user, channel_service, server_id, user_info.user_id).await;
+
+        let (joined, hidden): (Vec<i32>, Vec<i32>) = channel_ids
+            .iter()
+            .partition(|channel_id| visible.contains(channel_id));
+        let _ = socket.leave(
+            hidden
+                .iter()
+                .map(|channel_id| format!("channel:{}", channel_id))
+                .collect::<Vec<_>>(),
+        );
+        let in_server = socket
+            .rooms()
+            .is_ok_and(|socket_rooms| socket_rooms.iter().any(|room| *room == server_room));
+        if in_server {
+            let _ = socket.join(
+                joined
+                    .iter()
+                    .map(|channel_id| format!("channel:{}", channel_id))
+                    .collect::<Vec<_>>(),
+            );
+        }
+    }
+
+    // Sessions coupées : leurs rooms seront rejointes à la reprise
+    for (session_id, user_id, session_rooms) in hub.sessions().detached_in(&rooms) {
+        let visible = visible_channels(&mut visible_by_user, channel_service, server_id, user_id).await;
+
+        let (joined, hidden): (Vec<i32>, Vec<i32>) = channel_ids
+            .iter()
+            .partition(|channel_id| visible.contains(channel_id));
+        let joined = if session_rooms.contains(&server_room) { joined } else { Vec::new() };
+        hub.sessions().update_detached_rooms(
+            session_id,
+            &hidden
+                .iter()
+                .map(|channel_id| format!("channel:{}", channel_id))
+                .collect::<Vec<_>>(),
+            &joined
+                .iter()
+                .map(|channel_id| format!("channel:{}", channel_id))
+                .collect::<Vec<_>>(),
+        );
+    }
+}
+
+/// Canaux d'un serveur visibles par un utilisateur, mis en cache dans `visible_by_user`
+async fn visible_channels<'a>(
+    visible_by_user: &'a mut HashMap<i32, HashSet<i32>>,
+    channel_service: &ChannelService,
+    server_id: i32,
+    user_id: i32,
+) -> &'a HashSet<i32> {
+    match visible_by_user.entry(user_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let channels = channel_service
+                .get_server_channels(server_id, user_id)
+                .await
+                .unwrap_or_default();
+            entry.insert(channels.iter().map(|channel| channel.id).collect())
+        }
+    }
+}
+
+/// Retirer tous les sockets des rooms d'un serveur supprimé
+pub async fn close_server_rooms(hub: &Hub, server_id: i32, channel_ids: &[i32]) {
+    hub.close_server(server_id).await;
+
+    let mut rooms: Vec<String> = channel_ids
+        .iter()
+        .map(|channel_id| format!("channel:{}", channel_id))
+        .collect();
+    rooms.push(format!("server:{}", server_id));
+
+    hub.leave_rooms(rooms.clone(), rooms).await;
+}
+
+/// Notifier les sockets de cette instance des conséquences d'un changement d'état du hub
+///
+/// Chaque instance calcule ces événements pour ses propres sockets, qu'un changement
+/// vienne d'elle ou d'une autre instance : `user:connected` / `user:disconnected` et
+/// `presence:update` ne passent donc pas par le bus.
+pub async fn notify_hub_effects(
+    io: &SocketIo,
+    hub: &Hub,
+    presence_service: &PresenceService,
+    effects: HubEffects,
+) {
+    for change in &effects.presences {
+        broadcast_presence(io, hub, presence_service, change).await;
+    }
+
+    for (server_id, user_info) in effects.joined {
+        let event = SocketEvent::UserConnected {
+            server_id,
+            user_id: user_info.user_id,
+            username: user_info.username,
+        };
+        emit_to_other_members(io, hub, server_id, user_info.user_id, event);
+    }
+    for (server_id, user_info) in effects.left {
+        let event = SocketEvent::UserDisconnected {
+            server_id,
+            user_id: user_info.user_id,
+            username: user_info.username,
+        };
+        emit_to_other_members(io, hub, server_id, user_info.user_id, event);
+    }
+}
+
+/// Envoyer un événement aux sockets locaux d'un serveur, sauf à ceux de l'utilisateur concerné
+fn emit_to_other_members(io: &SocketIo, hub: &Hub, server_id: i32, user_id: i32, event: SocketEvent) {
+    hub.sessions().deliver(
+        io,
+        vec![format!("server:{}", server_id)],
+        vec![format!("user:{}", user_id)],
+        None,
+        &event,
+    );
+}
+
+/// Diffuser un changement de présence aux sockets de cette instance
+///
+/// Les sockets de l'utilisateur reçoivent son statut réel ; les serveurs dont il est
+/// membre reçoivent son statut public (hors ligne s'il est invisible), et seulement
+/// si celui-ci a changé.
+pub async fn broadcast_presence(
+    io: &SocketIo,
+    hub: &Hub,
+    presence_service: &PresenceService,
+    change: &PresenceChange,
+) {
+    let user_id = change.current.user_id;
+    let user_room = format!("user:{}", user_id);
+
+    let event = presence_event(&change.current);
+    hub.sessions()
+        .deliver(io, vec![user_room.clone()], Vec::new(), None, &event);
+
+    if !change.is_public() {
+        return;
+    }
+
+    let server_ids = match presence_service.get_server_ids(user_id).await {
+        Ok(server_ids) => server_ids,
+        Err(e) => {
+            error!("Impossible de lister les serveurs de {} : {}", user_id, e);
+            return;
+        }
+    };
+    if server_ids.is_empty() {
+        return;
+    }
+    let rooms: Vec<String> = server_ids
+        .iter()
+        .map(|server_id| format!("server:{}", server_id))
+        .collect();
+
+    let event = presence_event(&change.current.public());
+    hub.sessions().deliver(io, rooms, vec![user_room], None, &event);
+}
+
+/// Événement `presence:update` pour une présence
+fn presence_event(presence: &Presence) -> SocketEvent {
+    SocketEvent::PresenceUpdate {
+        user_id: presence.user_id,
+        status: presence.status,
+        custom_status: presence.custom_status.clone(),
+    }
+}
+
+/// Noter l'activité d'un socket, et diffuser la fin de l'inactivité s'il y a lieu
+async fn touch_presence(
+    socket: &SocketRef,
+    io: &SocketIo,
+    hub: &Hub,
+    presence_service: &PresenceService,
+    user_id: i32,
+) {
+    let effects = presence_service.touch(user_id, &socket.id.to_string()).await;
+    notify_hub_effects(io, hub, presence_service, effects).await;
+}
+
+/// Diffuser un nouveau message aux membres du canal, aux mentionnés et aux clients IRC
+pub async fn broadcast_new_message(
+    hub: &Hub,
+    irc: &IrcGateway,
+    message: &Message,
+    author_username: &str,
+    mentions: &MessageMentions,
+    nonce: Option<String>,
+) {
+    let event = SocketEvent::NewMessage {
+        channel_id: message.channel_id,
+        message_id: message.id,
+        content: message.content.clone(),
+        author_id: message.author_id,
+        author_username: author_username.to_string(),
+        reply_to_id: message.reply_to_id,
+        attachments: message.attachments.clone(),
+        nonce,
+        created_at: message.created_at.to_rfc3339(),
+    };
+
+    // Clients IRC de cette instance ; les autres instances relaient l'événement reçu du bus
+    irc.relay(&event).await;
+
+    hub.emit(format!("channel:{}", message.channel_id), event).await;
+
+    emit_mentions(hub, message, author_username, mentions).await;
+}
+
+/// Notifier chaque utilisateur mentionné dans sa room personnelle
+pub async fn emit_mentions(hub: &Hub, message: &Message, author_username: &str, mentions: &MessageMentions) {
+    for &(user_id, kind) in &mentions.recipients {
+        let event = SocketEvent::MentionNew {
+            server_id: mentions.server_id,
+            channel_id: message.channel_id,
+            thread_id: message.thread_id,
+            message_id: message.id,
+            content: message.content.clone(),
+            author_id: message.author_id,
+            author_username: author_username.to_string(),
+            kind,
+            created_at: message.created_at.to_rfc3339(),
+        };
+
+        hub.emit(format!("user:{}", user_id), event).await;
+    }
+}
+
+/// Signaler une erreur au socket (même format que l'API REST, avec un code)
+pub fn emit_error(socket: &SocketRef, error: &AppError) {
+    let _ = socket.emit("error", serde_json::json!({
+        "error": error.client_message(),
+        "code": error_code(error)
+    }));
+}
+
+/// Code d'erreur transmis aux clients Socket.IO
+fn error_code(error: &AppError) -> &'static str {
+    match error.status_code() {
+        StatusCode::UNAUTHORIZED => "UNAUTHORIZED",
+        StatusCode::FORBIDDEN => "FORBIDDEN",
+        StatusCode::NOT_FOUND => "NOT_FOUND",
+        StatusCode::INTERNAL_SERVER_ERROR => "INTERNAL_ERROR",
+        _ => "BAD_REQUEST",
+    }
+}
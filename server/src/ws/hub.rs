@@ -1,87 +1,938 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, OnceCell, RwLock};
+use tracing::{error, info};
+use uuid::Uuid;
+use crate::models::{Message, Presence, PresenceChange, PresenceSettings, PresenceStatus};
+use crate::ws::bus::{BusMessage, Envelope, EventBus};
+use crate::ws::session::SessionStore;
+use crate::ws::SocketEvent;
+
+/// Durée pendant laquelle un nonce client identifie le même envoi
+const NONCE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Message créé (ou en cours de création) pour un nonce client
+pub type NonceSlot = Arc<OnceCell<Message>>;
+
+/// Sockets de chaque utilisateur ayant rejoint un serveur (user_id -> socket_ids)
+type ServerSockets = HashMap<i32, HashSet<String>>;
+
+/// Nonces récents des envois `send_message` : (user_id, nonce) -> (date du premier envoi, message)
+type RecentNonces = HashMap<(i32, String), (Instant, NonceSlot)>;
 
 /// Informations utilisateur pour le WebSocket
+///
+/// Extension de chaque socket, posée à la connexion une fois le token vérifié.
 #[derive(Clone, Debug)]
 pub struct UserInfo {
     pub user_id: i32,
     pub username: String,
+    /// Session de connexion du token (`sid`), absente des annonces de présence
+    /// et des connexions IRC par mot de passe
+    pub auth_session_id: Option<i32>,
+}
+
+/// Socket d'un utilisateur, sur cette instance ou une autre
+#[derive(Clone, Debug)]
+struct SocketState {
+    /// Instance qui détient le socket
+    node: Uuid,
+    /// Dernière activité (suivie par l'instance qui détient le socket)
+    last_active: Instant,
+    idle: bool,
+}
+
+/// Présence d'un utilisateur connecté, sur l'ensemble de ses sockets
+#[derive(Clone, Debug)]
+struct UserPresence {
+    username: String,
+    settings: PresenceSettings,
+    /// socket_id -> état du socket
+    sockets: HashMap<String, SocketState>,
+}
+
+impl UserPresence {
+    /// Statut effectif : `online` devient `idle` quand tous les sockets sont inactifs
+    fn presence(&self, user_id: i32) -> Presence {
+        let all_idle = self.sockets.values().all(|socket| socket.idle);
+        let status = match self.settings.status {
+            PresenceStatus::Online if all_idle => PresenceStatus::Idle,
+            status => status,
+        };
+
+        Presence {
+            user_id,
+            status,
+            custom_status: self.settings.custom_status.clone(),
+        }
+    }
+}
+
+/// Changement d'état du hub, appliqué par l'instance d'origine puis par les autres
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HubUpdate {
+    /// Socket authentifié (`settings` ne sert qu'au premier socket de l'utilisateur)
+    SocketConnected {
+        socket_id: String,
+        user_id: i32,
+        username: String,
+        settings: PresenceSettings,
+    },
+    SocketActivity {
+        socket_id: String,
+        user_id: i32,
+        idle: bool,
+    },
+    SocketDisconnected {
+        socket_id: String,
+        user_id: i32,
+    },
+    SettingsChanged {
+        user_id: i32,
+        settings: PresenceSettings,
+    },
+    ServerJoined {
+        server_id: i32,
+        user_id: i32,
+        socket_id: String,
+    },
+    ServerLeft {
+        server_id: i32,
+        user_id: i32,
+        socket_id: String,
+    },
+    UserEvicted {
+        server_id: i32,
+        user_id: i32,
+    },
+    ServerClosed {
+        server_id: i32,
+    },
+}
+
+/// Socket d'une instance, tel qu'envoyé dans son état complet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocketSnapshot {
+    pub socket_id: String,
+    pub user_id: i32,
+    pub username: String,
+    pub settings: PresenceSettings,
+    pub idle: bool,
+}
+
+/// État complet des sockets d'une instance, pour une instance qui démarre ou se resynchronise
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeSnapshot {
+    pub sockets: Vec<SocketSnapshot>,
+    /// (server_id, user_id, socket_id)
+    pub servers: Vec<(i32, i32, String)>,
+}
+
+/// Conséquences d'un changement d'état, à notifier aux sockets de l'instance
+#[derive(Debug, Default)]
+pub struct HubEffects {
+    pub presences: Vec<PresenceChange>,
+    /// Premier socket d'un utilisateur sur un serveur (jamais pour un invisible)
+    pub joined: Vec<(i32, UserInfo)>,
+    /// Dernier socket d'un utilisateur parti d'un serveur (jamais pour un invisible)
+    pub left: Vec<(i32, UserInfo)>,
+}
+
+impl HubEffects {
+    fn extend(&mut self, other: HubEffects) {
+        self.presences.extend(other.presences);
+        self.joined.extend(other.joined);
+        self.left.extend(other.left);
+    }
+}
+
+/// Présence et utilisateurs connectés, toutes instances confondues
+#[derive(Default)]
+struct HubState {
+    /// Sockets ayant rejoint chaque serveur
+    /// server_id -> user_id -> socket_ids
+    connected_users: HashMap<i32, ServerSockets>,
+
+    /// Présence des utilisateurs connectés
+    /// user_id -> statut choisi et état de chaque socket
+    presences: HashMap<i32, UserPresence>,
+
+    /// Dernier signe de vie des autres instances
+    nodes: HashMap<Uuid, Instant>,
+}
+
+impl HubState {
+    fn presence(&self, user_id: i32) -> Presence {
+        self.presences
+            .get(&user_id)
+            .map(|presence| presence.presence(user_id))
+            .unwrap_or_else(|| Presence::offline(user_id))
+    }
+
+    /// Utilisateur à annoncer aux membres de ses serveurs (connecté et pas invisible)
+    fn visible_user(&self, user_id: i32) -> Option<UserInfo> {
+        let presence = self.presences.get(&user_id)?;
+        (presence.settings.status != PresenceStatus::Invisible).then(|| UserInfo {
+            user_id,
+            username: presence.username.clone(),
+            auth_session_id: None,
+        })
+    }
+
+    /// Appliquer un changement venu de l'instance `node`
+    fn apply(&mut self, node: Uuid, update: HubUpdate) -> HubEffects {
+        let mut effects = HubEffects::default();
+
+        match update {
+            HubUpdate::SocketConnected { socket_id, user_id, username, settings } => {
+                let previous = self.presence(user_id);
+                let presence = self.presences.entry(user_id).or_insert_with(|| UserPresence {
+                    username,
+                    settings,
+                    sockets: HashMap::new(),
+                });
+                presence.sockets.insert(
+                    socket_id,
+                    SocketState {
+                        node,
+                        last_active: Instant::now(),
+                        idle: false,
+                    },
+                );
+                effects.presences.extend(change(previous, self.presence(user_id)));
+            }
+            HubUpdate::SocketActivity { socket_id, user_id, idle } => {
+                let previous = self.presence(user_id);
+                if let Some(socket) = self
+                    .presences
+                    .get_mut(&user_id)
+                    .and_then(|presence| presence.sockets.get_mut(&socket_id))
+                {
+                    socket.idle = idle;
+                    if !idle {
+                        socket.last_active = Instant::now();
+                    }
+                }
+                effects.presences.extend(change(previous, self.presence(user_id)));
+            }
+            HubUpdate::SocketDisconnected { socket_id, user_id } => {
+                effects = self.remove_socket(user_id, &socket_id);
+            }
+            HubUpdate::SettingsChanged { user_id, settings } => {
+                let previous = self.presence(user_id);
+                if let Some(presence) = self.presences.get_mut(&user_id) {
+                    presence.settings = settings;
+                }
+                effects.presences.extend(change(previous, self.presence(user_id)));
+            }
+            HubUpdate::ServerJoined { server_id, user_id, socket_id } => {
+                let sockets = self
+                    .connected_users
+                    .entry(server_id)
+                    .or_default()
+                    .entry(user_id)
+                    .or_default();
+                let first = sockets.is_empty();
+                sockets.insert(socket_id);
+                if first {
+                    effects.joined.extend(self.visible_user(user_id).map(|info| (server_id, info)));
+                }
+            }
+            HubUpdate::ServerLeft { server_id, user_id, socket_id } => {
+                let Some(server_users) = self.connected_users.get_mut(&server_id) else {
+                    return effects;
+                };
+                let Some(sockets) = server_users.get_mut(&user_id) else {
+                    return effects;
+                };
+                if !sockets.remove(&socket_id) || !sockets.is_empty() {
+                    return effects;
+                }
+                server_users.remove(&user_id);
+                if server_users.is_empty() {
+                    self.connected_users.remove(&server_id);
+                }
+                effects.left.extend(self.visible_user(user_id).map(|info| (server_id, info)));
+            }
+            HubUpdate::UserEvicted { server_id, user_id } => {
+                // Pas de user:disconnected : le départ est annoncé par member:left
+                // ou par l'événement de modération
+                if let Some(server_users) = self.connected_users.get_mut(&server_id) {
+                    server_users.remove(&user_id);
+                    if server_users.is_empty() {
+                        self.connected_users.remove(&server_id);
+                    }
+                }
+            }
+            HubUpdate::ServerClosed { server_id } => {
+                self.connected_users.remove(&server_id);
+            }
+        }
+
+        effects
+    }
+
+    /// Retirer un socket de la présence et des serveurs de son utilisateur
+    fn remove_socket(&mut self, user_id: i32, socket_id: &str) -> HubEffects {
+        let mut effects = HubEffects::default();
+        let visible = self.visible_user(user_id);
+        let previous = self.presence(user_id);
+
+        for (&server_id, server_users) in self.connected_users.iter_mut() {
+            let Some(sockets) = server_users.get_mut(&user_id) else {
+                continue;
+            };
+            if sockets.remove(socket_id) && sockets.is_empty() {
+                server_users.remove(&user_id);
+                effects.left.extend(visible.clone().map(|info| (server_id, info)));
+            }
+        }
+        self.connected_users.retain(|_, server_users| !server_users.is_empty());
+
+        if let Some(presence) = self.presences.get_mut(&user_id) {
+            presence.sockets.remove(socket_id);
+            if presence.sockets.is_empty() {
+                self.presences.remove(&user_id);
+            }
+        }
+
+        effects.presences.extend(change(previous, self.presence(user_id)));
+        effects
+    }
+
+    /// Sockets détenus par une instance : (user_id, socket_id)
+    fn node_sockets(&self, node: Uuid) -> Vec<(i32, String)> {
+        self.presences
+            .iter()
+            .flat_map(|(&user_id, presence)| {
+                presence
+                    .sockets
+                    .iter()
+                    .filter(move |(_, socket)| socket.node == node)
+                    .map(move |(socket_id, _)| (user_id, socket_id.clone()))
+            })
+            .collect()
+    }
 }
 
 /// Hub central pour gérer les connexions WebSocket
 /// Cette structure garde trace de qui est connecté à quel serveur/canal
+///
+/// L'état partagé (présence, utilisateurs connectés) est répliqué sur toutes les
+/// instances via le bus d'événements ; les événements Socket.IO y sont publiés pour
+/// atteindre les sockets de chaque instance.
 #[derive(Clone)]
 pub struct Hub {
-    /// Map des utilisateurs connectés par serveur
-    /// server_id -> Vec<user_id>
-    connected_users: Arc<RwLock<HashMap<i32, Vec<i32>>>>,
+    /// Identifiant de cette instance sur le bus
+    node_id: Uuid,
+    
+    /// Bus d'événements entre instances
+    bus: Arc<dyn EventBus>,
+    
+    /// Présence et utilisateurs connectés, toutes instances confondues
+    state: Arc<RwLock<HubState>>,
+    
+    /// Ordre de publication des changements d'état, identique à l'ordre d'application
+    updates: Arc<Mutex<()>>,
+    
+    /// Nonces récents des envois `send_message`
+    recent_nonces: Arc<Mutex<RecentNonces>>,
     
-    /// Map socket_id -> UserInfo pour l'authentification
-    socket_to_user: Arc<RwLock<HashMap<String, UserInfo>>>,
+    /// Sessions des sockets de cette instance (numérotation et reprise)
+    sessions: Arc<SessionStore>,
 }
 
 impl Hub {
-    pub fn new() -> Self {
+    pub fn new(bus: Arc<dyn EventBus>, sessions: SessionStore) -> Self {
         Self {
-            connected_users: Arc::new(RwLock::new(HashMap::new())),
-            socket_to_user: Arc::new(RwLock::new(HashMap::new())),
+            node_id: Uuid::new_v4(),
+            bus,
+            state: Arc::new(RwLock::new(HubState::default())),
+            updates: Arc::new(Mutex::new(())),
+            recent_nonces: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(sessions),
         }
     }
 
-    /// Authentifier un socket avec les infos utilisateur
-    pub async fn authenticate_socket(&self, socket_id: String, user_id: i32, username: String) {
-        let mut map = self.socket_to_user.write().await;
-        map.insert(socket_id, UserInfo { user_id, username });
+    /// Identifiant de cette instance sur le bus
+    pub fn node_id(&self) -> Uuid {
+        self.node_id
     }
 
-    /// Récupérer les infos utilisateur depuis un socket_id
-    pub async fn get_user_info(&self, socket_id: &str) -> Option<UserInfo> {
-        let map = self.socket_to_user.read().await;
-        map.get(socket_id).cloned()
+    /// Sessions des sockets de cette instance
+    pub fn sessions(&self) -> &SessionStore {
+        &self.sessions
     }
 
-    /// Déconnecter un socket
-    pub async fn disconnect_socket(&self, socket_id: &str) {
-        let mut map = self.socket_to_user.write().await;
-        map.remove(socket_id);
+    /// Publier un message sur le bus (les échecs sont journalisés)
+    pub async fn publish(&self, message: BusMessage) {
+        let envelope = Envelope {
+            origin: self.node_id,
+            message,
+        };
+        if let Err(e) = self.bus.publish(&envelope).await {
+            error!("Échec de publication sur le bus d'événements : {}", e);
+        }
     }
 
-    /// Enregistrer qu'un utilisateur s'est connecté à un serveur
-    pub async fn connect_user(&self, server_id: i32, user_id: i32) {
-        let mut users = self.connected_users.write().await;
-        users
-            .entry(server_id)
-            .or_insert_with(Vec::new)
-            .push(user_id);
+    /// Appliquer un changement d'état ici, puis le transmettre aux autres instances
+    async fn update(&self, update: HubUpdate) -> HubEffects {
+        let _order = self.updates.lock().await;
+        let effects = self.state.write().await.apply(self.node_id, update.clone());
+        self.publish(BusMessage::Hub { update }).await;
+        effects
     }
 
-    /// Enregistrer qu'un utilisateur s'est déconnecté d'un serveur
-    pub async fn disconnect_user(&self, server_id: i32, user_id: i32) {
-        let mut users = self.connected_users.write().await;
-        if let Some(server_users) = users.get_mut(&server_id) {
-            server_users.retain(|&id| id != user_id);
+    /// Envoyer un événement aux sockets d'une room, sur toutes les instances
+    pub async fn emit(&self, room: String, event: SocketEvent) {
+        self.emit_to_rooms(vec![room], event).await;
+    }
+
+    /// Envoyer un événement aux sockets de plusieurs rooms, sur toutes les instances
+    pub async fn emit_to_rooms(&self, rooms: Vec<String>, event: SocketEvent) {
+        self.publish(BusMessage::Emit {
+            rooms,
+            except_socket: None,
+            event,
+        })
+        .await;
+    }
+
+    /// Envoyer un événement aux sockets d'une room, sauf au socket émetteur
+    pub async fn emit_except_socket(&self, room: String, socket_id: &str, event: SocketEvent) {
+        self.publish(BusMessage::Emit {
+            rooms: vec![room],
+            except_socket: Some(socket_id.to_string()),
+            event,
+        })
+        .await;
+    }
+
+    /// Retirer des rooms `rooms` les sockets présents dans `within`, sur toutes les instances
+    pub async fn leave_rooms(&self, within: Vec<String>, rooms: Vec<String>) {
+        self.publish(BusMessage::Leave { within, rooms }).await;
+    }
+
+    /// Fermer les sockets présents dans ces rooms, sur toutes les instances
+    pub async fn disconnect_rooms(&self, rooms: Vec<String>) {
+        self.publish(BusMessage::Disconnect { rooms }).await;
+    }
+
+    /// Fermer les sockets et connexions IRC ouverts avec des sessions révoquées,
+    /// sur toutes les instances
+    pub async fn revoke_sessions(&self, user_id: i32, session_ids: Vec<i32>) {
+        if session_ids.is_empty() {
+            return;
         }
+        self.publish(BusMessage::RevokeSessions { user_id, session_ids, password_logins: false }).await;
     }
 
-    /// Récupérer la liste des utilisateurs connectés à un serveur
-    pub async fn get_connected_users(&self, server_id: i32) -> Vec<i32> {
-        let users = self.connected_users.read().await;
-        users.get(&server_id).cloned().unwrap_or_default()
+    /// Mot de passe changé : comme `revoke_sessions`, et fermer aussi les connexions IRC
+    /// ouvertes avec l'ancien mot de passe (sans session)
+    pub async fn revoke_credentials(&self, user_id: i32, session_ids: Vec<i32>) {
+        self.publish(BusMessage::RevokeSessions { user_id, session_ids, password_logins: true }).await;
+    }
+
+    /// Réaligner les rooms des canaux d'un serveur, sur toutes les instances
+    pub async fn sync_channel_rooms(&self, server_id: i32) {
+        self.publish(BusMessage::SyncChannelRooms { server_id }).await;
+    }
+
+    /// Enregistrer un socket authentifié à la connexion
+    ///
+    /// `settings` (statut choisi, lu en base) ne sert qu'au premier socket ; les
+    /// suivants reprennent le statut en cours.
+    pub async fn connect_socket(
+        &self,
+        socket_id: String,
+        user_id: i32,
+        username: String,
+        settings: PresenceSettings,
+    ) -> HubEffects {
+        self.update(HubUpdate::SocketConnected {
+            socket_id,
+            user_id,
+            username,
+            settings,
+        })
+        .await
     }
 
-    /// Vérifier si un utilisateur est connecté à un serveur
-    pub async fn is_user_connected(&self, server_id: i32, user_id: i32) -> bool {
-        let users = self.connected_users.read().await;
-        users
+    /// Déconnecter un socket (serveurs rejoints et présence)
+    pub async fn disconnect_socket(&self, socket_id: &str, user_id: i32) -> HubEffects {
+        self.update(HubUpdate::SocketDisconnected {
+            socket_id: socket_id.to_string(),
+            user_id,
+        })
+        .await
+    }
+
+    /// Vérifier si un utilisateur a encore au moins un socket authentifié, sur une instance
+    pub async fn has_sockets(&self, user_id: i32) -> bool {
+        self.state.read().await.presences.contains_key(&user_id)
+    }
+
+    /// Enregistrer qu'un socket d'un utilisateur a rejoint un serveur
+    pub async fn connect_user(&self, server_id: i32, user_id: i32, socket_id: &str) -> HubEffects {
+        self.update(HubUpdate::ServerJoined {
+            server_id,
+            user_id,
+            socket_id: socket_id.to_string(),
+        })
+        .await
+    }
+
+    /// Enregistrer qu'un socket a quitté un serveur
+    pub async fn disconnect_user_socket(&self, server_id: i32, user_id: i32, socket_id: &str) -> HubEffects {
+        self.update(HubUpdate::ServerLeft {
+            server_id,
+            user_id,
+            socket_id: socket_id.to_string(),
+        })
+        .await
+    }
+
+    /// Retirer tous les sockets d'un utilisateur d'un serveur
+    pub async fn disconnect_user(&self, server_id: i32, user_id: i32) {
+        self.update(HubUpdate::UserEvicted { server_id, user_id }).await;
+    }
+
+    /// Retirer tous les sockets d'un serveur supprimé
+    pub async fn close_server(&self, server_id: i32) {
+        self.update(HubUpdate::ServerClosed { server_id }).await;
+    }
+
+    /// Utilisateurs ayant rejoint un serveur, sans les invisibles
+    pub async fn get_connected_users(&self, server_id: i32) -> Vec<i32> {
+        let state = self.state.read().await;
+        let mut user_ids: Vec<i32> = state
+            .connected_users
             .get(&server_id)
-            .map(|server_users| server_users.contains(&user_id))
-            .unwrap_or(false)
+            .map(|server_users| {
+                server_users
+                    .keys()
+                    .copied()
+                    .filter(|user_id| {
+                        state
+                            .presences
+                            .get(user_id)
+                            .is_none_or(|presence| presence.settings.status != PresenceStatus::Invisible)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        user_ids.sort_unstable();
+        user_ids
+    }
+
+    /// Noter une activité sur un socket (fin de l'inactivité automatique)
+    pub async fn touch_presence_socket(&self, user_id: i32, socket_id: &str) -> HubEffects {
+        {
+            let mut state = self.state.write().await;
+            let Some(socket) = state
+                .presences
+                .get_mut(&user_id)
+                .and_then(|presence| presence.sockets.get_mut(socket_id))
+            else {
+                return HubEffects::default();
+            };
+            socket.last_active = Instant::now();
+            if !socket.idle {
+                return HubEffects::default();
+            }
+        }
+
+        self.update(HubUpdate::SocketActivity {
+            socket_id: socket_id.to_string(),
+            user_id,
+            idle: false,
+        })
+        .await
+    }
+
+    /// Appliquer un nouveau statut choisi à un utilisateur connecté
+    pub async fn set_presence_settings(&self, user_id: i32, settings: PresenceSettings) -> HubEffects {
+        self.update(HubUpdate::SettingsChanged { user_id, settings }).await
+    }
+
+    /// Passer en inactif les sockets de cette instance sans activité depuis `idle_after`
+    pub async fn mark_idle_sockets(&self, idle_after: Duration) -> HubEffects {
+        let inactive: Vec<(i32, String)> = {
+            let state = self.state.read().await;
+            state
+                .presences
+                .iter()
+                .flat_map(|(&user_id, presence)| {
+                    presence
+                        .sockets
+                        .iter()
+                        .filter(|(_, socket)| {
+                            socket.node == self.node_id
+                                && !socket.idle
+                                && socket.last_active.elapsed() >= idle_after
+                        })
+                        .map(move |(socket_id, _)| (user_id, socket_id.clone()))
+                })
+                .collect()
+        };
+
+        let mut effects = HubEffects::default();
+        for (user_id, socket_id) in inactive {
+            effects.extend(
+                self.update(HubUpdate::SocketActivity {
+                    socket_id,
+                    user_id,
+                    idle: true,
+                })
+                .await,
+            );
+        }
+        effects
+    }
+
+    /// Présence d'un utilisateur, vue par lui-même
+    pub async fn get_presence(&self, user_id: i32) -> Presence {
+        self.state.read().await.presence(user_id)
+    }
+
+    /// Présences visibles des autres (ni hors ligne ni invisibles)
+    pub async fn get_online_presences(&self) -> Vec<Presence> {
+        let state = self.state.read().await;
+        state
+            .presences
+            .iter()
+            .map(|(&user_id, presence)| presence.presence(user_id).public())
+            .filter(|presence| presence.status != PresenceStatus::Offline)
+            .collect()
+    }
+
+    /// Appliquer un changement d'état publié par une autre instance
+    pub async fn apply_remote(&self, origin: Uuid, update: HubUpdate) -> HubEffects {
+        if origin == self.node_id {
+            return HubEffects::default();
+        }
+        self.state.write().await.apply(origin, update)
+    }
+
+    /// Noter un signe de vie d'une autre instance
+    pub async fn node_seen(&self, node: Uuid) {
+        if node != self.node_id {
+            self.state.write().await.nodes.insert(node, Instant::now());
+        }
+    }
+
+    /// Oublier les sockets des instances sans signe de vie depuis `timeout`
+    pub async fn expire_nodes(&self, timeout: Duration) -> HubEffects {
+        let mut state = self.state.write().await;
+        let expired: Vec<Uuid> = state
+            .nodes
+            .iter()
+            .filter(|(_, seen_at)| seen_at.elapsed() >= timeout)
+            .map(|(&node, _)| node)
+            .collect();
+
+        let mut effects = HubEffects::default();
+        for node in expired {
+            state.nodes.remove(&node);
+            for (user_id, socket_id) in state.node_sockets(node) {
+                effects.extend(state.remove_socket(user_id, &socket_id));
+            }
+            info!("Instance {} sans signe de vie : ses sockets sont oubliés", node);
+        }
+        effects
+    }
+
+    /// État complet des sockets de cette instance
+    pub async fn snapshot(&self) -> NodeSnapshot {
+        let state = self.state.read().await;
+        let mut snapshot = NodeSnapshot::default();
+
+        for (&user_id, presence) in &state.presences {
+            for (socket_id, socket) in &presence.sockets {
+                if socket.node == self.node_id {
+                    snapshot.sockets.push(SocketSnapshot {
+                        socket_id: socket_id.clone(),
+                        user_id,
+                        username: presence.username.clone(),
+                        settings: presence.settings.clone(),
+                        idle: socket.idle,
+                    });
+                }
+            }
+        }
+
+        let local: HashSet<&str> = snapshot.sockets.iter().map(|socket| socket.socket_id.as_str()).collect();
+        for (&server_id, server_users) in &state.connected_users {
+            for (&user_id, sockets) in server_users {
+                for socket_id in sockets.iter().filter(|socket_id| local.contains(socket_id.as_str())) {
+                    snapshot.servers.push((server_id, user_id, socket_id.clone()));
+                }
+            }
+        }
+
+        snapshot
+    }
+
+    /// Remplacer les sockets connus d'une autre instance par son état complet
+    ///
+    /// Seuls les changements de présence sont rapportés : les listes d'utilisateurs
+    /// connectés sont à jour, sans annonce des arrivées et départs.
+    pub async fn apply_snapshot(&self, origin: Uuid, snapshot: NodeSnapshot) -> HubEffects {
+        if origin == self.node_id {
+            return HubEffects::default();
+        }
+
+        let mut state = self.state.write().await;
+        let before: HashMap<i32, Presence> = state
+            .presences
+            .keys()
+            .chain(snapshot.sockets.iter().map(|socket| &socket.user_id))
+            .map(|&user_id| (user_id, state.presence(user_id)))
+            .collect();
+
+        for (user_id, socket_id) in state.node_sockets(origin) {
+            state.remove_socket(user_id, &socket_id);
+        }
+        for socket in snapshot.sockets {
+            state.apply(
+                origin,
+                HubUpdate::SocketConnected {
+                    socket_id: socket.socket_id.clone(),
+                    user_id: socket.user_id,
+                    username: socket.username,
+                    settings: socket.settings.clone(),
+                },
+            );
+            state.apply(
+                origin,
+                HubUpdate::SettingsChanged {
+                    user_id: socket.user_id,
+                    settings: socket.settings,
+                },
+            );
+            state.apply(
+                origin,
+                HubUpdate::SocketActivity {
+                    socket_id: socket.socket_id,
+                    user_id: socket.user_id,
+                    idle: socket.idle,
+                },
+            );
+        }
+        for (server_id, user_id, socket_id) in snapshot.servers {
+            state.apply(origin, HubUpdate::ServerJoined { server_id, user_id, socket_id });
+        }
+        state.nodes.insert(origin, Instant::now());
+
+        let mut effects = HubEffects::default();
+        for (user_id, previous) in before {
+            effects.presences.extend(change(previous, state.presence(user_id)));
+        }
+        effects
+    }
+
+    /// Emplacement du message associé à un nonce client
+    ///
+    /// Un même nonce renvoyé par le même utilisateur pendant `NONCE_WINDOW` obtient le
+    /// même emplacement : le message n'est créé qu'une fois, les renvois l'attendent.
+    pub async fn nonce_slot(&self, user_id: i32, nonce: &str) -> NonceSlot {
+        let mut nonces = self.recent_nonces.lock().await;
+        nonces.retain(|_, (seen_at, _)| seen_at.elapsed() < NONCE_WINDOW);
+
+        let (_, slot) = nonces
+            .entry((user_id, nonce.to_string()))
+            .or_insert_with(|| (Instant::now(), NonceSlot::default()));
+        slot.clone()
     }
 }
 
-impl Default for Hub {
-    fn default() -> Self {
-        Self::new()
+/// Changement de présence, s'il y en a un
+fn change(previous: Presence, current: Presence) -> Option<PresenceChange> {
+    (previous != current).then_some(PresenceChange { previous, current })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::bus::{BusEvent, BusReceiver, LocalBus};
+
+    /// Hub sur un bus en mémoire, avec le récepteur de ce qu'il publie
+    fn hub() -> (Hub, BusReceiver) {
+        let (bus, receiver) = LocalBus::new();
+        let sessions = SessionStore::new(10, Duration::from_secs(60));
+        (Hub::new(Arc::new(bus), sessions), receiver)
+    }
+
+    /// Transmettre à `to` les changements d'état publiés par `from`, comme le dispatcher
+    async fn replicate(from: &Hub, receiver: &mut BusReceiver, to: &Hub) -> HubEffects {
+        let mut effects = HubEffects::default();
+        while let Ok(event) = receiver.try_recv() {
+            if let BusEvent::Message(envelope) = event {
+                if let BusMessage::Hub { update } = envelope.message {
+                    effects.extend(to.apply_remote(from.node_id(), update).await);
+                }
+            }
+        }
+        effects
+    }
+
+    fn settings(status: PresenceStatus) -> PresenceSettings {
+        PresenceSettings {
+            status,
+            custom_status: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_connection_and_server_join_are_replicated() {
+        let (a, mut published) = hub();
+        let (b, _) = hub();
+
+        a.connect_socket("s1".to_string(), 1, "alice".to_string(), settings(PresenceStatus::Online)).await;
+        a.connect_user(10, 1, "s1").await;
+        let effects = replicate(&a, &mut published, &b).await;
+
+        assert_eq!(b.get_presence(1).await.status, PresenceStatus::Online);
+        assert!(b.has_sockets(1).await);
+        assert_eq!(b.get_connected_users(10).await, vec![1]);
+        assert_eq!(effects.presences.len(), 1);
+        assert_eq!(effects.presences[0].previous.status, PresenceStatus::Offline);
+        assert!(matches!(effects.joined.as_slice(), [(10, user)] if user.user_id == 1));
+    }
+
+    #[tokio::test]
+    async fn last_socket_disconnect_leaves_servers_and_goes_offline() {
+        let (a, mut published) = hub();
+        let (b, _) = hub();
+
+        for socket_id in ["s1", "s2"] {
+            a.connect_socket(socket_id.to_string(), 1, "alice".to_string(), PresenceSettings::default()).await;
+            a.connect_user(10, 1, socket_id).await;
+        }
+        replicate(&a, &mut published, &b).await;
+
+        // Un socket reste : l'utilisateur est toujours là
+        a.disconnect_socket("s1", 1).await;
+        let effects = replicate(&a, &mut published, &b).await;
+        assert!(effects.left.is_empty() && effects.presences.is_empty());
+        assert_eq!(b.get_connected_users(10).await, vec![1]);
+
+        a.disconnect_socket("s2", 1).await;
+        let effects = replicate(&a, &mut published, &b).await;
+        assert!(matches!(effects.left.as_slice(), [(10, user)] if user.user_id == 1));
+        assert_eq!(b.get_presence(1).await.status, PresenceStatus::Offline);
+        assert!(b.get_connected_users(10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn user_is_idle_only_when_every_socket_is_idle() {
+        let (b, _) = hub();
+        let node = Uuid::new_v4();
+        for socket_id in ["s1", "s2"] {
+            let update = HubUpdate::SocketConnected {
+                socket_id: socket_id.to_string(),
+                user_id: 1,
+                username: "alice".to_string(),
+                settings: PresenceSettings::default(),
+            };
+            b.apply_remote(node, update).await;
+        }
+
+        let idle = |socket_id: &str| HubUpdate::SocketActivity {
+            socket_id: socket_id.to_string(),
+            user_id: 1,
+            idle: true,
+        };
+        assert!(b.apply_remote(node, idle("s1")).await.presences.is_empty());
+        assert_eq!(b.get_presence(1).await.status, PresenceStatus::Online);
+
+        let effects = b.apply_remote(node, idle("s2")).await;
+        assert_eq!(b.get_presence(1).await.status, PresenceStatus::Idle);
+        assert_eq!(effects.presences.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn invisible_users_are_never_announced() {
+        let (a, mut published) = hub();
+        let (b, _) = hub();
+
+        a.connect_socket("s1".to_string(), 1, "alice".to_string(), settings(PresenceStatus::Invisible)).await;
+        a.connect_user(10, 1, "s1").await;
+        let effects = replicate(&a, &mut published, &b).await;
+
+        assert!(effects.joined.is_empty());
+        assert!(effects.presences.iter().all(|change| !change.is_public()));
+        assert!(b.get_connected_users(10).await.is_empty());
+        assert!(b.has_sockets(1).await);
+    }
+
+    #[tokio::test]
+    async fn own_updates_coming_back_from_the_bus_are_ignored() {
+        let (a, _) = hub();
+        let update = HubUpdate::SocketConnected {
+            socket_id: "s1".to_string(),
+            user_id: 1,
+            username: "alice".to_string(),
+            settings: PresenceSettings::default(),
+        };
+
+        let effects = a.apply_remote(a.node_id(), update).await;
+        assert!(effects.presences.is_empty());
+        assert!(!a.has_sockets(1).await);
+    }
+
+    #[tokio::test]
+    async fn snapshot_contains_only_local_sockets_and_replaces_remote_state() {
+        let (a, _) = hub();
+        let (b, _) = hub();
+
+        // Un socket de B ne fait pas partie de l'état de A
+        b.connect_socket("b1".to_string(), 2, "bob".to_string(), PresenceSettings::default()).await;
+
+        for socket_id in ["s1", "s2"] {
+            a.connect_socket(socket_id.to_string(), 1, "alice".to_string(), settings(PresenceStatus::Dnd)).await;
+            a.connect_user(10, 1, socket_id).await;
+        }
+
+        let snapshot = a.snapshot().await;
+        assert_eq!(snapshot.sockets.len(), 2);
+        assert_eq!(snapshot.servers.len(), 2);
+        assert!(b.snapshot().await.sockets.iter().all(|socket| socket.user_id == 2));
+
+        let effects = b.apply_snapshot(a.node_id(), snapshot).await;
+        assert_eq!(b.get_presence(1).await.status, PresenceStatus::Dnd);
+        assert_eq!(b.get_connected_users(10).await, vec![1]);
+        assert_eq!(effects.presences.len(), 1);
+
+        // Un nouvel état complet remplace le précédent : les sockets absents sont oubliés
+        a.disconnect_socket("s1", 1).await;
+        a.disconnect_socket("s2", 1).await;
+        b.apply_snapshot(a.node_id(), a.snapshot().await).await;
+        assert!(!b.has_sockets(1).await);
+        assert!(b.get_connected_users(10).await.is_empty());
+        assert!(b.has_sockets(2).await);
+    }
+
+    #[tokio::test]
+    async fn silent_nodes_expire_with_their_sockets() {
+        let (a, mut published) = hub();
+        let (b, _) = hub();
+
+        a.connect_socket("s1".to_string(), 1, "alice".to_string(), PresenceSettings::default()).await;
+        a.connect_user(10, 1, "s1").await;
+        replicate(&a, &mut published, &b).await;
+        b.node_seen(a.node_id()).await;
+
+        assert!(b.expire_nodes(Duration::from_secs(60)).await.presences.is_empty());
+        assert!(b.has_sockets(1).await);
+
+        let effects = b.expire_nodes(Duration::ZERO).await;
+        assert!(!b.has_sockets(1).await);
+        assert!(b.get_connected_users(10).await.is_empty());
+        assert!(matches!(effects.left.as_slice(), [(10, user)] if user.user_id == 1));
+        assert_eq!(effects.presences.len(), 1);
     }
 }
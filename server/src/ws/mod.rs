@@ -4,7 +4,12 @@
 pub mod hub;
 pub mod events;
 pub mod handlers;
+pub mod bus;
+pub mod dispatcher;
+pub mod session;
 
-pub use hub::{Hub, UserInfo};
+pub use hub::{Hub, HubEffects, UserInfo};
 pub use events::SocketEvent;
 pub use handlers::*;
+pub use dispatcher::Dispatcher;
+pub use session::SessionStore;
@@ -0,0 +1,341 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde_json::Value;
+use socketioxide::extract::SocketRef;
+use socketioxide::SocketIo;
+use uuid::Uuid;
+use crate::ws::SocketEvent;
+
+/// Événement envoyé à une session, conservé pour une reprise
+struct BufferedEvent {
+    seq: u64,
+    name: &'static str,
+    /// Contenu tel qu'envoyé, numéro de séquence compris
+    payload: Value,
+}
+
+/// Session Socket.IO : survit à une courte coupure du socket
+struct Session {
+    user_id: i32,
+    /// Socket actuel ; `None` pendant une coupure
+    socket_id: Option<String>,
+    /// Rooms du socket au moment de la coupure
+    rooms: HashSet<String>,
+    /// Numéro du dernier événement envoyé
+    last_seq: u64,
+    /// Derniers événements envoyés, du plus ancien au plus récent
+    buffer: VecDeque<BufferedEvent>,
+    /// Début de la coupure en cours
+    detached_at: Option<Instant>,
+}
+
+impl Session {
+    /// Numéroter un événement et le conserver ; retourne le contenu numéroté
+    fn stamp(&mut self, buffer_size: usize, name: &'static str, value: &Value) -> Value {
+        self.last_seq += 1;
+
+        let mut payload = value.clone();
+        if let Value::Object(fields) = &mut payload {
+            fields.insert("seq".to_string(), self.last_seq.into());
+        }
+
+        self.buffer.push_back(BufferedEvent {
+            seq: self.last_seq,
+            name,
+            payload: payload.clone(),
+        });
+        while self.buffer.len() > buffer_size {
+            self.buffer.pop_front();
+        }
+
+        payload
+    }
+}
+
+#[derive(Default)]
+struct SessionsState {
+    sessions: HashMap<Uuid, Session>,
+    /// socket_id -> session
+    by_socket: HashMap<String, Uuid>,
+}
+
+/// Résultat d'une reprise de session
+pub enum ResumeOutcome {
+    /// Les événements manqués ont été renvoyés
+    Replayed {
+        rooms: Vec<String>,
+        replayed: usize,
+        /// Ancien socket de la session, encore ouvert (coupure pas encore détectée)
+        previous_socket: Option<String>,
+    },
+    /// Trop d'événements manqués : les rooms sont restaurées, l'état est à recharger
+    BufferExceeded {
+        rooms: Vec<String>,
+        previous_socket: Option<String>,
+    },
+    /// Session inconnue ou expirée : une nouvelle session est ouverte, sans rooms
+    Expired,
+}
+
+/// Sessions des sockets de cette instance, avec numérotation des événements
+///
+/// Chaque `SocketEvent` envoyé à un socket reçoit un numéro de séquence propre à sa
+/// session (champ `seq`) et est conservé dans un tampon borné. Après une coupure, le
+/// client reprend sa session avec le dernier numéro reçu et obtient ce qu'il a manqué.
+pub struct SessionStore {
+    state: Mutex<SessionsState>,
+    /// Nombre d'événements conservés par session
+    buffer_size: usize,
+    /// Durée pendant laquelle une session coupée peut être reprise
+    resume_timeout: Duration,
+}
+
+impl SessionStore {
+    pub fn new(buffer_size: usize, resume_timeout: Duration) -> Self {
+        Self {
+            state: Mutex::new(SessionsState::default()),
+            buffer_size,
+            resume_timeout,
+        }
+    }
+
+    /// Ouvrir une nouvelle session pour un socket authentifié
+    pub fn open(&self, socket_id: &str, user_id: i32) -> Uuid {
+        let mut state = self.state.lock().unwrap();
+        open_session(&mut state, socket_id, user_id)
+    }
+
+    /// Envoyer un événement aux sockets de cette instance présents dans `rooms`
+    ///
+    /// Les sessions coupées qui étaient dans ces rooms le conservent pour leur reprise.
+    pub fn deliver(
+        &self,
+        io: &SocketIo,
+        rooms: Vec<String>,
+        except_rooms: Vec<String>,
+        except_socket: Option<&str>,
+        event: &SocketEvent,
+    ) {
+        let Some(namespace) = io.of("/") else {
+            return;
+        };
+        let name = event.event_name();
+        let value = serde_json::to_value(event).unwrap();
+
+        // Sockets calculés sous le verrou : une reprise ne peut pas s'intercaler
+        let mut state = self.state.lock().unwrap();
+        let sockets = namespace
+            .to(rooms.clone())
+            .except(except_rooms.clone())
+            .sockets()
+            .unwrap_or_default();
+
+        for socket in sockets {
+            let socket_id = socket.id.to_string();
+            if except_socket == Some(socket_id.as_str()) {
+                continue;
+            }
+            let session_id = state.by_socket.get(&socket_id).copied();
+            let payload = match session_id.and_then(|session_id| state.sessions.get_mut(&session_id)) {
+                Some(session) => session.stamp(self.buffer_size, name, &value),
+                None => value.clone(),
+            };
+            socket.emit(name, payload).ok();
+        }
+
+        for session in state.sessions.values_mut() {
+            let targeted = session.socket_id.is_none()
+                && rooms.iter().any(|room| session.rooms.contains(room))
+                && !except_rooms.iter().any(|room| session.rooms.contains(room));
+            if targeted {
+                session.stamp(self.buffer_size, name, &value);
+            }
+        }
+    }
+
+    /// Envoyer un événement à un seul socket
+    pub fn send(&self, socket: &SocketRef, event: &SocketEvent) {
+        let name = event.event_name();
+        let value = serde_json::to_value(event).unwrap();
+
+        let mut state = self.state.lock().unwrap();
+        let session_id = state.by_socket.get(&socket.id.to_string()).copied();
+        let payload = match session_id.and_then(|session_id| state.sessions.get_mut(&session_id)) {
+            Some(session) => session.stamp(self.buffer_size, name, &value),
+            None => value,
+        };
+        socket.emit(name, payload).ok();
+    }
+
+    /// Garder la session d'un socket qui se ferme, avec ses rooms
+    ///
+    /// À appeler avant que le socket ne quitte ses rooms (handler de déconnexion synchrone).
+    pub fn detach(&self, socket: &SocketRef) {
+        let rooms: HashSet<String> = socket
+            .rooms()
+            .unwrap_or_default()
+            .iter()
+            .map(|room| room.to_string())
+            .collect();
+
+        let mut state = self.state.lock().unwrap();
+        let Some(session_id) = state.by_socket.remove(&socket.id.to_string()) else {
+            return;
+        };
+        if let Some(session) = state.sessions.get_mut(&session_id) {
+            session.socket_id = None;
+            session.rooms = rooms;
+            session.detached_at = Some(Instant::now());
+        }
+    }
+
+    /// Reprendre une session sur un nouveau socket ; retourne la session du socket
+    ///
+    /// Le socket rejoint les rooms de la session, puis reçoit les événements numérotés
+    /// après `last_seq` s'ils sont encore tous dans le tampon.
+    pub fn resume(
+        &self,
+        io: &SocketIo,
+        socket: &SocketRef,
+        session_id: Uuid,
+        user_id: i32,
+        last_seq: u64,
+    ) -> (Uuid, ResumeOutcome) {
+        let socket_id = socket.id.to_string();
+        let mut state = self.state.lock().unwrap();
+
+        // Session ouverte à la connexion de ce socket (puis événement `resume`) : remplacée
+        if let Some(current) = state.by_socket.remove(&socket_id) {
+            if current != session_id {
+                state.sessions.remove(&current);
+            }
+        }
+
+        let Some(session) = state
+            .sessions
+            .get_mut(&session_id)
+            .filter(|session| session.user_id == user_id)
+        else {
+            let session_id = open_session(&mut state, &socket_id, user_id);
+            return (session_id, ResumeOutcome::Expired);
+        };
+
+        // Ancien socket encore ouvert : ses rooms actuelles font foi
+        let previous_socket = session.socket_id.take().filter(|previous| *previous != socket_id);
+        let previous_rooms = previous_socket
+            .as_ref()
+            .and_then(|previous| previous.parse().ok())
+            .and_then(|sid| io.get_socket(sid))
+            .and_then(|previous| previous.rooms().ok());
+        let rooms: Vec<String> = match previous_rooms {
+            Some(rooms) => rooms.iter().map(|room| room.to_string()).collect(),
+            None => session.rooms.drain().collect(),
+        };
+
+        session.socket_id = Some(socket_id.clone());
+        session.rooms.clear();
+        session.detached_at = None;
+        let _ = socket.join(rooms.clone());
+
+        let first_seq = session
+            .buffer
+            .front()
+            .map(|event| event.seq)
+            .unwrap_or(session.last_seq + 1);
+        let outcome = if last_seq <= session.last_seq && last_seq + 1 >= first_seq {
+            let missed: Vec<&BufferedEvent> = session
+                .buffer
+                .iter()
+                .filter(|event| event.seq > last_seq)
+                .collect();
+            for event in &missed {
+                socket.emit(event.name, event.payload.clone()).ok();
+            }
+            ResumeOutcome::Replayed {
+                rooms,
+                replayed: missed.len(),
+                previous_socket: previous_socket.clone(),
+            }
+        } else {
+            ResumeOutcome::BufferExceeded {
+                rooms,
+                previous_socket: previous_socket.clone(),
+            }
+        };
+
+        if let Some(previous) = previous_socket {
+            state.by_socket.remove(&previous);
+        }
+        state.by_socket.insert(socket_id, session_id);
+
+        (session_id, outcome)
+    }
+
+    /// Retirer des rooms `rooms` les sessions coupées présentes dans `within`
+    pub fn leave_detached(&self, within: &[String], rooms: &[String]) {
+        let mut state = self.state.lock().unwrap();
+        for session in state.sessions.values_mut() {
+            if session.socket_id.is_none() && within.iter().any(|room| session.rooms.contains(room)) {
+                for room in rooms {
+                    session.rooms.remove(room);
+                }
+            }
+        }
+    }
+
+    /// Sessions coupées présentes dans l'une de ces rooms : (session, utilisateur, rooms)
+    pub fn detached_in(&self, rooms: &[String]) -> Vec<(Uuid, i32, HashSet<String>)> {
+        let state = self.state.lock().unwrap();
+        state
+            .sessions
+            .iter()
+            .filter(|(_, session)| {
+                session.socket_id.is_none() && rooms.iter().any(|room| session.rooms.contains(room))
+            })
+            .map(|(&session_id, session)| (session_id, session.user_id, session.rooms.clone()))
+            .collect()
+    }
+
+    /// Modifier les rooms d'une session coupée
+    pub fn update_detached_rooms(&self, session_id: Uuid, leave: &[String], join: &[String]) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(session) = state
+            .sessions
+            .get_mut(&session_id)
+            .filter(|session| session.socket_id.is_none())
+        {
+            for room in leave {
+                session.rooms.remove(room);
+            }
+            session.rooms.extend(join.iter().cloned());
+        }
+    }
+
+    /// Oublier les sessions coupées depuis plus de `resume_timeout`
+    pub fn expire(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.sessions.retain(|_, session| {
+            session
+                .detached_at
+                .is_none_or(|detached_at| detached_at.elapsed() < self.resume_timeout)
+        });
+    }
+}
+
+fn open_session(state: &mut SessionsState, socket_id: &str, user_id: i32) -> Uuid {
+    let session_id = Uuid::new_v4();
+    state.sessions.insert(
+        session_id,
+        Session {
+            user_id,
+            socket_id: Some(socket_id.to_string()),
+            rooms: HashSet::new(),
+            last_seq: 0,
+            buffer: VecDeque::new(),
+            detached_at: None,
+        },
+    );
+    state.by_socket.insert(socket_id.to_string(), session_id);
+    session_id
+}